@@ -1,11 +1,11 @@
 use aspeng::{
     camera::FlyCamera,
-    entity::Entity,
+    entity::Name,
     input::InputManager,
     mesh::{Instance, Model},
     system::{Query, System},
     texture::TextureBuilder,
-    App, WorldBuilder,
+    App, Plugin, Prefab, World, WorldBuilder,
 };
 
 use std::sync::{Arc, Mutex};
@@ -19,21 +19,81 @@ struct Velocity {
     z: f32,
 }
 
-fn main() {
-    let mut world = WorldBuilder::new().with_frequency(60).build();
+/// Moves the camera shared with the input manager around with WASD and the mouse.
+struct FlyCameraControls;
+
+impl Plugin for FlyCameraControls {
+    fn build(&self, world: &mut World<'_>) {
+        world.add_fixed_system(System::new(
+            vec![
+                std::any::TypeId::of::<InputManager>(),
+                std::any::TypeId::of::<FlyCamera>(),
+            ],
+            |mut query: Query| {
+                let player = query.find_by_name("player").expect("Player not found");
+                let camera_mutex = query
+                    .get::<FlyCamera>(&player)
+                    .expect("Camera not found");
+                let mut camera_guard = camera_mutex.lock().unwrap();
+
+                let camera = camera_guard.downcast_mut::<FlyCamera>().unwrap();
+                let up = camera.up;
+                let right = camera.up.cross(&camera.dir);
+
+                query.all::<InputManager>(|input_managers| {
+                    let input_manager = &input_managers.values().next().unwrap();
+                    let analog_input = input_manager.analog_input;
+
+                    camera.turn(
+                        nalgebra::UnitQuaternion::from_axis_angle(
+                            &nalgebra::Unit::new_normalize(up),
+                            -analog_input.0 * 0.0008,
+                        ) * nalgebra::UnitQuaternion::from_axis_angle(
+                            &nalgebra::Unit::new_normalize(right),
+                            analog_input.1 * 0.0008,
+                        ),
+                    );
+
+                    for key in &input_manager.keys {
+                        if let winit::keyboard::PhysicalKey::Code(code) = key {
+                            let t = nalgebra::Isometry3::new(
+                                match code {
+                                    KeyCode::KeyW => camera.dir,
+                                    KeyCode::KeyS => -1.0 * camera.dir,
+                                    KeyCode::KeyA => -1.0 * (camera.dir).cross(&camera.up),
+                                    KeyCode::KeyD => (camera.dir).cross(&camera.up),
+                                    KeyCode::ShiftLeft => camera.up,
+                                    KeyCode::ControlLeft => -1.0 * camera.up,
+                                    _ => nalgebra::Vector3::zeros(),
+                                }
+                                .try_normalize(0.001)
+                                .unwrap_or(nalgebra::Vector3::zeros())
+                                    * 0.08,
+                                nalgebra::Vector3::new(0.0, 0.0, 0.0),
+                            );
+
+                            camera.eye = t.transform_point(&camera.eye);
+                        }
+                    }
+                });
+            },
+        )
+        .with_name("fly camera"));
+    }
+}
 
-    let balls = std::iter::repeat(0)
-        .take(9)
-        .map(|_| world.new_entity())
-        .collect::<Vec<Entity>>();
+fn main() {
+    let mut world = WorldBuilder::new()
+        .with_frequency(60)
+        .with_plugin(FlyCameraControls)
+        .build();
 
     let sphere_model = Model::from_obj("sphere.obj").with_tex(TextureBuilder::from_image("hello_texture.jpg"));
-    balls.iter().for_each(|ball| {
-        world.add_component(*ball, sphere_model.clone());
-    });
+    let ball = Prefab::new().with(sphere_model.clone());
 
     let input_manager = world.new_entity();
     world.add_component(input_manager, InputManager::new());
+    world.add_component(input_manager, Name::new("player"));
 
     let camera = Arc::new(Mutex::new(FlyCamera {
         eye: nalgebra::Point3::new(2.0, 3.0, 4.0),
@@ -45,10 +105,19 @@ fn main() {
 
     world.share_component(input_manager, camera.clone());
 
-    balls.iter().enumerate().for_each(|(index, ball)| {
+    for index in 0..9 {
+        let mut instance = Instance::new(&sphere_model.mesh);
+        instance.translate(nalgebra::Translation3::from(nalgebra::Vector3::new(
+            3.0 * (index % 3) as f32,
+            3.0 * (index / 3) as f32,
+            0.0,
+        )));
+
+        // every ball shares the model but needs an instance of its own to be drawn separately
+        let entity = world.spawn_prefab(&ball.clone().with(instance));
         if index < 5 {
             world.add_component(
-                *ball,
+                entity,
                 Velocity {
                     x: index as f32,
                     y: index as f32 + 1.0,
@@ -56,71 +125,7 @@ fn main() {
                 },
             )
         }
-
-        world.add_component(*ball, {
-            let mut instance = Instance::new(&sphere_model.mesh);
-            instance.translate(nalgebra::Translation3::from(nalgebra::Vector3::new(
-                3.0 * (index % 3) as f32,
-                3.0 * (index / 3) as f32,
-                0.0,
-            )));
-            instance
-        });
-    });
-
-    world.add_fixed_system(System::new(
-        vec![
-            std::any::TypeId::of::<InputManager>(),
-            std::any::TypeId::of::<FlyCamera>(),
-        ],
-        |mut query: Query| {
-            let camera_mutex = query
-                .get::<FlyCamera>(&query.entities[0])
-                .expect("Camera not found");
-            let mut camera_guard = camera_mutex.lock().unwrap();
-
-            let camera = camera_guard.downcast_mut::<FlyCamera>().unwrap();
-            let up = camera.up;
-            let right = camera.up.cross(&camera.dir);
-
-            query.all::<InputManager>(|input_managers| {
-                let input_manager = &input_managers.values().next().unwrap();
-                let analog_input = input_manager.analog_input;
-
-                camera.turn(
-                    nalgebra::UnitQuaternion::from_axis_angle(
-                        &nalgebra::Unit::new_normalize(up),
-                        -1.0 * analog_input.0 * 0.0008,
-                    ) * nalgebra::UnitQuaternion::from_axis_angle(
-                        &nalgebra::Unit::new_normalize(right),
-                        analog_input.1 * 0.0008,
-                    ),
-                );
-
-                for key in &input_manager.keys {
-                    if let winit::keyboard::PhysicalKey::Code(code) = key {
-                        let t = nalgebra::Isometry3::new(
-                            match code {
-                                KeyCode::KeyW => camera.dir,
-                                KeyCode::KeyS => -1.0 * camera.dir,
-                                KeyCode::KeyA => -1.0 * (camera.dir).cross(&camera.up),
-                                KeyCode::KeyD => (camera.dir).cross(&camera.up),
-                                KeyCode::ShiftLeft => camera.up,
-                                KeyCode::ControlLeft => -1.0 * camera.up,
-                                _ => nalgebra::Vector3::zeros(),
-                            }
-                            .try_normalize(0.001.into())
-                            .unwrap_or(nalgebra::Vector3::zeros())
-                                * 0.08,
-                            nalgebra::Vector3::new(0.0, 0.0, 0.0),
-                        );
-
-                        camera.eye = t.transform_point(&camera.eye);
-                    }
-                }
-            });
-        },
-    ));
+    }
 
     world.add_fixed_system(System::new(
         vec![
@@ -143,7 +148,8 @@ fn main() {
                 }
             });
         },
-    ));
+    )
+    .with_name("move balls"));
 
     let app = App::new(world, camera);
     app.run();
@@ -0,0 +1,50 @@
+use aspeng::{
+    mesh::Instance,
+    scene::Scene,
+    system::{Query, System},
+    App, WorldBuilder,
+};
+
+#[derive(Clone, Debug, Default)]
+struct Velocity {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+aspeng::reflect_struct!(Velocity { x, y, z });
+
+fn main() {
+    let scene = Scene::load("balls.ron").expect("Couldn't load scene");
+    let camera = scene.camera();
+
+    let mut world = WorldBuilder::new()
+        .with_type::<Velocity>("Velocity")
+        .with_scene(scene)
+        .build();
+
+    world.add_fixed_system(System::new(
+        vec![
+            std::any::TypeId::of::<Instance>(),
+            std::any::TypeId::of::<Velocity>(),
+        ],
+        |mut query: Query| {
+            let instances = query.get_all::<Instance>();
+
+            query.all::<Velocity>(|velocities| {
+                for (entity, velocity) in velocities {
+                    let mut instance_guard = instances.get(&entity).unwrap().lock().unwrap();
+                    let instance = instance_guard.downcast_mut::<Instance>().unwrap();
+
+                    instance.translate(nalgebra::Translation3::from(nalgebra::Vector3::new(
+                        velocity.x / 600.0,
+                        velocity.y / 600.0,
+                        velocity.z / 600.0,
+                    )));
+                }
+            });
+        },
+    ));
+
+    App::new(world, camera).run();
+}
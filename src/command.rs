@@ -0,0 +1,234 @@
+use crate::{Entity, World};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+/// A change to the world queued through a [`WorldHandle`].
+pub type Command = Box<dyn FnOnce(&mut World<'_>) + Send>;
+
+/// The error returned when sending a command to a world that has been dropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorldClosed;
+
+impl fmt::Display for WorldClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the world has been dropped")
+    }
+}
+
+impl std::error::Error for WorldClosed {}
+
+/// A cloneable, thread-safe handle through which a running world can be changed from anywhere.
+///
+/// Outside code such as a network listener, a file watcher, or a bridge to hardware can't hold a
+/// reference to the world, which is owned by whatever ticks it. A handle instead queues commands
+/// that are applied in the order they were sent at the start of the next [`World::tick`], before
+/// any system runs, or when [`World::apply_commands`] is called.
+///
+/// Entities spawned through a handle are given their id immediately so that further commands may
+/// refer to them, even though they only join the world once the commands are applied.
+///
+/// ```
+/// # use aspeng::{command::WorldClosed, Entity, WorldBuilder};
+/// # #[derive(Clone)]
+/// # struct Velocity {
+/// #     x: f32,
+/// #     y: f32,
+/// #     z: f32,
+/// # }
+/// # #[derive(Clone)]
+/// # struct Connected {
+/// #     entity: Entity,
+/// # }
+/// # let mut world = WorldBuilder::new().build();
+/// let handle = world.handle();
+/// let connection = std::thread::spawn(move || {
+///     let entity = handle.spawn()?;
+///     handle.insert(entity, Velocity { x: 1.0, y: 0.0, z: 0.0 })?;
+///     handle.send_event(Connected { entity })
+/// });
+/// # connection.join().unwrap().unwrap();
+/// # world.apply_commands();
+/// # Ok::<(), WorldClosed>(())
+/// ```
+///
+/// [`World::tick`]: crate::World::tick()
+/// [`World::apply_commands`]: crate::World::apply_commands()
+#[derive(Clone)]
+pub struct WorldHandle {
+    sender: Sender<Command>,
+    next_id: Arc<AtomicU32>,
+}
+
+impl fmt::Debug for WorldHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorldHandle").finish_non_exhaustive()
+    }
+}
+
+impl WorldHandle {
+    /// Queues an arbitrary change to the world.
+    pub fn run<F>(&self, command: F) -> Result<(), WorldClosed>
+    where
+        F: FnOnce(&mut World<'_>) + Send + 'static,
+    {
+        self.sender.send(Box::new(command)).map_err(|_| WorldClosed)
+    }
+
+    /// Reserves a new entity, which joins the world once the command is applied.
+    pub fn spawn(&self) -> Result<Entity, WorldClosed> {
+        let entity = Entity::new(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.run(move |world| world.entities.push(entity))?;
+        Ok(entity)
+    }
+
+    /// Queues a component to be added to an entity, replacing any component of the same type.
+    ///
+    /// Nothing happens if the entity has been despawned by the time the command is applied.
+    pub fn insert<T: Any + Send>(
+        &self,
+        entity: Entity,
+        component: T,
+    ) -> Result<(), WorldClosed> {
+        self.run(move |world| {
+            if world.contains(entity) {
+                world.add_component(entity, component);
+            }
+        })
+    }
+
+    /// Queues a component to be removed from an entity.
+    pub fn remove<T: Any>(&self, entity: Entity) -> Result<(), WorldClosed> {
+        self.run(move |world| {
+            world.remove_component::<T>(entity);
+        })
+    }
+
+    /// Queues an entity to be despawned.
+    pub fn despawn(&self, entity: Entity) -> Result<(), WorldClosed> {
+        self.run(move |world| {
+            if world.contains(entity) {
+                world.despawn(entity);
+            }
+        })
+    }
+
+    /// Queues a resource to be inserted, replacing any resource of the same type.
+    pub fn insert_resource<T>(&self, resource: T) -> Result<(), WorldClosed>
+    where
+        T: Any + Clone + Send + Sync,
+    {
+        self.run(move |world| world.insert_resource(resource))
+    }
+
+    /// Queues an event to be added to the world's [`Events<E>`] resource.
+    pub fn send_event<E: Any + Clone + Send + Sync>(&self, event: E) -> Result<(), WorldClosed> {
+        self.run(move |world| world.send_event(event))
+    }
+}
+
+/// A queue of events of a particular type, held by the world as a resource.
+///
+/// Events are added with [`World::send_event`] or [`WorldHandle::send_event`] and stay queued
+/// until a system drains them, so each event is handled exactly once even if no system runs in the
+/// tick it arrived in.
+///
+/// ```
+/// # use aspeng::{command::Events, Entity, Query};
+/// # struct Connected {
+/// #     entity: Entity,
+/// # }
+/// fn greet(query: Query) {
+///     if let Some(events) = query.resource::<Events<Connected>>() {
+///         for Connected { entity } in events.lock().unwrap().drain() {
+///             println!("{:?} connected", entity);
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`World::send_event`]: crate::World::send_event()
+#[derive(Clone, Debug)]
+pub struct Events<E> {
+    queue: VecDeque<E>,
+}
+
+impl<E> Default for Events<E> {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl<E> Events<E> {
+    /// Adds an event to the back of the queue.
+    pub fn push(&mut self, event: E) {
+        self.queue.push_back(event);
+    }
+
+    /// Removes every queued event, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = E> + '_ {
+        self.queue.drain(..)
+    }
+
+    /// Returns every queued event without removing them, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.queue.iter()
+    }
+
+    /// Returns the number of queued events.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns whether no events are queued.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl World<'_> {
+    /// Returns a handle through which the world can be changed from other threads.
+    ///
+    /// See [`WorldHandle`] for when the commands sent through it are applied.
+    pub fn handle(&self) -> WorldHandle {
+        WorldHandle {
+            sender: self.command_sender.clone(),
+            next_id: self.current_id.clone(),
+        }
+    }
+
+    /// Applies every command sent through a [`WorldHandle`] that hasn't been applied yet.
+    ///
+    /// Called automatically at the start of every [`tick`]. Does nothing when called by a command
+    /// (e.g. one that steps the world), so that commands are always applied in order.
+    ///
+    /// [`tick`]: Self::tick()
+    pub fn apply_commands(&mut self) {
+        if self.applying_commands {
+            return;
+        }
+
+        self.applying_commands = true;
+        while let Ok(command) = self.commands.try_recv() {
+            command(self);
+        }
+        self.applying_commands = false;
+    }
+
+    /// Adds an event to the world's [`Events<E>`] resource, inserting it if necessary.
+    pub fn send_event<E: Any + Clone + Send + Sync>(&mut self, event: E) {
+        match self.resource::<Events<E>>() {
+            Some(events) => events.lock().unwrap().push(event),
+            None => {
+                let mut events = Events::default();
+                events.push(event);
+                self.insert_resource(events);
+            }
+        }
+    }
+}
@@ -1,33 +1,552 @@
 use crate::Entity;
-use std::any::TypeId;
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+static VERSIONS: AtomicU64 = AtomicU64::new(0);
 
 /// A wrapper for data that associates it with an entity.
 ///
 /// Currently has no use in the publically accessible API although it may eventually gain some use.
 #[derive(Clone, Debug)]
 pub struct Component<T: 'static> {
-    pub(crate) data: HashMap<Entity, T>,
+    pub(crate) data: Storage<T>,
     pub(crate) type_id: std::any::TypeId,
+    pub(crate) cloner: Option<Cloner>,
+    /// Changes whenever the components may have been written to, which allows snapshots to reuse
+    /// the copies they made earlier. Unique across every store of every world.
+    pub(crate) version: u64,
+    /// Whether any component is shared with code outside of the world, which may write to it
+    /// without the store knowing.
+    pub(crate) shared: bool,
 }
 
 impl<T> Component<T> {
     pub(crate) fn new(type_id: TypeId) -> Self {
         Component {
-            data: HashMap::new(),
+            data: Storage::new(StorageType::default()),
             type_id,
+            cloner: None,
+            version: VERSIONS.fetch_add(1, Ordering::Relaxed),
+            shared: false,
         }
     }
 
+    /// Records that the components may have been written to.
+    pub(crate) fn touch(&mut self) {
+        self.version = VERSIONS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn with_cloner(mut self, cloner: Cloner) -> Self {
+        self.cloner = Some(cloner);
+        self
+    }
+
+    pub(crate) fn with_storage(mut self, storage_type: StorageType) -> Self {
+        self.data = Storage::new(storage_type);
+        self
+    }
+
     pub(crate) fn entities(&self) -> Vec<Entity> {
-        self.data.keys().cloned().collect()
+        self.data.keys().collect()
     }
 
-    pub(crate) fn add_entity(&mut self, entity: Entity, component: T) {
-        self.data.insert(entity, component);
+    pub(crate) fn add_entity(&mut self, entity: Entity, archetype: ArchetypeId, component: T) {
+        self.data.insert(entity, archetype, component);
+        self.touch();
     }
 
     pub(crate) fn remove_entity(&mut self, entity: &Entity) {
-        self.data.remove(&entity);
+        self.data.remove(entity);
+        self.touch();
+    }
+}
+
+/// How the components of a particular type are stored by the world.
+///
+/// Chosen per type with [`World::set_storage`].
+///
+/// [`World::set_storage`]: crate::World::set_storage()
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StorageType {
+    /// Components are stored in dense columns split by archetype (the set of table components an
+    /// entity holds), so entities with the same shape are iterated together and queries only
+    /// need to look at the archetypes that match them.
+    ///
+    /// Best for data that is iterated every tick and rarely added or removed (e.g. transforms or
+    /// velocities). Adding or removing a table component moves the entity's other table
+    /// components into the columns of its new archetype.
+    #[default]
+    Table,
+    /// Components are stored in a sparse set indexed directly by entity id.
+    ///
+    /// Best for markers that are frequently added and removed (e.g. `Selected` or `Colliding`)
+    /// since adding or removing them is constant time and never changes the entity's archetype.
+    SparseSet,
+}
+
+/// The data of a single component type, stored according to its [`StorageType`].
+#[derive(Clone, Debug)]
+pub(crate) enum Storage<T> {
+    Table(Table<T>),
+    SparseSet(SparseSet<T>),
+}
+
+impl<T> Storage<T> {
+    pub(crate) fn new(storage_type: StorageType) -> Self {
+        match storage_type {
+            StorageType::Table => Storage::Table(Table {
+                columns: HashMap::new(),
+                rows: HashMap::new(),
+            }),
+            StorageType::SparseSet => Storage::SparseSet(SparseSet {
+                sparse: Vec::new(),
+                entities: Vec::new(),
+                values: Vec::new(),
+            }),
+        }
+    }
+
+    /// Returns the storage as a table if it is one.
+    pub(crate) fn as_table(&self) -> Option<&Table<T>> {
+        match self {
+            Storage::Table(table) => Some(table),
+            Storage::SparseSet(_) => None,
+        }
+    }
+
+    pub(crate) fn storage_type(&self) -> StorageType {
+        match self {
+            Storage::Table(_) => StorageType::Table,
+            Storage::SparseSet(_) => StorageType::SparseSet,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Storage::Table(table) => table.rows.len(),
+            Storage::SparseSet(set) => set.entities.len(),
+        }
+    }
+
+    pub(crate) fn contains_key(&self, entity: &Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub(crate) fn get(&self, entity: &Entity) -> Option<&T> {
+        match self {
+            Storage::Table(table) => {
+                let (archetype, row) = table.rows.get(entity)?;
+                table.columns.get(archetype)?.values.get(*row)
+            }
+            Storage::SparseSet(set) => set.values.get(set.index(entity)?),
+        }
+    }
+
+    pub(crate) fn keys(&self) -> Box<dyn Iterator<Item = Entity> + '_> {
+        match self {
+            Storage::Table(table) => Box::new(
+                table
+                    .columns
+                    .values()
+                    .flat_map(|column| column.entities.iter().copied()),
+            ),
+            Storage::SparseSet(set) => Box::new(set.entities.iter().copied()),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = (&Entity, &T)> + '_> {
+        match self {
+            Storage::Table(table) => Box::new(
+                table
+                    .columns
+                    .values()
+                    .flat_map(|column| column.entities.iter().zip(column.values.iter())),
+            ),
+            Storage::SparseSet(set) => Box::new(set.entities.iter().zip(set.values.iter())),
+        }
+    }
+
+    /// Stores a component for an entity, replacing the one it already holds.
+    ///
+    /// Table storage places the component in the column of the given archetype (which must
+    /// match the archetype the entity's other table components are stored in).
+    pub(crate) fn insert(&mut self, entity: Entity, archetype: ArchetypeId, value: T) {
+        match self {
+            Storage::Table(table) => {
+                if let Some((current, row)) = table.rows.get(&entity) {
+                    if *current == archetype {
+                        table.columns.get_mut(current).unwrap().values[*row] = value;
+                        return;
+                    }
+
+                    table.take(&entity);
+                }
+
+                table.push(entity, archetype, value);
+            }
+            Storage::SparseSet(set) => set.insert(entity, value),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, entity: &Entity) -> Option<T> {
+        match self {
+            Storage::Table(table) => table.take(entity),
+            Storage::SparseSet(set) => set.remove(entity),
+        }
+    }
+
+    /// Moves an entity's component into the column of another archetype.
+    pub(crate) fn relocate(&mut self, entity: &Entity, archetype: ArchetypeId) {
+        if let Storage::Table(table) = self {
+            if table.rows.get(entity).is_some_and(|(current, _)| *current != archetype) {
+                let value = table.take(entity).unwrap();
+                table.push(*entity, archetype, value);
+            }
+        }
+    }
+
+    /// Moves every component into a storage of another type.
+    pub(crate) fn convert(&mut self, storage_type: StorageType, archetypes: &Archetypes) {
+        let mut converted = Storage::new(storage_type);
+        for entity in self.keys().collect::<Vec<_>>() {
+            let value = self.remove(&entity).unwrap();
+            converted.insert(entity, archetypes.of(&entity), value);
+        }
+
+        *self = converted;
+    }
+}
+
+/// Dense columns of components, one per archetype.
+#[derive(Clone, Debug)]
+pub(crate) struct Table<T> {
+    columns: HashMap<ArchetypeId, Column<T>>,
+    rows: HashMap<Entity, (ArchetypeId, usize)>,
+}
+
+#[derive(Clone, Debug)]
+struct Column<T> {
+    entities: Vec<Entity>,
+    values: Vec<T>,
+}
+
+impl<T> Table<T> {
+    /// Returns whether any entity of an archetype is stored in the table.
+    pub(crate) fn has_column(&self, archetype: &ArchetypeId) -> bool {
+        self.columns.contains_key(archetype)
+    }
+
+    /// Returns every archetype with entities stored in the table.
+    pub(crate) fn archetypes(&self) -> impl Iterator<Item = ArchetypeId> + '_ {
+        self.columns.keys().copied()
+    }
+
+    /// Returns the entities of an archetype along with their components, in the same order.
+    pub(crate) fn column(&self, archetype: &ArchetypeId) -> Option<(&[Entity], &[T])> {
+        self.columns
+            .get(archetype)
+            .map(|column| (column.entities.as_slice(), column.values.as_slice()))
+    }
+
+    fn push(&mut self, entity: Entity, archetype: ArchetypeId, value: T) {
+        let column = self.columns.entry(archetype).or_insert_with(|| Column {
+            entities: Vec::new(),
+            values: Vec::new(),
+        });
+
+        column.entities.push(entity);
+        column.values.push(value);
+        self.rows
+            .insert(entity, (archetype, column.entities.len() - 1));
+    }
+
+    fn take(&mut self, entity: &Entity) -> Option<T> {
+        let (archetype, row) = self.rows.remove(entity)?;
+        let column = self.columns.get_mut(&archetype)?;
+
+        column.entities.swap_remove(row);
+        let value = column.values.swap_remove(row);
+        if let Some(moved) = column.entities.get(row) {
+            self.rows.insert(*moved, (archetype, row));
+        }
+        if column.entities.is_empty() {
+            self.columns.remove(&archetype);
+        }
+
+        Some(value)
+    }
+}
+
+/// Densely packed components with a sparse index from entity ids into them.
+#[derive(Clone, Debug)]
+pub(crate) struct SparseSet<T> {
+    sparse: Vec<u32>,
+    entities: Vec<Entity>,
+    values: Vec<T>,
+}
+
+impl<T> SparseSet<T> {
+    const EMPTY: u32 = u32::MAX;
+
+    fn index(&self, entity: &Entity) -> Option<usize> {
+        match self.sparse.get(entity.id() as usize) {
+            Some(&index) if index != Self::EMPTY => Some(index as usize),
+            _ => None,
+        }
+    }
+
+    /// Stores a component for an entity. Components for [`Entity::PLACEHOLDER`] are dropped, since
+    /// indexing them would allocate an index covering every possible entity.
+    fn insert(&mut self, entity: Entity, value: T) {
+        if entity == Entity::PLACEHOLDER {
+            return;
+        }
+
+        if let Some(index) = self.index(&entity) {
+            self.values[index] = value;
+            return;
+        }
+
+        let id = entity.id() as usize;
+        if self.sparse.len() <= id {
+            self.sparse.resize(id + 1, Self::EMPTY);
+        }
+
+        self.sparse[id] = self.entities.len() as u32;
+        self.entities.push(entity);
+        self.values.push(value);
+    }
+
+    fn remove(&mut self, entity: &Entity) -> Option<T> {
+        let index = self.index(entity)?;
+        self.sparse[entity.id() as usize] = Self::EMPTY;
+
+        self.entities.swap_remove(index);
+        let value = self.values.swap_remove(index);
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[moved.id() as usize] = index as u32;
+        }
+
+        Some(value)
+    }
+}
+
+/// Identifies a set of table component types.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct ArchetypeId(usize);
+
+impl ArchetypeId {
+    /// The archetype of entities that hold no table components.
+    pub(crate) const EMPTY: ArchetypeId = ArchetypeId(0);
+}
+
+/// Tracks which archetype every entity belongs to.
+#[derive(Clone, Debug)]
+pub(crate) struct Archetypes {
+    types: Vec<Vec<TypeId>>,
+    ids: HashMap<Vec<TypeId>, ArchetypeId>,
+    members: Vec<HashSet<Entity>>,
+    entities: HashMap<Entity, ArchetypeId>,
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        Self {
+            types: vec![Vec::new()],
+            ids: HashMap::from([(Vec::new(), ArchetypeId::EMPTY)]),
+            members: vec![HashSet::new()],
+            entities: HashMap::new(),
+        }
+    }
+}
+
+impl Archetypes {
+    /// Returns the archetype of an entity.
+    pub(crate) fn of(&self, entity: &Entity) -> ArchetypeId {
+        self.entities.get(entity).copied().unwrap_or_default()
+    }
+
+    /// Returns the archetype holding exactly the given types, creating it if needed.
+    fn get_or_create(&mut self, mut types: Vec<TypeId>) -> ArchetypeId {
+        types.sort();
+        types.dedup();
+
+        if let Some(id) = self.ids.get(&types) {
+            return *id;
+        }
+
+        let id = ArchetypeId(self.types.len());
+        self.types.push(types.clone());
+        self.members.push(HashSet::new());
+        self.ids.insert(types, id);
+        id
+    }
+
+    /// Moves an entity to the archetype of its current types with one added or removed, returning
+    /// the new archetype.
+    pub(crate) fn toggle(&mut self, entity: Entity, type_id: TypeId, present: bool) -> ArchetypeId {
+        let mut types = self.types[self.of(&entity).0].clone();
+        types.retain(|t| *t != type_id);
+        if present {
+            types.push(type_id);
+        }
+
+        let archetype = self.get_or_create(types);
+        self.assign(entity, archetype);
+        archetype
+    }
+
+    /// Moves an entity to the archetype holding exactly the given types.
+    pub(crate) fn set(&mut self, entity: Entity, types: Vec<TypeId>) -> ArchetypeId {
+        let archetype = self.get_or_create(types);
+        self.assign(entity, archetype);
+        archetype
+    }
+
+    fn assign(&mut self, entity: Entity, archetype: ArchetypeId) {
+        let previous = self.of(&entity);
+        self.members[previous.0].remove(&entity);
+        self.members[archetype.0].insert(entity);
+        self.entities.insert(entity, archetype);
+    }
+
+    pub(crate) fn remove(&mut self, entity: &Entity) {
+        if let Some(archetype) = self.entities.remove(entity) {
+            self.members[archetype.0].remove(entity);
+        }
+    }
+
+    /// Returns every entity whose archetype holds at least the given types.
+    pub(crate) fn matching(&self, types: &[TypeId]) -> Vec<Entity> {
+        self.types
+            .iter()
+            .zip(&self.members)
+            .filter(|(held, _)| types.iter().all(|t| held.contains(t)))
+            .flat_map(|(_, members)| members.iter().copied())
+            .collect()
+    }
+}
+
+/// Type-erased functions used to duplicate data whose concrete type is only known when it is first
+/// added to the world.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Cloner {
+    /// Copies the data into an immutable value that can be held by a snapshot.
+    pub(crate) freeze: fn(&dyn Any) -> Arc<dyn Any + Send + Sync>,
+    /// Copies the data into a new value that can be stored by the world.
+    pub(crate) thaw: fn(&dyn Any) -> Arc<Mutex<dyn Any>>,
+    /// Copies the data over existing data of the same type.
+    pub(crate) overwrite: fn(&dyn Any, &mut dyn Any),
+}
+
+impl Cloner {
+    pub(crate) fn of<T: Any + Clone + Send + Sync>() -> Self {
+        Self {
+            freeze: |data| Arc::new(downcast::<T>(data).clone()),
+            thaw: |data| Arc::new(Mutex::new(downcast::<T>(data).clone())),
+            overwrite: |data, target| {
+                *target.downcast_mut::<T>().expect("cloner type mismatch") =
+                    downcast::<T>(data).clone()
+            },
+        }
+    }
+}
+
+fn downcast<T: Any>(data: &dyn Any) -> &T {
+    data.downcast_ref::<T>().expect("cloner type mismatch")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArchetypeId, Archetypes, Storage, StorageType};
+    use crate::Entity;
+    use std::any::TypeId;
+
+    struct Position;
+    struct Velocity;
+
+    fn sorted(mut entities: Vec<Entity>) -> Vec<Entity> {
+        entities.sort_by_key(Entity::id);
+        entities
+    }
+
+    #[test]
+    fn archetypes_are_shared_by_entities_with_the_same_types() {
+        let mut archetypes = Archetypes::default();
+        let (a, b) = (Entity::new(0), Entity::new(1));
+        let (position, velocity) = (TypeId::of::<Position>(), TypeId::of::<Velocity>());
+
+        archetypes.toggle(a, position, true);
+        let moving = archetypes.toggle(a, velocity, true);
+        archetypes.toggle(b, velocity, true);
+        assert_eq!(archetypes.toggle(b, position, true), moving);
+        assert_eq!(archetypes.set(b, vec![velocity, position]), moving);
+        assert_eq!(sorted(archetypes.matching(&[velocity])), [a, b]);
+
+        let still = archetypes.toggle(a, velocity, false);
+        assert_ne!(still, moving);
+        assert_eq!(archetypes.matching(&[velocity]), [b]);
+        assert_eq!(sorted(archetypes.matching(&[position])), [a, b]);
+
+        archetypes.remove(&b);
+        assert_eq!(archetypes.of(&b), ArchetypeId::EMPTY);
+        assert_eq!(archetypes.matching(&[position]), [a]);
+    }
+
+    #[test]
+    fn tables_keep_rows_consistent_across_moves() {
+        let (first, second) = (ArchetypeId(1), ArchetypeId(2));
+        let mut storage = Storage::new(StorageType::Table);
+        for id in 0..3 {
+            storage.insert(Entity::new(id), first, id * 10);
+        }
+
+        storage.relocate(&Entity::new(0), second);
+        assert_eq!(storage.get(&Entity::new(0)), Some(&0));
+        assert_eq!(storage.get(&Entity::new(2)), Some(&20));
+        let (entities, values) = storage.as_table().unwrap().column(&first).unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(values.len(), 2);
+
+        storage.insert(Entity::new(2), second, 21);
+        assert_eq!(storage.remove(&Entity::new(1)), Some(10));
+        assert!(!storage.as_table().unwrap().has_column(&first));
+        assert_eq!(storage.get(&Entity::new(0)), Some(&0));
+        assert_eq!(storage.get(&Entity::new(2)), Some(&21));
+        assert_eq!(storage.len(), 2);
+    }
+
+    #[test]
+    fn sparse_sets_keep_their_index_across_removals() {
+        let mut storage = Storage::new(StorageType::SparseSet);
+        for id in [4, 1, 7] {
+            storage.insert(Entity::new(id), ArchetypeId::EMPTY, id);
+        }
+        storage.insert(Entity::PLACEHOLDER, ArchetypeId::EMPTY, 0);
+
+        assert_eq!(storage.remove(&Entity::new(4)), Some(4));
+        assert_eq!(storage.remove(&Entity::new(4)), None);
+        assert_eq!(storage.get(&Entity::new(7)), Some(&7));
+        assert_eq!(storage.get(&Entity::new(1)), Some(&1));
+        assert!(!storage.contains_key(&Entity::PLACEHOLDER));
+        assert_eq!(storage.len(), 2);
+    }
+
+    #[test]
+    fn converting_storage_keeps_every_component() {
+        let mut archetypes = Archetypes::default();
+        let mut storage = Storage::new(StorageType::SparseSet);
+        for id in 0..3 {
+            let entity = Entity::new(id);
+            let archetype = archetypes.toggle(entity, TypeId::of::<Position>(), true);
+            storage.insert(entity, archetype, id);
+        }
+
+        storage.convert(StorageType::Table, &archetypes);
+        assert_eq!(storage.storage_type(), StorageType::Table);
+        assert_eq!(sorted(storage.keys().collect()), (0..3).map(Entity::new).collect::<Vec<_>>());
+        assert_eq!(storage.get(&Entity::new(1)), Some(&1));
     }
 }
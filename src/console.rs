@@ -0,0 +1,833 @@
+use crate::{
+    entity::Names,
+    prefab::Prefabs,
+    reflect::Value,
+    Entity, World,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::BufRead;
+use std::sync::Arc;
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
+use winit::window::Window;
+
+/// Carries out a command given its arguments, returning the text to show or an error message.
+type Run = Arc<dyn Fn(&mut World<'_>, &[&str]) -> Result<String, String> + Send + Sync>;
+
+/// Suggests values for the next argument of a command given the arguments before it.
+type Complete = Arc<dyn Fn(&World<'_>, &[&str]) -> Vec<String> + Send + Sync>;
+
+/// A command that can be typed into the [`Console`].
+#[derive(Clone)]
+pub struct ConsoleCommand {
+    help: String,
+    run: Run,
+    complete: Option<Complete>,
+}
+
+impl fmt::Debug for ConsoleCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsoleCommand")
+            .field("help", &self.help)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConsoleCommand {
+    /// Creates a command from a line of help and the function carrying it out.
+    ///
+    /// The function is given the world and the arguments typed after the name of the command, and
+    /// returns the text to show (which may be empty) or a message describing what went wrong.
+    pub fn new(
+        help: &str,
+        run: impl Fn(&mut World<'_>, &[&str]) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            help: help.to_string(),
+            run: Arc::new(run),
+            complete: None,
+        }
+    }
+
+    /// Suggests values for the arguments of the command when tab is pressed.
+    ///
+    /// The function is given the arguments typed before the one being completed and returns every
+    /// value that argument may take; the console keeps the ones that start with what has been
+    /// typed so far.
+    pub fn with_completion(
+        mut self,
+        complete: impl Fn(&World<'_>, &[&str]) -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.complete = Some(Arc::new(complete));
+        self
+    }
+
+    /// Returns the line of help describing the command.
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+}
+
+/// An error produced while running a line typed into the [`Console`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsoleError {
+    /// No command is registered under the name.
+    UnknownCommand(String),
+    /// The command was found but failed.
+    Failed(String),
+}
+
+impl fmt::Display for ConsoleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsoleError::UnknownCommand(name) => {
+                write!(f, "unknown command `{}` (try `help`)", name)
+            }
+            ConsoleError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConsoleError {}
+
+/// The commands that can be typed into a developer console, held by the world as a resource.
+///
+/// Lines are run with [`World::run_command`]: the first word names the command and the rest are
+/// its arguments, with double quotes grouping words into a single argument. Every line run is kept
+/// in the history, and [`World::complete_command`] completes the word being typed.
+///
+/// Commands are registered by user code and plugins with [`World::register_command`]. The
+/// following are built in:
+///
+/// | Command | Effect |
+/// |---|---|
+/// | `help [command]` | Lists the commands or describes one. |
+/// | `entities` | Lists every entity with its name and registered components. |
+/// | `spawn <prefab> [x y z]` | Spawns a [registered prefab](World::register_prefab()), optionally at a position. |
+/// | `despawn <entity>` | Despawns an entity. |
+/// | `get <entity> <Component.path>` | Prints a registered component or one of its fields. |
+/// | `set <entity> <Component.path> <value...>` | Writes a registered component or one of its fields. |
+/// | `pause` / `resume` | Pauses or resumes the world. |
+/// | `step [n]` | Runs `n` (by default one) fixed steps, even while paused. |
+/// | `timescale [scale]` | Prints or changes the time scale. |
+///
+/// Entities are referred to by their id (`5` or `entity 5`) or their [`Name`]. Values are typed
+/// in the short-hand of [`Value::parse`], and several values form a list (e.g.
+/// `set ball Instance.translation 0 1 0`).
+///
+/// In an [`App`], the console is opened and closed with the grave key (`` ` ``); while it is open,
+/// typing goes to the console rather than the world. Tab completes the word being typed and the up
+/// and down arrows recall earlier lines. Nothing is drawn over the scene: the line being typed and
+/// the first line of the last output are shown in the title of the window, while the full output
+/// of every command is printed to standard output. Headless programs can read commands from
+/// standard input with [`World::read_commands_from_stdin`].
+///
+/// ```
+/// # use aspeng::{console::ConsoleCommand, WorldBuilder};
+/// # #[derive(Clone)]
+/// # struct Gravity {
+/// #     y: f32,
+/// # }
+/// # let mut world = WorldBuilder::new().build();
+/// world.register_command(
+///     "gravity",
+///     ConsoleCommand::new("gravity <y> - changes the strength of gravity", |world, args| {
+///         let y = args.first().and_then(|y| y.parse().ok()).ok_or("expected a number")?;
+///         world.insert_resource(Gravity { y });
+///         Ok(format!("gravity is now {}", y))
+///     }),
+/// );
+///
+/// assert_eq!(world.run_command("gravity -9.8").unwrap(), "gravity is now -9.8");
+/// ```
+///
+/// [`World::run_command`]: crate::World::run_command()
+/// [`World::complete_command`]: crate::World::complete_command()
+/// [`World::register_command`]: crate::World::register_command()
+/// [`World::read_commands_from_stdin`]: crate::World::read_commands_from_stdin()
+/// [`Name`]: crate::entity::Name
+/// [`App`]: crate::os::App
+#[derive(Clone, Debug)]
+pub struct Console {
+    commands: BTreeMap<String, ConsoleCommand>,
+    history: Vec<String>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Console {
+    /// Creates a console with the built-in commands.
+    pub fn new() -> Self {
+        let mut console = Self::empty();
+        builtins(&mut console);
+        console
+    }
+
+    /// Creates a console without any commands.
+    pub fn empty() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Registers a command under a name, replacing any command of the same name.
+    pub fn register(&mut self, name: &str, command: ConsoleCommand) {
+        self.commands.insert(name.to_string(), command);
+    }
+
+    /// Returns the command registered under a name.
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommand> {
+        self.commands.get(name)
+    }
+
+    /// Returns every command along with its name, ordered by name.
+    pub fn commands(&self) -> impl Iterator<Item = (&str, &ConsoleCommand)> {
+        self.commands.iter().map(|(name, command)| (name.as_str(), command))
+    }
+
+    /// Returns every line that has been run, oldest first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+}
+
+/// Splits a line into words, treating text between double quotes as a single word.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut started = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    tokens.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+
+    if started {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+impl World<'_> {
+    /// Registers a command with the world's [`Console`], replacing any command of the same name.
+    pub fn register_command(&mut self, name: &str, command: ConsoleCommand) {
+        match self.resource::<Console>() {
+            Some(console) => console.lock().unwrap().register(name, command),
+            None => {
+                let mut console = Console::new();
+                console.register(name, command);
+                self.insert_resource(console);
+            }
+        }
+    }
+
+    /// Runs a line as if it had been typed into the [`Console`], returning the text to show.
+    pub fn run_command(&mut self, line: &str) -> Result<String, ConsoleError> {
+        let tokens = tokenize(line);
+        let Some((name, args)) = tokens.split_first() else {
+            return Ok(String::new());
+        };
+
+        let console = match self.resource::<Console>() {
+            Some(console) => console,
+            None => {
+                self.insert_resource(Console::new());
+                self.resource::<Console>().unwrap()
+            }
+        };
+        let command = {
+            let mut console = console.lock().unwrap();
+            console.history.push(line.trim().to_string());
+            console.get(name).cloned()
+        };
+        let command = command.ok_or_else(|| ConsoleError::UnknownCommand(name.clone()))?;
+
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        (command.run)(self, &args).map_err(ConsoleError::Failed)
+    }
+
+    /// Returns every way the last word of a line could be completed, as whole lines.
+    ///
+    /// The first word is completed from the names of the commands, and later ones by the command
+    /// being typed (see [`ConsoleCommand::with_completion`]).
+    pub fn complete_command(&self, line: &str) -> Vec<String> {
+        let partial = if line.ends_with(char::is_whitespace) {
+            ""
+        } else {
+            line.split_whitespace().last().unwrap_or_default()
+        };
+        let head = &line[..line.len() - partial.len()];
+        let before = tokenize(head);
+
+        let Some(console) = self.resource::<Console>() else {
+            return Vec::new();
+        };
+        let candidates = match before.split_first() {
+            None => console
+                .lock()
+                .unwrap()
+                .commands
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            Some((name, args)) => {
+                let complete = console
+                    .lock()
+                    .unwrap()
+                    .get(name)
+                    .and_then(|command| command.complete.clone());
+                let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+                complete
+                    .map(|complete| complete(self, &args))
+                    .unwrap_or_default()
+            }
+        };
+
+        let mut lines = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(partial))
+            .map(|candidate| format!("{}{}", head, candidate))
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+
+    /// Reads lines from standard input on a thread of its own and runs each as a command, printing
+    /// its output to standard output.
+    ///
+    /// Lines are run through the world's [`WorldHandle`](crate::command::WorldHandle), so they
+    /// take effect at the start of the next tick (or step) like any other command.
+    pub fn read_commands_from_stdin(&self) {
+        let handle = self.handle();
+        let spawned = std::thread::Builder::new()
+            .name("console".to_string())
+            .spawn(move || {
+                for line in std::io::stdin().lock().lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+
+                    let sent = handle.run(move |world| match world.run_command(&line) {
+                        Ok(output) if output.is_empty() => {}
+                        Ok(output) => println!("{}", output),
+                        Err(e) => println!("error: {}", e),
+                    });
+                    if sent.is_err() {
+                        break;
+                    }
+                }
+            });
+
+        if let Err(e) = spawned {
+            error!("couldn't start reading commands: {}", e);
+        }
+    }
+}
+
+/// Finds the entity an argument refers to by id (`5` or `entity 5`) or name, returning the
+/// arguments after it.
+fn parse_entity<'s, 'a>(
+    world: &World<'_>,
+    args: &'s [&'a str],
+) -> Result<(Entity, &'s [&'a str]), String> {
+    let (entity, rest) = match args {
+        ["entity", id, rest @ ..] => {
+            let id = id.parse().map_err(|_| format!("`{}` isn't an entity id", id))?;
+            (Entity::new(id), rest)
+        }
+        [first, rest @ ..] => match first.parse() {
+            Ok(id) => (Entity::new(id), rest),
+            Err(_) => (
+                world
+                    .find_by_name(first)
+                    .ok_or_else(|| format!("no entity named `{}`", first))?,
+                rest,
+            ),
+        },
+        [] => return Err("expected an entity".to_string()),
+    };
+
+    if world.contains(entity) {
+        Ok((entity, rest))
+    } else {
+        Err(format!("no entity {}", entity.id()))
+    }
+}
+
+/// Splits `Component.path.to.field` into the name of the component and the path of the field.
+fn parse_path(arg: Option<&&str>) -> Result<(String, String), String> {
+    let arg = arg.ok_or("expected a component")?;
+    let (name, path) = arg.split_once('.').unwrap_or((arg, ""));
+    Ok((name.to_string(), path.to_string()))
+}
+
+/// Suggests the registered components of an entity and their fields once it has been typed.
+fn complete_path(world: &World<'_>, args: &[&str]) -> Vec<String> {
+    let entity = match parse_entity(world, args) {
+        Ok((entity, [])) => entity,
+        _ => return Vec::new(),
+    };
+
+    world
+        .reflect_entity(entity)
+        .into_iter()
+        .flat_map(|(name, value)| {
+            let fields = match value {
+                Value::Map(fields) => fields.into_iter().map(|(field, _)| field).collect(),
+                _ => Vec::new(),
+            };
+            std::iter::once(name.clone())
+                .chain(fields.into_iter().map(move |field| format!("{}.{}", name, field)))
+        })
+        .collect()
+}
+
+fn builtins(console: &mut Console) {
+    console.register(
+        "help",
+        ConsoleCommand::new("help [command] - lists the commands or describes one", |world, args| {
+            let console = world.resource::<Console>().ok_or("the world has no console")?;
+            let console = console.lock().unwrap();
+            match args.first() {
+                Some(name) => console
+                    .get(name)
+                    .map(|command| command.help().to_string())
+                    .ok_or_else(|| format!("unknown command `{}`", name)),
+                None => Ok(console
+                    .commands()
+                    .map(|(_, command)| command.help())
+                    .collect::<Vec<_>>()
+                    .join("\n")),
+            }
+        })
+        .with_completion(|world, args| match args {
+            [] => world
+                .resource::<Console>()
+                .map(|console| console.lock().unwrap().commands.keys().cloned().collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }),
+    );
+
+    console.register(
+        "entities",
+        ConsoleCommand::new("entities - lists every entity", |world, _| {
+            let names = world.resource::<Names>();
+            Ok(world
+                .entities
+                .iter()
+                .map(|&entity| {
+                    let components = world
+                        .reflect_entity(entity)
+                        .into_iter()
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let name = names
+                        .as_ref()
+                        .and_then(|names| names.lock().unwrap().name_of(entity).map(str::to_string));
+                    match name {
+                        Some(name) => format!("{} {}: {}", entity.id(), name, components),
+                        None => format!("{}: {}", entity.id(), components),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }),
+    );
+
+    console.register(
+        "spawn",
+        ConsoleCommand::new(
+            "spawn <prefab> [x y z] - spawns a registered prefab, optionally at a position",
+            |world, args| {
+                let name = args.first().ok_or("expected a prefab")?;
+                let prefab = world
+                    .resource::<Prefabs>()
+                    .and_then(|prefabs| prefabs.lock().unwrap().get(name).cloned())
+                    .ok_or_else(|| format!("no prefab named `{}`", name))?;
+
+                let position = match &args[1..] {
+                    [] => None,
+                    [x, y, z] => Some(
+                        [x, y, z]
+                            .into_iter()
+                            .map(|coordinate| Value::parse(coordinate))
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(|e| e.to_string())?,
+                    ),
+                    _ => return Err("expected a position of three coordinates".to_string()),
+                };
+
+                let entity = world.spawn_prefab(&prefab);
+                if let Some(position) = position {
+                    if let Err(e) =
+                        world.set_reflect(entity, "Instance", "translation", &Value::List(position))
+                    {
+                        world.despawn(entity);
+                        return Err(e.to_string());
+                    }
+                }
+
+                Ok(format!("spawned entity {}", entity.id()))
+            },
+        )
+        .with_completion(|world, args| match args {
+            [] => world
+                .resource::<Prefabs>()
+                .map(|prefabs| prefabs.lock().unwrap().names().map(str::to_string).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }),
+    );
+
+    console.register(
+        "despawn",
+        ConsoleCommand::new("despawn <entity> - despawns an entity", |world, args| {
+            let (entity, _) = parse_entity(world, args)?;
+            world.despawn(entity);
+            Ok(format!("despawned entity {}", entity.id()))
+        }),
+    );
+
+    console.register(
+        "get",
+        ConsoleCommand::new(
+            "get <entity> <Component.path> - prints a component or one of its fields",
+            |world, args| {
+                let (entity, rest) = parse_entity(world, args)?;
+                let (name, path) = parse_path(rest.first())?;
+                world
+                    .get_reflect(entity, &name, &path)
+                    .map(|value| value.to_string())
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .with_completion(complete_path),
+    );
+
+    console.register(
+        "set",
+        ConsoleCommand::new(
+            "set <entity> <Component.path> <value...> - writes a component or one of its fields",
+            |world, args| {
+                let (entity, rest) = parse_entity(world, args)?;
+                let (name, path) = parse_path(rest.first())?;
+                let value = match &rest[1..] {
+                    [] => return Err("expected a value".to_string()),
+                    [value] => Value::parse(value).map_err(|e| e.to_string())?,
+                    values => Value::List(
+                        values
+                            .iter()
+                            .map(|value| Value::parse(value))
+                            .collect::<Result<_, _>>()
+                            .map_err(|e| e.to_string())?,
+                    ),
+                };
+
+                world
+                    .set_reflect(entity, &name, &path, &value)
+                    .map_err(|e| e.to_string())?;
+                Ok(String::new())
+            },
+        )
+        .with_completion(complete_path),
+    );
+
+    console.register(
+        "pause",
+        ConsoleCommand::new("pause - stops time from passing", |world, _| {
+            world.pause();
+            Ok("paused".to_string())
+        }),
+    );
+
+    console.register(
+        "resume",
+        ConsoleCommand::new("resume - lets time pass again", |world, _| {
+            world.resume();
+            Ok("resumed".to_string())
+        }),
+    );
+
+    console.register(
+        "step",
+        ConsoleCommand::new("step [n] - runs n fixed steps, even while paused", |world, args| {
+            let steps = match args.first() {
+                Some(steps) => steps
+                    .parse()
+                    .map_err(|_| format!("`{}` isn't a number of steps", steps))?,
+                None => 1,
+            };
+
+            world.step_n(steps).map_err(|e| e.to_string())?;
+            Ok(format!("ran {} steps", steps))
+        }),
+    );
+
+    console.register(
+        "timescale",
+        ConsoleCommand::new("timescale [scale] - prints or changes the time scale", |world, args| {
+            if let Some(scale) = args.first() {
+                let scale = scale
+                    .parse()
+                    .map_err(|_| format!("`{}` isn't a time scale", scale))?;
+                world.set_time_scale(scale);
+            }
+
+            Ok(format!("time scale is {}", world.time_scale()))
+        }),
+    );
+}
+
+/// The line being typed into the console of an [`App`](crate::os::App).
+#[derive(Debug, Default)]
+pub(crate) struct ConsolePrompt {
+    open: bool,
+    line: String,
+    /// The position in the history of the line being recalled, if any.
+    recalled: Option<usize>,
+    /// The first line of the output of the last command.
+    output: String,
+    /// The title of the window before the console was opened.
+    title: String,
+}
+
+impl ConsolePrompt {
+    /// Handles a key event, returning whether it was meant for the console rather than the world.
+    pub(crate) fn key(&mut self, world: &mut World<'_>, window: &Window, event: &KeyEvent) -> bool {
+        if event.physical_key == PhysicalKey::Code(KeyCode::Backquote) {
+            if event.state == ElementState::Pressed && !event.repeat {
+                self.toggle(window);
+            }
+            return true;
+        }
+
+        // releases still reach the world so that keys held when the console opened don't stick
+        if !self.open || event.state != ElementState::Pressed {
+            return false;
+        }
+
+        match &event.logical_key {
+            Key::Named(NamedKey::Enter) => self.submit(world),
+            Key::Named(NamedKey::Escape) => {
+                self.toggle(window);
+                return true;
+            }
+            Key::Named(NamedKey::Backspace) => {
+                self.line.pop();
+            }
+            Key::Named(NamedKey::Tab) => self.complete(world),
+            Key::Named(NamedKey::ArrowUp) => self.recall(world, true),
+            Key::Named(NamedKey::ArrowDown) => self.recall(world, false),
+            _ => {
+                if let Some(text) = &event.text {
+                    self.line.extend(text.chars().filter(|c| !c.is_control()));
+                }
+            }
+        }
+
+        window.set_title(&format!("> {}_    {}", self.line, self.output));
+        true
+    }
+
+    fn toggle(&mut self, window: &Window) {
+        self.open = !self.open;
+        if self.open {
+            self.title = window.title();
+            window.set_title(&format!("> {}_    {}", self.line, self.output));
+        } else {
+            window.set_title(&self.title);
+        }
+    }
+
+    fn submit(&mut self, world: &mut World<'_>) {
+        let line = std::mem::take(&mut self.line);
+        self.recalled = None;
+
+        let output = match world.run_command(&line) {
+            Ok(output) => output,
+            Err(e) => format!("error: {}", e),
+        };
+        if !output.is_empty() {
+            println!("> {}\n{}", line, output);
+        }
+        self.output = output.lines().next().unwrap_or_default().to_string();
+    }
+
+    fn complete(&mut self, world: &World<'_>) {
+        let candidates = world.complete_command(&self.line);
+        match candidates.as_slice() {
+            [] => {}
+            [only] => self.line = format!("{} ", only),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.as_str(), |common, candidate| {
+                    let length = common
+                        .char_indices()
+                        .zip(candidate.chars())
+                        .take_while(|((_, a), b)| a == b)
+                        .last()
+                        .map_or(0, |((i, a), _)| i + a.len_utf8());
+                    &common[..length]
+                });
+                self.line = common.to_string();
+
+                let head = self.line.len() - self.line.split_whitespace().last().map_or(0, str::len);
+                self.output = candidates
+                    .iter()
+                    .map(|candidate| &candidate[head.min(candidate.len())..])
+                    .collect::<Vec<_>>()
+                    .join("  ");
+            }
+        }
+    }
+
+    fn recall(&mut self, world: &World<'_>, older: bool) {
+        let Some(console) = world.resource::<Console>() else {
+            return;
+        };
+        let console = console.lock().unwrap();
+        let history = console.history();
+        if history.is_empty() {
+            return;
+        }
+
+        let index = match (self.recalled, older) {
+            (None, true) => Some(history.len() - 1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+
+        self.recalled = index;
+        self.line = index.map(|index| history[index].clone()).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, ConsoleCommand, ConsoleError};
+    use crate::{
+        assert_component, testing::Fixture, type_ids, Prefab, Query, System, World, WorldBuilder,
+    };
+
+    #[derive(Clone, Debug, Default)]
+    struct Velocity {
+        x: f32,
+    }
+
+    crate::reflect_struct!(Velocity { x });
+
+    #[derive(Clone, Debug, Default)]
+    struct Steps(u32);
+
+    fn count(query: Query) {
+        query.resource::<Steps>().unwrap().lock().unwrap().0 += 1;
+    }
+
+    fn with_ball<'a>() -> World<'a> {
+        Fixture::from_builder(WorldBuilder::new().with_seed(0).with_type::<Velocity>("Velocity"))
+            .with_entity("ball", Prefab::new().with(Velocity { x: 1.0 }))
+            .with_resource(Steps::default())
+            .with_fixed_system(System::new(type_ids![], count))
+            .build()
+    }
+
+    #[test]
+    fn words_in_quotes_are_kept_together() {
+        assert_eq!(
+            tokenize(r#"set "big ball" Velocity.x  2"#),
+            vec!["set", "big ball", "Velocity.x", "2"],
+        );
+        assert_eq!(tokenize(r#"say """#), vec!["say", ""]);
+    }
+
+    #[test]
+    fn get_and_set_reach_registered_components() {
+        let mut world = with_ball();
+        let ball = world.find_by_name("ball").unwrap();
+
+        assert_eq!(world.run_command("set ball Velocity.x 2.5"), Ok(String::new()));
+        assert_component!(world, ball, Velocity, |v| v.x == 2.5);
+        assert_eq!(world.run_command("get ball Velocity.x"), Ok("2.5".to_string()));
+        assert_eq!(
+            world.run_command(&format!("get {} Velocity.x", ball.id())),
+            Ok("2.5".to_string()),
+        );
+        assert!(matches!(
+            world.run_command("get ball Velocity.y"),
+            Err(ConsoleError::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn spawn_despawn_and_step() {
+        let mut world = with_ball();
+        world.register_prefab("ball", Prefab::new().with(Velocity { x: 3.0 }));
+
+        let spawned = world.run_command("spawn ball").unwrap();
+        let id = spawned.strip_prefix("spawned entity ").unwrap();
+        assert_eq!(world.run_command(&format!("get {} Velocity.x", id)), Ok("3.0".to_string()));
+
+        world.run_command("despawn ball").unwrap();
+        assert_eq!(world.find_by_name("ball"), None);
+
+        world.run_command("pause").unwrap();
+        world.run_command("step 3").unwrap();
+        assert_eq!(world.resource::<Steps>().unwrap().lock().unwrap().0, 3);
+    }
+
+    #[test]
+    fn registered_commands_run_and_are_recorded() {
+        let mut world = with_ball();
+        world.register_command(
+            "double",
+            ConsoleCommand::new("double <n> - doubles a number", |_, args| {
+                let n = args
+                    .first()
+                    .and_then(|n| n.parse::<i32>().ok())
+                    .ok_or("expected a number")?;
+                Ok((n * 2).to_string())
+            }),
+        );
+
+        assert_eq!(world.run_command("double 21"), Ok("42".to_string()));
+        assert_eq!(
+            world.run_command("double"),
+            Err(ConsoleError::Failed("expected a number".to_string()))
+        );
+        assert_eq!(
+            world.run_command("triple 1"),
+            Err(ConsoleError::UnknownCommand("triple".to_string()))
+        );
+        assert_eq!(world.run_command("   "), Ok(String::new()));
+
+        let console = world.resource::<super::Console>().unwrap();
+        let history = console.lock().unwrap().history().to_vec();
+        assert_eq!(history, vec!["double 21", "double", "triple 1"]);
+    }
+}
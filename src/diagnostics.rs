@@ -0,0 +1,326 @@
+use crate::World;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The number of ticks averaged over unless chosen otherwise.
+const DEFAULT_WINDOW: usize = 60;
+
+/// An average over the most recent samples of some measurement.
+#[derive(Clone, Debug)]
+pub struct RollingAverage {
+    samples: VecDeque<f64>,
+    window: usize,
+    sum: f64,
+}
+
+impl RollingAverage {
+    /// Creates an average over the given number of most recent samples.
+    pub fn new(window: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(window),
+            window: window.max(1),
+            sum: 0.0,
+        }
+    }
+
+    /// Adds a sample, forgetting the oldest one if the window is full.
+    pub fn push(&mut self, sample: f64) {
+        if self.samples.len() == self.window {
+            self.sum -= self.samples.pop_front().unwrap_or_default();
+        }
+
+        self.samples.push_back(sample);
+        self.sum += sample;
+    }
+
+    /// Returns the average of the samples in the window, or zero if there are none.
+    pub fn average(&self) -> f64 {
+        if self.samples.is_empty() {
+            0.0
+        } else {
+            // samples are never negative, but the running sum may drift slightly below zero
+            (self.sum / self.samples.len() as f64).max(0.0)
+        }
+    }
+
+    /// Returns the most recent sample, or zero if there are none.
+    pub fn latest(&self) -> f64 {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    /// Returns the largest sample in the window, or zero if there are none.
+    pub fn max(&self) -> f64 {
+        self.samples.iter().copied().fold(0.0, f64::max)
+    }
+
+    fn resize(&mut self, window: usize) {
+        self.window = window.max(1);
+        while self.samples.len() > self.window {
+            self.sum -= self.samples.pop_front().unwrap_or_default();
+        }
+    }
+}
+
+/// The time spent running a single system.
+#[derive(Clone, Debug)]
+pub struct SystemDiagnostics {
+    name: String,
+    duration: RollingAverage,
+    runs: u32,
+    enabled: bool,
+}
+
+impl SystemDiagnostics {
+    fn new(name: String, window: usize) -> Self {
+        Self {
+            name,
+            duration: RollingAverage::new(window),
+            runs: 0,
+            enabled: true,
+        }
+    }
+
+    /// Returns the name the system is reported under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the average time spent running the system per tick.
+    ///
+    /// Fixed systems may run several times in a single tick, in which case every run counts
+    /// towards the time spent in that tick.
+    pub fn average(&self) -> Duration {
+        Duration::from_secs_f64(self.duration.average())
+    }
+
+    /// Returns the time spent running the system in the last tick.
+    pub fn latest(&self) -> Duration {
+        Duration::from_secs_f64(self.duration.latest())
+    }
+
+    /// Returns the longest time spent running the system in a single recent tick.
+    pub fn max(&self) -> Duration {
+        Duration::from_secs_f64(self.duration.max())
+    }
+
+    /// Returns the number of times the system ran in the last tick.
+    pub fn runs(&self) -> u32 {
+        self.runs
+    }
+
+    /// Returns whether the system is still enabled (see [`ErrorPolicy::Disable`] and
+    /// [`World::set_system_enabled`]).
+    ///
+    /// [`ErrorPolicy::Disable`]: crate::system::ErrorPolicy::Disable
+    /// [`World::set_system_enabled`]: crate::World::set_system_enabled()
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Measurements of the performance of the world, held by the world as a resource.
+///
+/// Updated at the end of every [`World::tick`] with the time spent in each system, the number of
+/// fixed steps taken, the time between ticks, and the number of entities and components. Times
+/// are averaged over the most recent ticks so that they are readable while the world runs.
+///
+/// The diagnostics may also be printed periodically by choosing a log interval with
+/// [`set_log_interval`] or [`WorldBuilder::with_diagnostics_logging`].
+///
+/// [`World::tick`]: crate::World::tick()
+/// [`set_log_interval`]: Self::set_log_interval()
+/// [`WorldBuilder::with_diagnostics_logging`]: crate::WorldBuilder::with_diagnostics_logging()
+#[derive(Clone, Debug)]
+pub struct Diagnostics {
+    window: usize,
+    frame_time: RollingAverage,
+    fixed_steps: RollingAverage,
+    systems: Vec<SystemDiagnostics>,
+    /// The number of fixed systems, which come first in `systems`.
+    fixed: usize,
+    ticks: u64,
+    entities: usize,
+    components: usize,
+    component_types: usize,
+    log_interval: Option<Duration>,
+    last_log: Instant,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl Diagnostics {
+    /// Creates diagnostics that average over the given number of most recent ticks.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            frame_time: RollingAverage::new(window),
+            fixed_steps: RollingAverage::new(window),
+            systems: Vec::new(),
+            fixed: 0,
+            ticks: 0,
+            entities: 0,
+            components: 0,
+            component_types: 0,
+            log_interval: None,
+            last_log: Instant::now(),
+        }
+    }
+
+    /// Changes the number of most recent ticks that are averaged over.
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window;
+        self.frame_time.resize(window);
+        self.fixed_steps.resize(window);
+        for system in self.systems.iter_mut() {
+            system.duration.resize(window);
+        }
+    }
+
+    /// Prints the diagnostics every time the given interval has passed, or never if `None`.
+    pub fn set_log_interval(&mut self, interval: Option<Duration>) {
+        self.log_interval = interval;
+        self.last_log = Instant::now();
+    }
+
+    /// Returns the average time between ticks (i.e. the frame time of GUI applications).
+    pub fn frame_time(&self) -> Duration {
+        Duration::from_secs_f64(self.frame_time.average())
+    }
+
+    /// Returns the average number of ticks per second (i.e. the frame rate of GUI applications).
+    pub fn fps(&self) -> f64 {
+        match self.frame_time.average() {
+            average if average > 0.0 => 1.0 / average,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the average number of fixed steps taken per tick.
+    pub fn fixed_steps(&self) -> f64 {
+        self.fixed_steps.average()
+    }
+
+    /// Returns the time spent in every system, fixed systems first, in the order they were
+    /// added.
+    pub fn systems(&self) -> &[SystemDiagnostics] {
+        &self.systems
+    }
+
+    /// Returns the time spent in the system with a particular name.
+    pub fn system(&self, name: &str) -> Option<&SystemDiagnostics> {
+        self.systems.iter().find(|system| system.name == name)
+    }
+
+    /// Returns the number of ticks recorded.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the number of entities in the world.
+    pub fn entity_count(&self) -> usize {
+        self.entities
+    }
+
+    /// Returns the number of components held by all entities in the world.
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    /// Returns the number of distinct component types stored by the world.
+    pub fn component_type_count(&self) -> usize {
+        self.component_types
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:.1} fps ({:.2?} per frame), {:.2} fixed steps per tick, {} entities, {} components \
+             of {} types",
+            self.fps(),
+            self.frame_time(),
+            self.fixed_steps(),
+            self.entities,
+            self.components,
+            self.component_types,
+        )?;
+
+        let mut systems = self.systems.iter().collect::<Vec<_>>();
+        systems.sort_by(|a, b| b.duration.average().total_cmp(&a.duration.average()));
+
+        for system in systems {
+            writeln!(
+                f,
+                "  {:<32} {:>10.2?} avg {:>10.2?} max{}",
+                system.name,
+                system.average(),
+                system.max(),
+                if system.enabled { "" } else { " (disabled)" },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl World<'_> {
+    /// Records the measurements of a tick in the [`Diagnostics`] resource.
+    pub(crate) fn record_diagnostics(&mut self, frame_time: Duration, fixed_steps: u32) {
+        let Some(diagnostics) = self.resource::<Diagnostics>() else {
+            return;
+        };
+        let mut diagnostics = diagnostics.lock().unwrap();
+        let window = diagnostics.window;
+
+        diagnostics.ticks += 1;
+        diagnostics.frame_time.push(frame_time.as_secs_f64());
+        diagnostics.fixed_steps.push(f64::from(fixed_steps));
+        diagnostics.entities = self.entities.len();
+        diagnostics.components = self.components.iter().map(|c| c.data.len()).sum();
+        diagnostics.component_types = self
+            .components
+            .iter()
+            .filter(|c| c.data.len() > 0)
+            .count();
+
+        // systems are never removed and keep their position among systems of their kind, so
+        // only systems added since the last tick need new entries
+        while diagnostics.fixed < self.fixed_systems.len() {
+            let fixed = diagnostics.fixed;
+            let entry = SystemDiagnostics::new(self.fixed_systems[fixed].label.clone(), window);
+            diagnostics.systems.insert(fixed, entry);
+            diagnostics.fixed += 1;
+        }
+        while diagnostics.systems.len() < diagnostics.fixed + self.dependent_systems.len() {
+            let dependent = diagnostics.systems.len() - diagnostics.fixed;
+            let label = self.dependent_systems[dependent].label.clone();
+            diagnostics.systems.push(SystemDiagnostics::new(label, window));
+        }
+
+        let systems = self
+            .fixed_systems
+            .iter_mut()
+            .chain(self.dependent_systems.iter_mut());
+        for (entry, system) in diagnostics.systems.iter_mut().zip(systems) {
+            entry.duration.push(system.elapsed.as_secs_f64());
+            entry.runs = system.runs;
+            entry.enabled = system.enabled;
+
+            system.elapsed = Duration::ZERO;
+            system.runs = 0;
+        }
+
+        if let Some(interval) = diagnostics.log_interval {
+            if diagnostics.last_log.elapsed() >= interval {
+                diagnostics.last_log = Instant::now();
+                info!("{}", diagnostics);
+            }
+        }
+    }
+}
@@ -0,0 +1,493 @@
+use crate::{entity::Name, reflect::TypeRegistry, snapshot::Snapshot, Entity, World};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The contents of a single component in a [`WorldDump`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComponentDump {
+    /// The value of a component registered for reflection, which can be compared field by field.
+    Reflected(crate::reflect::Value),
+    /// The [`Debug`](fmt::Debug) output of a component registered with
+    /// [`World::register_debug_type`].
+    ///
+    /// [`World::register_debug_type`]: crate::World::register_debug_type()
+    Debug(String),
+}
+
+/// A single component of an entity in a [`WorldDump`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DumpedComponent {
+    /// The name the component's type was registered under.
+    pub name: String,
+    /// The type of the component.
+    pub type_id: TypeId,
+    /// The contents of the component.
+    pub value: ComponentDump,
+}
+
+/// A single entity along with its components in a [`WorldDump`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DumpedEntity {
+    /// The entity that was dumped.
+    pub entity: Entity,
+    /// The [`Name`] of the entity, if it had one.
+    pub name: Option<String>,
+    /// Every component of the entity that could be printed, in the order the world stores them.
+    pub components: Vec<DumpedComponent>,
+    /// The number of components held by the entity whose type is registered neither for
+    /// reflection nor with [`World::register_debug_type`], which can't be printed.
+    ///
+    /// [`World::register_debug_type`]: crate::World::register_debug_type()
+    pub hidden: usize,
+}
+
+/// A readable copy of every entity and component in a world, taken with [`World::dump`] or
+/// [`World::dump_snapshot`].
+///
+/// Displaying a dump prints it as a tree of entities, their components, and the fields of those
+/// components. Two dumps can be compared with [`diff`] to find out what changed in between (e.g.
+/// over a single step of a misbehaving simulation).
+///
+/// Only components whose type is registered with the world's [`TypeRegistry`] can be printed.
+/// Types that can't be reflected may be registered with [`World::register_debug_type`] to be
+/// printed with their [`Debug`](fmt::Debug) implementation instead.
+///
+/// [`World::dump`]: crate::World::dump()
+/// [`World::dump_snapshot`]: crate::World::dump_snapshot()
+/// [`World::register_debug_type`]: crate::World::register_debug_type()
+/// [`diff`]: Self::diff()
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorldDump {
+    /// Every entity in the world, ordered by id.
+    pub entities: Vec<DumpedEntity>,
+}
+
+impl WorldDump {
+    /// Returns the dumped entity, if it existed.
+    pub fn get(&self, entity: Entity) -> Option<&DumpedEntity> {
+        self.entities.iter().find(|dumped| dumped.entity == entity)
+    }
+
+    /// Keeps only the components of the given types, along with the entities holding at least one
+    /// of them.
+    pub fn filtered(&self, types: &[TypeId]) -> WorldDump {
+        WorldDump {
+            entities: self
+                .entities
+                .iter()
+                .filter_map(|dumped| {
+                    let components = dumped
+                        .components
+                        .iter()
+                        .filter(|component| types.contains(&component.type_id))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    (!components.is_empty()).then(|| DumpedEntity {
+                        entity: dumped.entity,
+                        name: dumped.name.clone(),
+                        components,
+                        hidden: 0,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Compares this dump with a later one, listing the entities that were spawned or despawned
+    /// and every component and field that changed in between.
+    pub fn diff(&self, later: &WorldDump) -> DumpDiff {
+        let before = self
+            .entities
+            .iter()
+            .map(|dumped| (dumped.entity, dumped))
+            .collect::<HashMap<_, _>>();
+        let after = later
+            .entities
+            .iter()
+            .map(|dumped| (dumped.entity, dumped))
+            .collect::<HashMap<_, _>>();
+
+        let mut diff = DumpDiff {
+            spawned: later
+                .entities
+                .iter()
+                .filter(|dumped| !before.contains_key(&dumped.entity))
+                .map(|dumped| dumped.entity)
+                .collect(),
+            despawned: self
+                .entities
+                .iter()
+                .filter(|dumped| !after.contains_key(&dumped.entity))
+                .map(|dumped| dumped.entity)
+                .collect(),
+            changed: Vec::new(),
+        };
+
+        for dumped in &later.entities {
+            let Some(previous) = before.get(&dumped.entity) else {
+                continue;
+            };
+
+            let mut changes = EntityChanges {
+                entity: dumped.entity,
+                name: dumped.name.clone(),
+                added: Vec::new(),
+                removed: Vec::new(),
+                fields: Vec::new(),
+            };
+
+            for component in &previous.components {
+                match dumped
+                    .components
+                    .iter()
+                    .find(|c| c.type_id == component.type_id)
+                {
+                    Some(current) => diff_component(
+                        &component.name,
+                        &component.value,
+                        &current.value,
+                        &mut changes.fields,
+                    ),
+                    None => changes.removed.push(component.name.clone()),
+                }
+            }
+            changes.added = dumped
+                .components
+                .iter()
+                .filter(|c| !previous.components.iter().any(|p| p.type_id == c.type_id))
+                .map(|c| c.name.clone())
+                .collect();
+
+            if !changes.is_empty() {
+                diff.changed.push(changes);
+            }
+        }
+
+        diff
+    }
+}
+
+impl fmt::Display for WorldDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for dumped in &self.entities {
+            write!(f, "{}", dumped.entity)?;
+            if let Some(name) = &dumped.name {
+                write!(f, " {:?}", name)?;
+            }
+            writeln!(f)?;
+
+            let count = dumped.components.len() + usize::from(dumped.hidden > 0);
+            for (index, component) in dumped.components.iter().enumerate() {
+                let last = index + 1 == count;
+                let (branch, indent) = if last { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+
+                match &component.value {
+                    ComponentDump::Reflected(value) => {
+                        write_value(f, branch, indent, &component.name, value)?
+                    }
+                    ComponentDump::Debug(text) => {
+                        writeln!(f, "{}{}: {}", branch, component.name, text)?
+                    }
+                }
+            }
+
+            if dumped.hidden > 0 {
+                writeln!(f, "└─ ({} unregistered)", dumped.hidden)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a value as a node of the tree, expanding maps into one child per field.
+fn write_value(
+    f: &mut fmt::Formatter<'_>,
+    branch: &str,
+    indent: &str,
+    name: &str,
+    value: &crate::reflect::Value,
+) -> fmt::Result {
+    use crate::reflect::Value;
+
+    match value {
+        Value::Map(fields) if !fields.is_empty() => {
+            writeln!(f, "{}{}", branch, name)?;
+            for (index, (field, value)) in fields.iter().enumerate() {
+                let last = index + 1 == fields.len();
+                write_value(
+                    f,
+                    &format!("{}{}", indent, if last { "└─ " } else { "├─ " }),
+                    &format!("{}{}", indent, if last { "   " } else { "│  " }),
+                    field,
+                    value,
+                )?;
+            }
+            Ok(())
+        }
+        _ => writeln!(f, "{}{}: {}", branch, name, value),
+    }
+}
+
+/// A field that holds a different value in two dumps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// The `.`-separated path to the field, starting with the component's registered name (e.g.
+    /// `Instance.translation.x`). List elements are addressed by their index.
+    pub path: String,
+    /// The value held by the earlier dump.
+    pub before: String,
+    /// The value held by the later dump.
+    pub after: String,
+}
+
+/// The changes made to a single entity between two dumps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntityChanges {
+    /// The entity that changed.
+    pub entity: Entity,
+    /// The [`Name`] of the entity in the later dump, if it had one.
+    pub name: Option<String>,
+    /// The components that were added to the entity.
+    pub added: Vec<String>,
+    /// The components that were removed from the entity.
+    pub removed: Vec<String>,
+    /// Every field of a component that changed.
+    pub fields: Vec<FieldChange>,
+}
+
+impl EntityChanges {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.fields.is_empty()
+    }
+}
+
+/// The differences between two [`WorldDump`]s, produced by [`WorldDump::diff`].
+///
+/// Displaying a diff prints spawned entities with `+`, despawned entities with `-`, and changed
+/// entities with `~` followed by the components and fields that changed.
+///
+/// [`WorldDump::diff`]: WorldDump::diff()
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DumpDiff {
+    /// Entities that only exist in the later dump.
+    pub spawned: Vec<Entity>,
+    /// Entities that only exist in the earlier dump.
+    pub despawned: Vec<Entity>,
+    /// Entities that exist in both dumps but whose components changed.
+    pub changed: Vec<EntityChanges>,
+}
+
+impl DumpDiff {
+    /// Returns whether nothing changed between the two dumps.
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty() && self.despawned.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for DumpDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entity in &self.spawned {
+            writeln!(f, "+ {}", entity)?;
+        }
+        for entity in &self.despawned {
+            writeln!(f, "- {}", entity)?;
+        }
+
+        for changes in &self.changed {
+            write!(f, "~ {}", changes.entity)?;
+            if let Some(name) = &changes.name {
+                write!(f, " {:?}", name)?;
+            }
+            writeln!(f)?;
+
+            for component in &changes.added {
+                writeln!(f, "    + {}", component)?;
+            }
+            for component in &changes.removed {
+                writeln!(f, "    - {}", component)?;
+            }
+            for field in &changes.fields {
+                writeln!(f, "    {}: {} -> {}", field.path, field.before, field.after)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Records every field that differs between two dumps of the same component.
+fn diff_component(
+    name: &str,
+    before: &ComponentDump,
+    after: &ComponentDump,
+    fields: &mut Vec<FieldChange>,
+) {
+    match (before, after) {
+        (ComponentDump::Reflected(before), ComponentDump::Reflected(after)) => {
+            diff_values(name.to_string(), before, after, fields)
+        }
+        (ComponentDump::Debug(before), ComponentDump::Debug(after)) if before == after => {}
+        (before, after) => fields.push(FieldChange {
+            path: name.to_string(),
+            before: describe(before),
+            after: describe(after),
+        }),
+    }
+}
+
+fn describe(dump: &ComponentDump) -> String {
+    match dump {
+        ComponentDump::Reflected(value) => value.to_string(),
+        ComponentDump::Debug(text) => text.clone(),
+    }
+}
+
+/// Records every leaf that differs between two values, descending into maps and lists whose
+/// shape didn't change.
+fn diff_values(
+    path: String,
+    before: &crate::reflect::Value,
+    after: &crate::reflect::Value,
+    fields: &mut Vec<FieldChange>,
+) {
+    use crate::reflect::Value;
+
+    if before == after {
+        return;
+    }
+
+    match (before, after) {
+        (Value::Map(a), Value::Map(b))
+            if a.len() == b.len() && a.iter().zip(b).all(|((x, _), (y, _))| x == y) =>
+        {
+            for ((field, a), (_, b)) in a.iter().zip(b) {
+                diff_values(format!("{}.{}", path, field), a, b, fields);
+            }
+        }
+        (Value::List(a), Value::List(b)) if a.len() == b.len() => {
+            for (index, (a, b)) in a.iter().zip(b).enumerate() {
+                diff_values(format!("{}.{}", path, index), a, b, fields);
+            }
+        }
+        _ => fields.push(FieldChange {
+            path,
+            before: before.to_string(),
+            after: after.to_string(),
+        }),
+    }
+}
+
+/// Dumps a single component if its type is registered in some way.
+fn dump_component(
+    registry: &TypeRegistry,
+    type_id: TypeId,
+    data: &dyn Any,
+) -> Option<DumpedComponent> {
+    if let Some(registration) = registry.get_by_id(type_id) {
+        let value = registration.reflect(data)?.to_value();
+        return Some(DumpedComponent {
+            name: registration.name().to_string(),
+            type_id,
+            value: ComponentDump::Reflected(value),
+        });
+    }
+
+    let (name, debug) = registry.get_debug(type_id)?;
+    Some(DumpedComponent {
+        name: name.clone(),
+        type_id,
+        value: ComponentDump::Debug(debug(data)),
+    })
+}
+
+/// Dumps an entity from every one of its components along with their types.
+fn dump_entity<'d>(
+    registry: &TypeRegistry,
+    entity: Entity,
+    components: impl Iterator<Item = (TypeId, &'d dyn Any)>,
+) -> DumpedEntity {
+    let mut dumped = DumpedEntity {
+        entity,
+        name: None,
+        components: Vec::new(),
+        hidden: 0,
+    };
+
+    for (type_id, data) in components {
+        if let Some(name) = data.downcast_ref::<Name>() {
+            dumped.name = Some(name.0.clone());
+        }
+
+        match dump_component(registry, type_id, data) {
+            Some(component) => dumped.components.push(component),
+            None => dumped.hidden += 1,
+        }
+    }
+
+    dumped
+}
+
+impl World<'_> {
+    /// Captures a readable copy of every entity and its components.
+    ///
+    /// The dump can be printed as a tree, narrowed down to particular component types with
+    /// [`WorldDump::filtered`], or compared with a later dump with [`WorldDump::diff`]:
+    ///
+    /// ```
+    /// # let mut world = aspeng::WorldBuilder::new().build();
+    /// let before = world.dump();
+    /// world.tick()?;
+    /// print!("{}", before.diff(&world.dump()));
+    /// # Ok::<(), aspeng::system::SystemError>(())
+    /// ```
+    ///
+    /// [`WorldDump::filtered`]: crate::dump::WorldDump::filtered()
+    /// [`WorldDump::diff`]: crate::dump::WorldDump::diff()
+    pub fn dump(&self) -> WorldDump {
+        let mut entities = self.entities.clone();
+        entities.sort_by_key(|entity| entity.id());
+
+        WorldDump {
+            entities: entities
+                .into_iter()
+                .map(|entity| {
+                    let guards = self
+                        .components
+                        .iter()
+                        .filter_map(|component| {
+                            let data = component.data.get(&entity)?;
+                            Some((component.type_id, data.lock().unwrap()))
+                        })
+                        .collect::<Vec<_>>();
+
+                    dump_entity(
+                        &self.registry,
+                        entity,
+                        guards.iter().map(|(type_id, data)| (*type_id, &**data)),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Captures a readable copy of every entity and its components as they were when a snapshot
+    /// was taken.
+    ///
+    /// Allows the state of the world at two points in time to be compared without restoring
+    /// either of them, e.g. `world.dump_snapshot(&a).diff(&world.dump_snapshot(&b))`. Types are
+    /// looked up in the current registry of the world.
+    pub fn dump_snapshot(&self, snapshot: &Snapshot) -> WorldDump {
+        let mut entities = snapshot.entities().to_vec();
+        entities.sort_by_key(|entity| entity.id());
+
+        WorldDump {
+            entities: entities
+                .into_iter()
+                .map(|entity| dump_entity(&self.registry, entity, snapshot.captured(entity)))
+                .collect(),
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use crate::{component::StorageType, World};
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+
 /// An entity in the ECS architecture
 ///
 /// A newtype around a [`u32`] that represents the id for an entity. This id is used to index
@@ -5,8 +9,296 @@
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 pub struct Entity(u32);
 
+impl Default for Entity {
+    /// Returns [`Entity::PLACEHOLDER`].
+    fn default() -> Self {
+        Entity::PLACEHOLDER
+    }
+}
+
 impl Entity {
+    /// An entity that is never handed out by a world.
+    ///
+    /// Used as the default value for components that refer to other entities before they are
+    /// given a real one (e.g. when they are created through reflection). Components added to it
+    /// are dropped.
+    pub const PLACEHOLDER: Entity = Entity(u32::MAX);
+
     pub(crate) fn new(id: u32) -> Self {
         Entity(id)
     }
+
+    /// Returns the raw id of the entity.
+    pub fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A human-readable name for an entity.
+///
+/// Names are indexed by the world, allowing entities to be looked up with [`World::find_by_name`]
+/// or [`Query::find_by_name`] rather than relying on the order in which they were spawned. If
+/// several entities share a name, the one that was named most recently is found.
+///
+/// The index is kept up to date when a name is added, replaced, removed, or written through
+/// [`World::set_reflect`]. Changing a name in place from a system bypasses it, so replace the
+/// component instead.
+///
+/// [`World::find_by_name`]: crate::World::find_by_name()
+/// [`Query::find_by_name`]: crate::system::Query::find_by_name()
+/// [`World::set_reflect`]: crate::World::set_reflect()
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Name(pub String);
+
+impl Name {
+    /// Creates a new name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Name(name.into())
+    }
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The index of every named entity, held by the world as a resource.
+#[derive(Clone, Debug, Default)]
+pub struct Names {
+    /// The entities holding each name in the order they were named.
+    by_name: HashMap<String, Vec<Entity>>,
+    by_entity: HashMap<Entity, String>,
+}
+
+impl Names {
+    /// Returns the entity with a particular name.
+    ///
+    /// If several entities share the name, the one that was named most recently is returned.
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.by_name
+            .get(name)
+            .and_then(|entities| entities.last())
+            .copied()
+    }
+
+    /// Returns every entity with a particular name in the order they were named.
+    pub fn get_all(&self, name: &str) -> &[Entity] {
+        self.by_name.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns the name of a particular entity.
+    pub fn name_of(&self, entity: Entity) -> Option<&str> {
+        self.by_entity.get(&entity).map(String::as_str)
+    }
+
+    fn insert(&mut self, entity: Entity, name: &str) {
+        self.by_name.entry(name.to_string()).or_default().push(entity);
+        self.by_entity.insert(entity, name.to_string());
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        if let Some(name) = self.by_entity.remove(&entity) {
+            if let Some(entities) = self.by_name.get_mut(&name) {
+                entities.retain(|e| *e != entity);
+
+                if entities.is_empty() {
+                    self.by_name.remove(&name);
+                }
+            }
+        }
+    }
+}
+
+/// The index of every entity holding a tag, held by the world as a resource.
+///
+/// Tags are zero-sized components added with [`World::add_tag`] that mark entities (e.g. as the
+/// player) without holding any data.
+///
+/// [`World::add_tag`]: crate::World::add_tag()
+#[derive(Clone, Debug, Default)]
+pub struct Tags {
+    tagged: HashMap<TypeId, HashSet<Entity>>,
+}
+
+impl Tags {
+    /// Returns every entity holding a particular tag.
+    pub fn get<T: 'static>(&self) -> Vec<Entity> {
+        self.tagged
+            .get(&TypeId::of::<T>())
+            .map(|entities| entities.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns whether an entity holds a particular tag.
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.tagged
+            .get(&TypeId::of::<T>())
+            .is_some_and(|entities| entities.contains(&entity))
+    }
+}
+
+impl World<'_> {
+    pub(crate) fn index_names(&mut self) {
+        self.insert_resource(Names::default());
+
+        self.on_insert::<Name>(|world, entity| {
+            let name = world.with_component(entity, |name: &Name| name.0.clone());
+            if let (Some(name), Some(names)) = (name, world.resource::<Names>()) {
+                let mut names = names.lock().unwrap();
+                names.remove(entity);
+                names.insert(entity, &name);
+            }
+        });
+        self.on_remove::<Name>(|world, entity| {
+            if let Some(names) = world.resource::<Names>() {
+                names.lock().unwrap().remove(entity);
+            }
+        });
+    }
+
+    pub(crate) fn index_tags(&mut self) {
+        self.insert_resource(Tags::default());
+    }
+
+    /// Returns the entity with a particular [`Name`].
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        self.resource::<Names>()
+            .and_then(|names| names.lock().unwrap().get(name))
+    }
+
+    /// Describes an entity by its [`Name`] if it has one or by its id otherwise.
+    ///
+    /// Intended for debug output.
+    pub fn label(&self, entity: Entity) -> String {
+        self.resource::<Names>()
+            .and_then(|names| names.lock().unwrap().name_of(entity).map(str::to_string))
+            .unwrap_or_else(|| entity.to_string())
+    }
+
+    /// Marks an entity with a zero-sized tag component.
+    ///
+    /// Tags are indexed so that every entity holding a tag can be found through
+    /// [`tagged`] or [`Query::tagged`] without searching the world. They can be removed with
+    /// [`remove_component`] like any other component. Unless chosen otherwise with
+    /// [`set_storage`], tags are stored in sparse sets so that adding and removing them is cheap.
+    ///
+    /// [`tagged`]: Self::tagged()
+    /// [`Query::tagged`]: crate::system::Query::tagged()
+    /// [`remove_component`]: Self::remove_component()
+    /// [`set_storage`]: Self::set_storage()
+    pub fn add_tag<T: Any + Clone + Default + Send + Sync>(&mut self, entity: Entity) {
+        debug_assert_eq!(std::mem::size_of::<T>(), 0, "tags must be zero-sized");
+
+        index_tag::<T>(self);
+        self.register_clone::<T>();
+        self.add_component(entity, T::default());
+    }
+
+    /// Returns every entity holding a particular tag.
+    pub fn tagged<T: 'static>(&self) -> Vec<Entity> {
+        self.resource::<Tags>()
+            .map(|tags| tags.lock().unwrap().get::<T>())
+            .unwrap_or_default()
+    }
+}
+
+/// Starts indexing a tag type unless it already is.
+///
+/// Entities that already hold the tag (e.g. because it was added with
+/// [`World::add_component`]) are indexed right away.
+pub(crate) fn index_tag<T: 'static>(world: &mut World<'_>) {
+    let type_id = TypeId::of::<T>();
+    let Some(tags) = world.resource::<Tags>() else {
+        return;
+    };
+
+    if tags.lock().unwrap().tagged.contains_key(&type_id) {
+        return;
+    }
+
+    let tagged = match world.components.iter().find(|c| c.type_id == type_id) {
+        Some(component) => component.entities().into_iter().collect(),
+        None => {
+            world.set_storage::<T>(StorageType::SparseSet);
+            HashSet::new()
+        }
+    };
+    tags.lock().unwrap().tagged.insert(type_id, tagged);
+
+    world.on_add::<T>(|world, entity| {
+        if let Some(tags) = world.resource::<Tags>() {
+            if let Some(tagged) = tags.lock().unwrap().tagged.get_mut(&TypeId::of::<T>()) {
+                tagged.insert(entity);
+            }
+        }
+    });
+    world.on_remove::<T>(|world, entity| {
+        if let Some(tags) = world.resource::<Tags>() {
+            if let Some(tagged) = tags.lock().unwrap().tagged.get_mut(&TypeId::of::<T>()) {
+                tagged.remove(&entity);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Name;
+    use crate::{reflect::Value, WorldBuilder};
+
+    #[derive(Clone, Debug, Default)]
+    struct Player;
+
+    #[test]
+    fn names_follow_renames_and_despawns() {
+        let mut world = WorldBuilder::new().build();
+        let first = world.new_entity();
+        let second = world.new_entity();
+        world.add_component(first, Name::new("goblin"));
+        world.add_component(second, Name::new("goblin"));
+        assert_eq!(world.find_by_name("goblin"), Some(second));
+
+        world.add_component(second, Name::new("chief"));
+        assert_eq!(world.find_by_name("goblin"), Some(first));
+        assert_eq!(world.find_by_name("chief"), Some(second));
+
+        world
+            .set_reflect(first, "Name", "", &Value::String("scout".into()))
+            .unwrap();
+        assert_eq!(world.find_by_name("goblin"), None);
+        assert_eq!(world.label(first), "scout");
+
+        world.despawn(second);
+        assert_eq!(world.find_by_name("chief"), None);
+        assert_eq!(world.label(second), second.to_string());
+
+        world.remove_component::<Name>(first);
+        assert_eq!(world.find_by_name("scout"), None);
+    }
+
+    #[test]
+    fn tags_follow_removals_and_despawns() {
+        let mut world = WorldBuilder::new().build();
+        let early = world.new_entity();
+        world.add_component(early, Player);
+
+        let tagged = world.new_entity();
+        world.add_tag::<Player>(tagged);
+        let mut players = world.tagged::<Player>();
+        players.sort_by_key(|entity| entity.id());
+        assert_eq!(players, [early, tagged]);
+
+        world.remove_component::<Player>(early);
+        assert_eq!(world.tagged::<Player>(), [tagged]);
+
+        world.despawn(tagged);
+        assert!(world.tagged::<Player>().is_empty());
+    }
 }
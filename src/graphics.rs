@@ -1,10 +1,12 @@
 use crate::{
     camera::{Camera, CameraUniform},
     texture::{TextureBuilder, Texture},
-    mesh::{Instance, InstanceInfo, InstanceRaw, Mesh, MeshId, MeshInfo, ModelInfo, Vertex},
+    mesh::{Instance, InstanceInfo, InstanceRaw, Mesh, MeshId, MeshInfo, Model, ModelInfo, Vertex},
 };
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 use wgpu::util::DeviceExt;
 
 /// A generic renderer that can by used by the [`App`]
@@ -19,6 +21,13 @@ pub trait Renderer<'a> {
     fn attach<T>(&mut self, item: &T, instance: Instance)
     where
         T: Renderable;
+    /// Called whenever an instance stops being rendered (e.g. its entity is despawned or its
+    /// model is replaced) so that any data held for it can be freed.
+    fn detach<T>(&mut self, _item: &T, _instance: &Instance)
+    where
+        T: Renderable,
+    {
+    }
     /// Called every frame after attaching instances to render the frame to the screen.
     fn render(&mut self);
     /// Called upon screen resize to update the renderer.
@@ -59,7 +68,10 @@ impl<'a> Renderer<'a> for WgpuRenderer<'a> {
     where
         T: Renderable,
     {
-        if self.vertex_buffers.get(&item.mesh().id).is_none() {
+        let _span = span!("attach", mesh = item.mesh().id.0);
+
+        if !self.vertex_buffers.contains_key(&item.mesh().id) {
+            let _span = span!("create vertex buffer", vertices = item.mesh().vertices.len());
             let vertex_buffer = self
                 .device
                 .create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -80,7 +92,7 @@ impl<'a> Renderer<'a> for WgpuRenderer<'a> {
             );
         }
 
-        if self.instances.get(&item.mesh().id).is_none() {
+        if !self.instances.contains_key(&item.mesh().id) {
             self.instances
                 .insert(item.mesh().id, InstanceInfo::new(&self.device, vec![]));
         } else if self
@@ -102,7 +114,22 @@ impl<'a> Renderer<'a> for WgpuRenderer<'a> {
         // TODO: refactor to remove unused instances
     }
 
+    fn detach<T>(&mut self, item: &T, instance: &Instance)
+    where
+        T: Renderable,
+    {
+        let _span = span!("detach", mesh = item.mesh().id.0);
+
+        if let Some(instances) = self.instances.get_mut(&item.mesh().id) {
+            if instances.contains(instance.id) {
+                instances.remove(instance.id);
+                instances.write_buffer(&self.device);
+            }
+        }
+    }
+
     fn render(&mut self) {
+        let _span = span!("render");
         self.camera_uniform
             .update_raw(self.camera.lock().unwrap().build_view_projection_matrix());
         self.queue.write_buffer(
@@ -180,6 +207,8 @@ impl<'a> Renderer<'a> for WgpuRenderer<'a> {
     }
 
     fn resize(&mut self, physical_size: winit::dpi::PhysicalSize<u32>) {
+        let _span = span!("resize", width = physical_size.width, height = physical_size.height);
+
         self.surface_config.width = physical_size.width;
         self.surface_config.height = physical_size.height;
 
@@ -387,3 +416,76 @@ impl<'a> WgpuRenderer<'a> {
         }
     }
 }
+
+/// Everything needed to draw a single frame, published by a simulation running on another thread.
+pub(crate) struct RenderState<C> {
+    /// Every instance that should be drawn along with its model.
+    pub(crate) instances: Vec<(Arc<Model>, Instance)>,
+    /// The camera the frame should be seen through.
+    pub(crate) camera: Option<C>,
+}
+
+impl<C> Default for RenderState<C> {
+    fn default() -> Self {
+        Self {
+            instances: Vec::new(),
+            camera: None,
+        }
+    }
+}
+
+/// Hands frames from the simulation thread to the render thread.
+///
+/// Each side owns a [`RenderState`] of its own: the simulation fills its state in and publishes
+/// it, after which the renderer picks it up the next time it draws. States are swapped rather than
+/// copied, so neither side ever waits for the other to finish with a frame and the allocations
+/// are reused. Every time the renderer draws it asks for another frame, which the simulation can
+/// wait for in order to publish frames as often as they are drawn.
+pub(crate) struct RenderBuffer<C> {
+    latest: Mutex<RenderState<C>>,
+    fresh: AtomicBool,
+    /// The number of frames the renderer has asked for.
+    requests: Mutex<u64>,
+    requested: Condvar,
+}
+
+impl<C> RenderBuffer<C> {
+    pub(crate) fn new() -> Self {
+        Self {
+            latest: Mutex::new(RenderState::default()),
+            fresh: AtomicBool::new(false),
+            requests: Mutex::new(0),
+            requested: Condvar::new(),
+        }
+    }
+
+    /// Blocks until the renderer has asked for a frame since the last one seen or until a timeout
+    /// passes, returning immediately if a request arrived in the meantime.
+    pub(crate) fn wait_for_request(&self, seen: &mut u64, timeout: Duration) {
+        let requests = self.requests.lock().unwrap();
+        let (requests, _) = self
+            .requested
+            .wait_timeout_while(requests, timeout, |requests| *requests == *seen)
+            .unwrap();
+        *seen = *requests;
+    }
+
+    /// Publishes a finished frame, leaving an older state behind to be filled in next.
+    pub(crate) fn publish(&self, state: &mut RenderState<C>) {
+        std::mem::swap(&mut *self.latest.lock().unwrap(), state);
+        self.fresh.store(true, Ordering::Release);
+    }
+
+    /// Swaps the most recently published frame in if there is one that hasn't been taken yet.
+    pub(crate) fn take(&self, state: &mut RenderState<C>) -> bool {
+        *self.requests.lock().unwrap() += 1;
+        self.requested.notify_one();
+
+        if !self.fresh.swap(false, Ordering::Acquire) {
+            return false;
+        }
+
+        std::mem::swap(&mut *self.latest.lock().unwrap(), state);
+        true
+    }
+}
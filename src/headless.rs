@@ -0,0 +1,252 @@
+use crate::{command::Events, diagnostics::Diagnostics, system::SystemError, World};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// An event raised by a system to stop a [`HeadlessRunner`] at the end of the current step.
+///
+/// ```
+/// # use aspeng::{command::Events, headless::Exit, Query};
+/// # struct Settled;
+/// fn stop_when_settled(query: Query) {
+///     if query.resource::<Settled>().is_some() {
+///         if let Some(exit) = query.resource::<Events<Exit>>() {
+///             exit.lock().unwrap().push(Exit);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exit;
+
+/// How quickly a [`HeadlessRunner`] steps the world.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pacing {
+    /// Steps are run back to back as quickly as the machine allows (e.g. for batch simulations or
+    /// tests).
+    #[default]
+    Fast,
+    /// Steps are run at the frequency of the world's fixed systems (adjusted by its time scale) so
+    /// that simulated time keeps up with real time (e.g. for a server or hardware in the loop). If
+    /// a step runs late, the steps that follow run back to back until the simulation has caught
+    /// up.
+    RealTime,
+}
+
+/// Why a [`HeadlessRunner`] stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The maximum number of steps was run.
+    MaxSteps,
+    /// The maximum amount of simulated time passed.
+    MaxTime,
+    /// A system raised an [`Exit`] event.
+    Exit,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::MaxSteps => write!(f, "reached the maximum number of steps"),
+            StopReason::MaxTime => write!(f, "reached the maximum simulated time"),
+            StopReason::Exit => write!(f, "exit requested"),
+        }
+    }
+}
+
+/// What happened during a run of a [`HeadlessRunner`].
+#[derive(Clone, Debug)]
+pub struct RunSummary {
+    /// The number of fixed steps that were run.
+    pub steps: u64,
+    /// The simulated time that passed.
+    pub simulated_time: Duration,
+    /// The real time the run took.
+    pub wall_time: Duration,
+    /// Why the run stopped.
+    pub reason: StopReason,
+    /// The [`Diagnostics`] of the world at the end of the run, unless the resource was removed.
+    pub diagnostics: Option<Diagnostics>,
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} after {} steps ({:.3}s simulated in {:.3}s)",
+            self.reason,
+            self.steps,
+            self.simulated_time.as_secs_f64(),
+            self.wall_time.as_secs_f64()
+        )?;
+
+        if let Some(diagnostics) = &self.diagnostics {
+            write!(f, "{}", diagnostics)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Drives a world without a window until a stopping condition is met.
+///
+/// Replaces the loop calling [`World::tick`] that non-GUI simulations would otherwise need. The
+/// world is advanced with [`World::step`], so every step advances the simulation by exactly the
+/// period of the fixed systems and a run of a given number of steps always covers the same
+/// simulated time, however fast it is [paced](Pacing).
+///
+/// The run stops at the end of the first step after which any of the following holds:
+/// - the maximum number of steps ([`with_max_steps`]) has been run;
+/// - the maximum simulated time ([`with_max_time`]) has passed;
+/// - a system has raised an [`Exit`] event through the world's [`Events<Exit>`] resource, which is
+///   inserted at the start of the run.
+///
+/// A runner with no limits runs until an [`Exit`] event is raised.
+///
+/// While the world is [paused](World::pause()), no steps are run but commands sent to the world
+/// are still applied. When paced to real time, the world's [time scale](World::set_time_scale())
+/// speeds up or slows down the steps.
+///
+/// ```
+/// # use aspeng::{headless::Pacing, HeadlessRunner, WorldBuilder};
+/// # use std::time::Duration;
+/// # let mut world = WorldBuilder::new().build();
+/// let summary = HeadlessRunner::new()
+///     .with_pacing(Pacing::Fast)
+///     .with_max_time(Duration::from_secs(60))
+///     .run(&mut world)?;
+///
+/// println!("{}", summary);
+/// # Ok::<(), aspeng::system::SystemError>(())
+/// ```
+///
+/// [`World::tick`]: crate::World::tick()
+/// [`World::step`]: crate::World::step()
+/// [`with_max_steps`]: Self::with_max_steps()
+/// [`with_max_time`]: Self::with_max_time()
+#[derive(Clone, Debug, Default)]
+pub struct HeadlessRunner {
+    pacing: Pacing,
+    max_steps: Option<u64>,
+    max_time: Option<Duration>,
+}
+
+impl HeadlessRunner {
+    /// Creates a runner that steps as fast as possible with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chooses how quickly the world is stepped.
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    /// Stops the run once the given number of steps have been run.
+    pub fn with_max_steps(mut self, steps: u64) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Stops the run once the given simulated time has passed.
+    pub fn with_max_time(mut self, time: Duration) -> Self {
+        self.max_time = Some(time);
+        self
+    }
+
+    /// Steps the world until a stopping condition is met.
+    ///
+    /// Returns the error of the first system to fail if the world's [`ErrorPolicy`] is
+    /// [`ErrorPolicy::Stop`].
+    ///
+    /// [`ErrorPolicy`]: crate::system::ErrorPolicy
+    /// [`ErrorPolicy::Stop`]: crate::system::ErrorPolicy::Stop
+    pub fn run(&self, world: &mut World<'_>) -> Result<RunSummary, SystemError> {
+        let _span = span!("headless run");
+        if world.resource::<Events<Exit>>().is_none() {
+            world.insert_resource(Events::<Exit>::default());
+        }
+
+        let period = world.period();
+        let start = Instant::now();
+        // when the previous step was due, or none if the next step is due right away
+        let mut previous: Option<Instant> = None;
+        let mut steps: u64 = 0;
+
+        let reason = loop {
+            if self.max_steps.is_some_and(|max| steps >= max) {
+                break StopReason::MaxSteps;
+            }
+            if self.max_time.is_some_and(|max| period.mul_f64(steps as f64) >= max) {
+                break StopReason::MaxTime;
+            }
+
+            // scales too close to zero for a step to ever come due are treated like a pause
+            let due = Duration::try_from_secs_f32(period.as_secs_f32() / world.time_scale())
+                .ok()
+                .filter(|_| !world.is_paused())
+                .and_then(|interval| match previous {
+                    Some(previous) if self.pacing == Pacing::RealTime => {
+                        previous.checked_add(interval)
+                    }
+                    _ => Some(Instant::now()),
+                });
+
+            let now = Instant::now();
+            match due {
+                Some(due) if due <= now => {
+                    previous = Some(due);
+                    world.step()?;
+                    steps += 1;
+                }
+                _ => {
+                    // waiting at most a period at a time lets commands (e.g. resuming the world or
+                    // changing its time scale) take effect in the meantime
+                    world.apply_commands();
+                    std::thread::sleep(due.map_or(period, |due| (due - now).min(period)));
+                    if due.is_none() {
+                        previous = None;
+                    }
+                }
+            }
+
+            let exited = world
+                .resource::<Events<Exit>>()
+                .is_some_and(|exit| exit.lock().unwrap().drain().count() > 0);
+            if exited {
+                break StopReason::Exit;
+            }
+        };
+
+        Ok(RunSummary {
+            steps,
+            simulated_time: period.mul_f64(steps as f64),
+            wall_time: start.elapsed(),
+            reason,
+            diagnostics: world
+                .resource::<Diagnostics>()
+                .map(|diagnostics| diagnostics.lock().unwrap().clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeadlessRunner, Pacing, StopReason};
+    use crate::WorldBuilder;
+
+    #[test]
+    fn near_zero_time_scales_pause_the_run() {
+        let mut world = WorldBuilder::new().build();
+        world.set_time_scale(1e-30);
+        world.handle().run(|world| world.set_time_scale(1.0)).unwrap();
+
+        let summary = HeadlessRunner::new()
+            .with_pacing(Pacing::RealTime)
+            .with_max_steps(2)
+            .run(&mut world)
+            .unwrap();
+        assert_eq!(summary.steps, 2);
+        assert_eq!(summary.reason, StopReason::MaxSteps);
+    }
+}
@@ -13,6 +13,12 @@ pub struct InputManager {
     pub analog_input: (f32, f32),
 }
 
+impl Default for InputManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InputManager {
     /// Creates a new empty input manager.
     pub fn new() -> Self {
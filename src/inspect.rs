@@ -0,0 +1,411 @@
+use crate::{
+    command::WorldHandle, diagnostics::Diagnostics, entity::Names, reflect::Value, Entity, World,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How long a connection waits for the world to answer a request before giving up (e.g. because
+/// the world has stopped ticking).
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The states of a request that has been queued on the world.
+const PENDING: u8 = 0;
+const STARTED: u8 = 1;
+const CANCELLED: u8 = 2;
+
+/// A request sent to an [`Inspector`], tagged by its `cmd` field.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    Entities,
+    Entity {
+        entity: u32,
+    },
+    Get {
+        entity: u32,
+        component: String,
+        #[serde(default)]
+        path: String,
+    },
+    Set {
+        entity: u32,
+        component: String,
+        #[serde(default)]
+        path: String,
+        value: Value,
+    },
+    Resources,
+    Systems,
+    SetSystem {
+        name: String,
+        enabled: bool,
+    },
+    Diagnostics,
+}
+
+/// A request along with the id the client chose to match it with its response.
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(flatten)]
+    request: Request,
+}
+
+/// A server through which external tools and scripts can look inside a running world.
+///
+/// The server only listens on localhost. Clients connect over TCP and send one JSON request per
+/// line, each answered by one JSON response per line in the same order. Every request is carried
+/// out through the world's [`WorldHandle`], so it is answered at the start of the next tick and
+/// never observes the world halfway through one. A request the world doesn't start carrying out
+/// within five seconds (e.g. because it has stopped ticking) is cancelled and answered with an
+/// error, so it never takes effect later.
+///
+/// Requests are tagged by their `cmd` and may carry an `id`, which is echoed in the response:
+///
+/// | `cmd` | Fields | Result |
+/// |---|---|---|
+/// | `entities` | | Every entity with its name and the names of its registered components. |
+/// | `entity` | `entity` | The values of every registered component of an entity. |
+/// | `get` | `entity`, `component`, `path` | The value of a component or one of its fields. |
+/// | `set` | `entity`, `component`, `path`, `value` | Writes a component or one of its fields. |
+/// | `resources` | | The values of every registered resource. |
+/// | `systems` | | Every system with its kind and whether it is enabled. |
+/// | `set_system` | `name`, `enabled` | Enables or disables the systems with a name. |
+/// | `diagnostics` | | The world's [`Diagnostics`]. |
+///
+/// Components and fields are addressed as with [`World::get_reflect`], and only types registered
+/// with the world's [`TypeRegistry`] are visible. Successful responses hold the result under `ok`
+/// and failed ones a message under `error`:
+///
+/// ```text
+/// > {"id": 1, "cmd": "get", "entity": 3, "component": "Velocity", "path": "x"}
+/// < {"id":1,"ok":1.0}
+/// > {"id": 2, "cmd": "set", "entity": 3, "component": "Velocity", "path": "x", "value": 2.5}
+/// < {"id":2,"ok":null}
+/// > {"id": 3, "cmd": "set_system", "name": "move balls", "enabled": false}
+/// < {"id":3,"ok":true}
+/// ```
+///
+/// The server stops when the inspector is dropped, closing every connection.
+///
+/// Requires the `inspect` feature.
+///
+/// [`World::get_reflect`]: crate::World::get_reflect()
+/// [`TypeRegistry`]: crate::reflect::TypeRegistry
+#[derive(Debug)]
+pub struct Inspector {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Inspector {
+    /// Starts listening on a port of localhost for requests to the world behind a handle.
+    ///
+    /// A port of zero lets the operating system choose a free port, which can be read back with
+    /// [`addr`]. The server runs on a thread of its own until the inspector is dropped; requests
+    /// sent after the world has been dropped are answered with an error.
+    ///
+    /// [`addr`]: Self::addr()
+    pub fn start(handle: WorldHandle, port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let addr = listener.local_addr()?;
+        info!("inspector listening on {}", addr);
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let stopped = shutdown.clone();
+        let thread = std::thread::Builder::new()
+            .name("inspector".to_string())
+            .spawn(move || listen(listener, handle, &stopped))?;
+
+        Ok(Self {
+            addr,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for Inspector {
+    /// Stops the server, waiting for every connection to finish the request it is answering.
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+
+        // accepting blocks until a client connects, so connect in order to wake the server up
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("the inspector thread panicked");
+            }
+        }
+    }
+}
+
+/// Accepts clients until asked to shut down, then closes every connection.
+fn listen(listener: TcpListener, handle: WorldHandle, shutdown: &AtomicBool) {
+    let mut connections: Vec<(TcpStream, JoinHandle<()>)> = Vec::new();
+
+    for stream in listener.incoming() {
+        if shutdown.load(Ordering::Acquire) {
+            break;
+        }
+
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("inspector couldn't accept connection: {}", e);
+                continue;
+            }
+        };
+        let closer = match stream.try_clone() {
+            Ok(closer) => closer,
+            Err(e) => {
+                error!("inspector couldn't start connection: {}", e);
+                continue;
+            }
+        };
+
+        let handle = handle.clone();
+        let spawned = std::thread::Builder::new()
+            .name("inspector connection".to_string())
+            .spawn(move || {
+                if let Err(e) = serve(stream, handle) {
+                    error!("inspector connection failed: {}", e);
+                }
+            });
+        match spawned {
+            Ok(thread) => {
+                connections.retain(|(_, thread)| !thread.is_finished());
+                connections.push((closer, thread));
+            }
+            Err(e) => error!("inspector couldn't start connection: {}", e),
+        }
+    }
+
+    for (stream, thread) in connections {
+        let _ = stream.shutdown(Shutdown::Both);
+        let _ = thread.join();
+    }
+}
+
+/// Answers the requests of a single client until it disconnects.
+fn serve(stream: TcpStream, handle: WorldHandle) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (response, closed) = match serde_json::from_str::<Envelope>(&line) {
+            Ok(Envelope { id, request }) => {
+                let (response, closed) = answer(&handle, request);
+                (with_id(response, id), closed)
+            }
+            Err(e) => (json!({ "error": format!("invalid request: {}", e) }), false),
+        };
+
+        writeln!(writer, "{}", response)?;
+        writer.flush()?;
+
+        if closed {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Carries out a request on the world, returning the response and whether the world is gone.
+///
+/// A request the world hasn't started on when the timeout runs out is cancelled, while one it has
+/// started on is waited for.
+fn answer(handle: &WorldHandle, request: Request) -> (serde_json::Value, bool) {
+    let (sender, receiver) = mpsc::channel();
+    let state = Arc::new(AtomicU8::new(PENDING));
+    let started = state.clone();
+    let sent = handle.run(move |world| {
+        if started
+            .compare_exchange(PENDING, STARTED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            let _ = sender.send(respond(world, request));
+        }
+    });
+
+    if let Err(e) = sent {
+        return (json!({ "error": e.to_string() }), true);
+    }
+
+    let received = match receiver.recv_timeout(TIMEOUT) {
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            let cancelled = state
+                .compare_exchange(PENDING, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok();
+            if cancelled {
+                return (json!({ "error": "the world didn't answer in time" }), false);
+            }
+
+            receiver.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected)
+        }
+        received => received,
+    };
+
+    match received {
+        Ok(Ok(result)) => (json!({ "ok": result }), false),
+        Ok(Err(message)) => (json!({ "error": message }), false),
+        Err(_) => (json!({ "error": "the world has been dropped" }), true),
+    }
+}
+
+fn with_id(mut response: serde_json::Value, id: Option<serde_json::Value>) -> serde_json::Value {
+    if let (Some(id), Some(fields)) = (id, response.as_object_mut()) {
+        fields.insert("id".to_string(), id);
+    }
+
+    response
+}
+
+fn respond(world: &mut World<'_>, request: Request) -> Result<serde_json::Value, String> {
+    match request {
+        Request::Entities => Ok(world
+            .entities
+            .iter()
+            .map(|&entity| {
+                let components = world
+                    .reflect_entity(entity)
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>();
+                json!({
+                    "id": entity.id(),
+                    "name": name_of(world, entity),
+                    "components": components,
+                })
+            })
+            .collect()),
+        Request::Entity { entity } => {
+            let entity = find(world, entity)?;
+            Ok(json!({
+                "id": entity.id(),
+                "name": name_of(world, entity),
+                "components": to_json(&Value::Map(world.reflect_entity(entity)))?,
+            }))
+        }
+        Request::Get {
+            entity,
+            component,
+            path,
+        } => {
+            let entity = find(world, entity)?;
+            let value = world
+                .get_reflect(entity, &component, &path)
+                .map_err(|e| e.to_string())?;
+            to_json(&value)
+        }
+        Request::Set {
+            entity,
+            component,
+            path,
+            value,
+        } => {
+            let entity = find(world, entity)?;
+            world
+                .set_reflect(entity, &component, &path, &value)
+                .map_err(|e| e.to_string())?;
+            Ok(serde_json::Value::Null)
+        }
+        Request::Resources => to_json(&Value::Map(world.reflect_resources())),
+        Request::Systems => {
+            let fixed = world.fixed_systems.iter().map(|s| ("fixed", s));
+            let dependent = world.dependent_systems.iter().map(|s| ("dependent", s));
+            Ok(fixed
+                .chain(dependent)
+                .map(|(kind, system)| {
+                    json!({
+                        "name": system.label,
+                        "kind": kind,
+                        "enabled": system.enabled,
+                    })
+                })
+                .collect())
+        }
+        Request::SetSystem { name, enabled } => {
+            if world.set_system_enabled(&name, enabled) {
+                Ok(json!(true))
+            } else {
+                Err(format!("no system named `{}`", name))
+            }
+        }
+        Request::Diagnostics => {
+            let diagnostics = world
+                .resource::<Diagnostics>()
+                .ok_or("the world has no diagnostics")?;
+            let diagnostics = diagnostics.lock().unwrap();
+            let systems = diagnostics
+                .systems()
+                .iter()
+                .map(|system| {
+                    json!({
+                        "name": system.name(),
+                        "average_ms": system.average().as_secs_f64() * 1000.0,
+                        "max_ms": system.max().as_secs_f64() * 1000.0,
+                        "runs": system.runs(),
+                        "enabled": system.enabled(),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            Ok(json!({
+                "ticks": diagnostics.ticks(),
+                "fps": diagnostics.fps(),
+                "frame_time_ms": diagnostics.frame_time().as_secs_f64() * 1000.0,
+                "fixed_steps": diagnostics.fixed_steps(),
+                "entities": diagnostics.entity_count(),
+                "components": diagnostics.component_count(),
+                "systems": systems,
+            }))
+        }
+    }
+}
+
+fn find(world: &World<'_>, id: u32) -> Result<Entity, String> {
+    let entity = Entity::new(id);
+    if world.contains(entity) {
+        Ok(entity)
+    } else {
+        Err(format!("no entity {}", id))
+    }
+}
+
+fn name_of(world: &World<'_>, entity: Entity) -> Option<String> {
+    world
+        .resource::<Names>()
+        .and_then(|names| names.lock().unwrap().name_of(entity).map(str::to_string))
+}
+
+fn to_json(value: &Value) -> Result<serde_json::Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+impl World<'_> {
+    /// Starts an [`Inspector`] for the world on a port of localhost.
+    ///
+    /// Requires the `inspect` feature.
+    pub fn start_inspector(&self, port: u16) -> io::Result<Inspector> {
+        Inspector::start(self.handle(), port)
+    }
+}
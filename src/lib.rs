@@ -3,49 +3,140 @@
 //!
 //! Focuses on three main goals:
 //! - **Customizable**: Every task completed by the user should be customizable and all engine code
-//! (with exception of the core ECS system) should be replaceable.
+//!   (with exception of the core ECS system) should be replaceable.
 //! - **Simple**: Tasks should be simple to complete and intuitive to learn or well-documented when
-//! unintuitive.
+//!   unintuitive.
 //! - **Data-driven**: Data should be the focus of all tasks as dictated by the ECS paradigm.
 //!
 //! See the [`README`] for more information.
 //!
+//! # Profiling
+//!
+//! With the `tracing` feature enabled, the engine emits [`tracing`] spans around every tick, every
+//! system run (named after the system), the renderer, asset loading and the creation of GPU
+//! buffers, and reports errors through `tracing` events rather than standard error. Any subscriber
+//! may collect them; e.g. a Chrome trace viewable in `chrome://tracing` or Perfetto can be captured
+//! with `tracing-chrome`:
+//!
+//! ```ignore
+//! use tracing_subscriber::prelude::*;
+//!
+//! let (chrome_layer, _guard) = tracing_chrome::ChromeLayerBuilder::new().build();
+//! tracing_subscriber::registry().with(chrome_layer).init();
+//!
+//! // the trace is written to the working directory once the guard is dropped
+//! app.run();
+//! ```
+//!
 //! [`README`]: https://github.com/jwjbadger/aspen
+//! [`tracing`]: https://docs.rs/tracing
 #![warn(missing_docs)]
 
+#[macro_use]
+mod trace;
+
 /// Handles everything related to the camera, which provides a point of access to the world,
 /// allowing it to be rendered to the screen.
 pub mod camera;
 /// Handles everything related to WGPU textures, allowing them to be built and used by WGPU.
 pub mod texture;
+/// Handles sending commands to a world from other threads.
+pub mod command;
+/// Handles the developer console, through which commands can be typed into a running world.
+pub mod console;
 /// Handles the component side of ECS. Rarely used externally.
 pub mod component;
-/// Handles the entity side of ECS.
+/// Handles measuring the performance of the world.
+pub mod diagnostics;
+/// Handles printing the state of a world and comparing it over time for debugging.
+pub mod dump;
+/// Handles the entity side of ECS, including naming and tagging entities.
 pub mod entity;
 /// Primarily handles renderers and renderable objects.
 pub mod graphics;
+/// Handles running worlds without a window (e.g. for batch simulations and servers).
+pub mod headless;
 /// Handles all input.
 pub mod input;
+/// Handles inspecting and editing a running world from external tools over a local connection.
+#[cfg(feature = "inspect")]
+pub mod inspect;
 /// Holds all structures required to create something that is renderable.
 pub mod mesh;
 /// Used for GUI applications to handle operating system specific tasks (e.g. requesting input and
 /// creating windows).
 pub mod os;
+/// Handles reacting to components being added to and removed from entities.
+pub mod observer;
+/// Handles spawning copies of entities from reusable descriptions.
+pub mod prefab;
+/// Handles plugins, which package systems and resources so that they can be reused.
+pub mod plugin;
+/// Handles runtime reflection, allowing components to be inspected and edited by name.
+pub mod reflect;
+/// Handles reproducible randomness.
+pub mod random;
+/// Handles typed relationships between entities.
+pub mod relation;
+/// Handles resources, which hold global data that is shared by every system.
+pub mod resource;
+/// Handles saving worlds to files and loading them back.
+#[cfg(feature = "serde")]
+pub mod save;
+/// Handles loading worlds from declarative scene files.
+#[cfg(feature = "scene")]
+pub mod scene;
+/// Handles capturing the state of a world in memory so that it can be restored later.
+pub mod snapshot;
+/// Handles application states and the systems that run when entering, leaving, or staying in them.
+pub mod state;
 /// Handles the system side of ECS.
 pub mod system;
+/// Handles running long work in the background and bringing its results back into the world.
+pub mod task;
+/// Handles setting up worlds for unit tests and checking their state.
+pub mod testing;
 
 pub use crate::{
+    command::WorldHandle,
     component::Component,
-    entity::Entity,
+    entity::{Entity, Name},
     graphics::{Renderer, WgpuRenderer},
-    os::App,
+    headless::HeadlessRunner,
+    os::{App, ThreadedApp},
+    plugin::Plugin,
+    prefab::Prefab,
+    reflect::{Reflect, TypeRegistry, Value},
+    relation::Relation,
+    resource::Resources,
     system::{Query, System, SystemInterface},
 };
 
+use crate::{
+    command::Command,
+    console::Console,
+    component::{Archetypes, Cloner, StorageType},
+    diagnostics::Diagnostics,
+    observer::{Lifecycle, Observers},
+    random::Rng,
+    reflect::{ReflectError, TypeRegistration},
+    relation::{ChildOf, OnDespawn},
+    snapshot::CapturedComponent,
+    state::StateMachines,
+    system::{Changes, ErrorPolicy, ScheduledSystem, SystemError},
+    task::TaskPool,
+};
+
 use std::{
-    any::Any,
-    sync::{Arc, Mutex},
-    time::Instant,
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 /// The world in which all entities, components, and system lie
@@ -55,19 +146,33 @@ use std::{
 ///
 /// The world is used equally for visual and non-visual simulations---the only thing that changes is
 /// how the world is stored and how certain special systems are handled. See [`App`] for more
-/// information on GUI applications.
+/// information on GUI applications and [`HeadlessRunner`] for non-GUI ones.
 ///
 /// [`WorldBuilder`]: crate::WorldBuilder
 /// [`App`]: crate::os::App
+/// [`HeadlessRunner`]: crate::headless::HeadlessRunner
 pub struct World<'a> {
     entities: Vec<Entity>,
     components: Vec<Component<Arc<Mutex<dyn Any>>>>,
-    fixed_systems: Vec<Box<dyn SystemInterface + 'a>>,
-    dependent_systems: Vec<Box<dyn SystemInterface + 'a>>,
-    current_id: u32,
+    archetypes: Archetypes,
+    resources: Resources,
+    changes: Changes,
+    captures: RefCell<HashMap<TypeId, CapturedComponent>>,
+    error_policy: ErrorPolicy,
+    fixed_systems: Vec<ScheduledSystem<'a>>,
+    dependent_systems: Vec<ScheduledSystem<'a>>,
+    registry: TypeRegistry,
+    observers: Observers<'a>,
+    states: StateMachines<'a>,
+    current_id: Arc<AtomicU32>,
+    commands: Receiver<Command>,
+    command_sender: Sender<Command>,
+    applying_commands: bool,
     period: f32,
     previous_time: Instant,
     accumulator: f32,
+    paused: bool,
+    time_scale: f32,
     phantom: std::marker::PhantomData<&'a ()>,
 }
 
@@ -89,30 +194,200 @@ impl<'a> World<'a> {
     /// Represents a time step within the simulation that should be called within a loop for non-GUI
     /// applications.
     ///
+    /// The time spent in each step is recorded in the [`Diagnostics`] resource.
+    ///
     /// During each time step, two primary tasks are executed:
     /// - All fixed systems are ran as many times as they need to be in order to make up the time
-    /// between ticks. For instance, if fixed systems are intended to be ran ten times per second
-    /// and 1.25 seconds have passed, the systems will be ran twelve times, with the thirteenth
-    /// occurring the next time tick is called and at least 0.05 seconds have passed.
+    ///   between ticks. For instance, if fixed systems are intended to be ran ten times per second
+    ///   and 1.25 seconds have passed, the systems will be ran twelve times, with the thirteenth
+    ///   occurring the next time tick is called and at least 0.05 seconds have passed.
     /// - All dependent systems are ran a single time (mostly intended for GUI applications where
-    /// certain systems should be linked to the frame rate)
-    pub fn tick(&mut self) {
+    ///   certain systems should be linked to the frame rate)
+    ///
+    /// Before any system runs, every command sent through a [`WorldHandle`] since the previous
+    /// tick is applied, after which any requested [`State`] transitions take place.
+    ///
+    /// Time passes at the world's [time scale], and not at all while the world is [paused], in
+    /// which case only the dependent systems run.
+    ///
+    /// Errors returned by systems are handled according to the world's [`ErrorPolicy`]. If the
+    /// policy is [`ErrorPolicy::Stop`], the tick ends as soon as a system fails and the error is
+    /// returned.
+    ///
+    /// [time scale]: Self::set_time_scale()
+    /// [paused]: Self::pause()
+    /// [`ErrorPolicy`]: crate::system::ErrorPolicy
+    /// [`ErrorPolicy::Stop`]: crate::system::ErrorPolicy::Stop
+    /// [`Diagnostics`]: crate::diagnostics::Diagnostics
+    /// [`WorldHandle`]: crate::command::WorldHandle
+    /// [`State`]: crate::state::State
+    pub fn tick(&mut self) -> Result<(), SystemError> {
+        let _span = span!("tick");
+        self.apply_commands();
+        self.apply_state_transitions()?;
+
         let current_time = Instant::now();
         let delta_time = self.previous_time.elapsed();
         self.previous_time = current_time;
 
-        self.accumulator += delta_time.as_secs_f32();
+        if !self.paused {
+            self.accumulator += delta_time.as_secs_f32() * self.time_scale;
+        }
+        let mut fixed_steps = 0;
         while self.accumulator >= self.period {
-            self.fixed_systems
-                .iter_mut()
-                .for_each(|s| s.execute(Query::new(&mut self.components, s.components())));
+            Self::run_systems(
+                &mut self.fixed_systems,
+                &mut self.components,
+                &self.resources,
+                &self.archetypes,
+                &self.changes,
+                self.error_policy,
+            )?;
 
             self.accumulator -= self.period;
+            fixed_steps += 1;
         }
 
-        self.dependent_systems
-            .iter_mut()
-            .for_each(|s| s.execute(Query::new(&mut self.components, s.components())));
+        Self::run_systems(
+            &mut self.dependent_systems,
+            &mut self.components,
+            &self.resources,
+            &self.archetypes,
+            &self.changes,
+            self.error_policy,
+        )?;
+
+        // every system has now seen the changes, so they no longer need to be kept
+        self.changes.clear();
+        self.record_diagnostics(delta_time, fixed_steps);
+
+        Ok(())
+    }
+
+    /// Runs exactly one fixed step regardless of how much time has passed.
+    ///
+    /// Operates like [`tick`] except that the fixed systems run exactly once, so a simulation
+    /// driven by `step` advances by the same amount of simulated time (the period of the fixed
+    /// systems) on every call no matter how fast or slow the machine running it is. Dependent
+    /// systems still run once afterwards. The step is run even if the world is paused, which
+    /// allows a paused simulation to be advanced a step at a time.
+    ///
+    /// Time spent stepping is not made up for by later calls to [`tick`].
+    ///
+    /// [`tick`]: Self::tick()
+    pub fn step(&mut self) -> Result<(), SystemError> {
+        let _span = span!("step");
+        self.apply_commands();
+        self.apply_state_transitions()?;
+
+        let delta_time = self.previous_time.elapsed();
+        self.previous_time = Instant::now();
+
+        Self::run_systems(
+            &mut self.fixed_systems,
+            &mut self.components,
+            &self.resources,
+            &self.archetypes,
+            &self.changes,
+            self.error_policy,
+        )?;
+        Self::run_systems(
+            &mut self.dependent_systems,
+            &mut self.components,
+            &self.resources,
+            &self.archetypes,
+            &self.changes,
+            self.error_policy,
+        )?;
+
+        self.changes.clear();
+        self.record_diagnostics(delta_time, 1);
+
+        Ok(())
+    }
+
+    /// Returns the simulated time that passes in a single fixed step.
+    pub fn period(&self) -> Duration {
+        Duration::from_secs_f32(self.period)
+    }
+
+    fn run_systems(
+        systems: &mut [ScheduledSystem<'a>],
+        components: &mut [Component<Arc<Mutex<dyn Any>>>],
+        resources: &Resources,
+        archetypes: &Archetypes,
+        changes: &Changes,
+        error_policy: ErrorPolicy,
+    ) -> Result<(), SystemError> {
+        for system in systems.iter_mut().filter(|s| s.should_run(resources)) {
+            if let Err(error) = system.run(components, resources, archetypes, changes) {
+                system.fail(error, error_policy)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the time left until the next fixed step is due.
+    ///
+    /// Allows loops that call [`tick`] to sleep rather than spin while waiting for the fixed
+    /// systems to run again.
+    ///
+    /// While the world is paused (or its time scale is so close to zero that no step is ever
+    /// due), the period of the fixed systems is returned in order for such loops to keep waking up
+    /// (e.g. to apply commands that resume it).
+    ///
+    /// [`tick`]: Self::tick()
+    pub fn time_until_step(&self) -> Duration {
+        if self.paused || self.time_scale <= 0.0 {
+            return self.period();
+        }
+
+        let owed = self.accumulator + self.previous_time.elapsed().as_secs_f32() * self.time_scale;
+        Duration::try_from_secs_f32(((self.period - owed) / self.time_scale).max(0.0))
+            .unwrap_or_else(|_| self.period())
+    }
+
+    /// Stops time from passing in [`tick`] so that fixed systems no longer run.
+    ///
+    /// Dependent systems (e.g. rendering) keep running, and the world can still be advanced with
+    /// [`step`].
+    ///
+    /// [`tick`]: Self::tick()
+    /// [`step`]: Self::step()
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Lets time pass again after the world was paused.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns whether the world is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Chooses how quickly simulated time passes relative to real time in [`tick`] (e.g. `0.5`
+    /// for slow motion or `2.0` for fast-forward).
+    ///
+    /// Negative scales are treated as zero. Scales so close to zero that a fixed step would never
+    /// come due pause the world like a scale of zero does.
+    ///
+    /// [`tick`]: Self::tick()
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_scale = scale.max(0.0);
+    }
+
+    /// Returns how quickly simulated time passes relative to real time.
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Chooses what happens when a system returns an error.
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.error_policy = policy;
     }
 
     /// Requests a new [`Entity`] from the world.
@@ -124,9 +399,14 @@ impl<'a> World<'a> {
     /// [`Entity`]: crate::entity::Entity
     /// [`Component`]: crate::component::Component
     pub fn new_entity(&mut self) -> Entity {
-        self.entities.push(Entity::new(self.current_id));
-        self.current_id += 1;
-        *self.entities.last().unwrap()
+        let entity = self.reserve_entity();
+        self.entities.push(entity);
+        entity
+    }
+
+    /// Hands out the id of a new entity without adding the entity to the world yet.
+    pub(crate) fn reserve_entity(&self) -> Entity {
+        Entity::new(self.current_id.fetch_add(1, Ordering::Relaxed))
     }
 
     /// Registers a component with a particular [`Entity`] in the world.
@@ -134,8 +414,14 @@ impl<'a> World<'a> {
     /// Stores a component so that it may be retrieved with other components of the same type and
     /// indexed by the [`Entity`]. In order for a [`Component`] to be operated on by the world, it
     /// must be registered as such. The component is stored as a [`Arc<Mutex<T>>`] under the hood.
-    pub fn add_component<T: Any + Clone + 'static>(&mut self, entity: Entity, data: T) {
-        self.share_component::<T>(entity, Arc::new(Mutex::new(data)));
+    ///
+    /// Components are only captured by snapshots and copied by prefabs if their type was
+    /// registered as clonable with [`register_clone`] (or reflected with [`register_type`]).
+    ///
+    /// [`register_clone`]: Self::register_clone()
+    /// [`register_type`]: Self::register_type()
+    pub fn add_component<T: Any + 'static>(&mut self, entity: Entity, data: T) {
+        self.share_component_with(entity, Arc::new(Mutex::new(data)));
     }
 
     /// Shares a component that may be used outside of the world as well.
@@ -145,23 +431,213 @@ impl<'a> World<'a> {
     /// world at the same time.
     ///
     /// [`add_component`]: Self::add_component()
-    pub fn share_component<T: Any + Clone + 'static>(
+    pub fn share_component<T: Any + 'static>(&mut self, entity: Entity, data: Arc<Mutex<T>>) {
+        self.share_component_with(entity, data);
+        self.mark_shared(TypeId::of::<T>());
+    }
+
+    /// Records that components of type `T` can be cloned, so that they are captured by snapshots
+    /// and copied by prefabs.
+    ///
+    /// See [`TypeRegistry::register_clone`].
+    pub fn register_clone<T: Any + Clone + Send + Sync>(&mut self) {
+        self.registry.register_clone::<T>();
+        self.adopt_cloner(TypeId::of::<T>());
+    }
+
+    /// Gives the store of a type the cloner registered for it if it doesn't have one yet.
+    fn adopt_cloner(&mut self, type_id: TypeId) {
+        let cloner = self.registry.cloner(type_id);
+        if let Some(component) = self.components.iter_mut().find(|c| c.type_id == type_id) {
+            if component.cloner.is_none() {
+                component.cloner = cloner;
+            }
+        }
+    }
+
+    /// Records that components of a type may be written to from outside of the world.
+    fn mark_shared(&mut self, type_id: TypeId) {
+        if let Some(component) = self.components.iter_mut().find(|c| c.type_id == type_id) {
+            component.shared = true;
+        }
+    }
+
+    fn share_component_with<T: Any + 'static>(&mut self, entity: Entity, data: Arc<Mutex<T>>) {
+        self.share_component_by_id(entity, TypeId::of::<T>(), data, None);
+    }
+
+    /// Adds a type-erased component of a particular type to an entity.
+    ///
+    /// The store of the type is given the cloner registered for it, or the given one otherwise.
+    fn share_component_by_id(
         &mut self,
         entity: Entity,
-        data: Arc<Mutex<T>>,
+        type_id: TypeId,
+        data: Arc<Mutex<dyn Any>>,
+        cloner: Option<Cloner>,
     ) {
-        for e in self.components.iter_mut() {
-            if e.type_id == std::any::TypeId::of::<T>() {
-                e.add_entity(entity, data);
-                return;
+        if entity == Entity::PLACEHOLDER {
+            error!("placeholder entities can't hold components");
+            return;
+        }
+
+        let index = match self.components.iter().position(|c| c.type_id == type_id) {
+            Some(index) => index,
+            None => {
+                self.components
+                    .push(Component::<Arc<Mutex<dyn Any + 'static>>>::new(type_id));
+                self.components.len() - 1
             }
+        };
+
+        if self.components[index].cloner.is_none() {
+            self.components[index].cloner = self.registry.cloner(type_id).or(cloner);
+        }
+
+        let replaced = self.components[index].data.contains_key(&entity);
+        if replaced {
+            self.trigger(type_id, Lifecycle::Remove, entity);
         }
 
+        // hooks may have added or removed component types, so the index can't be trusted
+        let component = self
+            .components
+            .iter()
+            .find(|c| c.type_id == type_id)
+            .unwrap();
+        let archetype = if component.data.storage_type() == StorageType::Table
+            && !component.data.contains_key(&entity)
+        {
+            let archetype = self.archetypes.toggle(entity, type_id, true);
+            self.relocate(entity, archetype);
+            archetype
+        } else {
+            self.archetypes.of(&entity)
+        };
+
         self.components
-            .push(Component::<Arc<Mutex<dyn Any + 'static>>>::new(
-                std::any::TypeId::of::<T>(),
-            ));
-        self.components.last_mut().unwrap().add_entity(entity, data);
+            .iter_mut()
+            .find(|c| c.type_id == type_id)
+            .unwrap()
+            .add_entity(entity, archetype, data);
+        self.changes.record(entity);
+
+        if !replaced {
+            self.trigger(type_id, Lifecycle::Add, entity);
+        }
+        self.trigger(type_id, Lifecycle::Insert, entity);
+    }
+
+    /// Removes a component of type `T` from an entity, returning whether it held one.
+    ///
+    /// Any [`on_remove`] hooks are called before the component is removed.
+    ///
+    /// [`on_remove`]: Self::on_remove()
+    pub fn remove_component<T: Any + 'static>(&mut self, entity: Entity) -> bool {
+        self.remove_component_by_id(TypeId::of::<T>(), entity)
+    }
+
+    fn remove_component_by_id(&mut self, type_id: TypeId, entity: Entity) -> bool {
+        let held = self
+            .components
+            .iter()
+            .any(|c| c.type_id == type_id && c.data.contains_key(&entity));
+
+        if held {
+            self.trigger(type_id, Lifecycle::Remove, entity);
+
+            if let Some(component) = self.components.iter_mut().find(|c| c.type_id == type_id) {
+                component.remove_entity(&entity);
+                self.changes.record(entity);
+
+                if component.data.storage_type() == StorageType::Table {
+                    let archetype = self.archetypes.toggle(entity, type_id, false);
+                    self.relocate(entity, archetype);
+                }
+            }
+        }
+
+        held
+    }
+
+    /// Moves every table component of an entity into the columns of its archetype.
+    fn relocate(&mut self, entity: Entity, archetype: component::ArchetypeId) {
+        for component in self.components.iter_mut() {
+            component.data.relocate(&entity, archetype);
+        }
+    }
+
+    /// Reassigns every entity to the archetype matching the table components it holds.
+    ///
+    /// Used after component data has been changed wholesale (e.g. when restoring a snapshot).
+    fn rebuild_archetypes(&mut self) {
+        self.archetypes = Archetypes::default();
+
+        for entity in self.entities.clone() {
+            let types = self
+                .components
+                .iter()
+                .filter(|c| {
+                    c.data.storage_type() == StorageType::Table && c.data.contains_key(&entity)
+                })
+                .map(|c| c.type_id)
+                .collect();
+
+            let archetype = self.archetypes.set(entity, types);
+            self.relocate(entity, archetype);
+        }
+    }
+
+    /// Chooses how components of type `T` are stored.
+    ///
+    /// Components use [`StorageType::Table`] unless told otherwise, which suits data that is
+    /// iterated every tick. Markers that are frequently added and removed should use
+    /// [`StorageType::SparseSet`] instead; tags added with [`add_tag`] do so automatically.
+    /// Components that are already stored are moved into the new storage.
+    ///
+    /// [`add_tag`]: Self::add_tag()
+    pub fn set_storage<T: Any + 'static>(&mut self, storage_type: StorageType) {
+        let type_id = TypeId::of::<T>();
+        match self.components.iter_mut().find(|c| c.type_id == type_id) {
+            Some(component) if component.data.storage_type() != storage_type => {
+                component.data.convert(storage_type, &self.archetypes);
+                self.rebuild_archetypes();
+            }
+            Some(_) => {}
+            None => self
+                .components
+                .push(Component::new(type_id).with_storage(storage_type)),
+        }
+    }
+
+    /// Removes an entity along with all of its components from the world.
+    ///
+    /// Any [`on_remove`] hooks are called for each component before it is removed. Afterwards,
+    /// every [`Relation`] targeting the entity is cleaned up according to its [`OnDespawn`]
+    /// policy.
+    ///
+    /// [`on_remove`]: Self::on_remove()
+    /// [`OnDespawn`]: crate::relation::OnDespawn
+    pub fn despawn(&mut self, entity: Entity) {
+        let held = self
+            .components
+            .iter()
+            .filter(|c| c.data.contains_key(&entity))
+            .map(|c| c.type_id)
+            .collect::<Vec<_>>();
+
+        for type_id in held {
+            self.remove_component_by_id(type_id, entity);
+        }
+
+        self.entities.retain(|e| *e != entity);
+        self.archetypes.remove(&entity);
+        self.despawn_relations(entity);
+    }
+
+    /// Returns whether an entity exists in the world (i.e. it has been spawned and not despawned).
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
     }
 
     /// Registers a fixed system with the world.
@@ -172,7 +648,13 @@ impl<'a> World<'a> {
     ///
     /// [`tick`]: Self::tick()
     pub fn add_fixed_system<T: SystemInterface + 'a>(&mut self, system: T) {
-        self.fixed_systems.push(Box::new(system));
+        let label = system
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("fixed system {}", self.fixed_systems.len()));
+        let stream = self.rng_stream(system.name());
+        self.fixed_systems
+            .push(ScheduledSystem::new(Box::new(system), label, stream));
     }
 
     /// Registers a dependent system with the world.
@@ -180,7 +662,298 @@ impl<'a> World<'a> {
     /// Dependent systems are ran once per game tick and are intended to handle all functionality
     /// that is non-deterministic. For GUI applications, this will typically be ran once per frame.
     pub fn add_dependent_system<T: SystemInterface + 'a>(&mut self, system: T) {
-        self.dependent_systems.push(Box::new(system));
+        let label = system
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("dependent system {}", self.dependent_systems.len()));
+        let stream = self.rng_stream(system.name());
+        self.dependent_systems
+            .push(ScheduledSystem::new(Box::new(system), label, stream));
+    }
+
+    /// Enables or disables every fixed and dependent system with a particular name, returning
+    /// whether any system has that name.
+    ///
+    /// Disabled systems are skipped by [`tick`] until enabled again. Enabling a system that was
+    /// disabled by [`ErrorPolicy::Disable`] also resets its count of failures.
+    ///
+    /// [`tick`]: Self::tick()
+    /// [`ErrorPolicy::Disable`]: crate::system::ErrorPolicy::Disable
+    pub fn set_system_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for system in self
+            .fixed_systems
+            .iter_mut()
+            .chain(self.dependent_systems.iter_mut())
+            .filter(|s| s.label == name)
+        {
+            system.enabled = enabled;
+            if enabled {
+                system.failures = 0;
+            }
+            found = true;
+        }
+
+        found
+    }
+
+    /// Adds a [`Plugin`] to the world, allowing it to register its systems and resources.
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) {
+        plugin.build(self);
+    }
+
+    /// Inserts a resource into the world, replacing any resource of the same type.
+    ///
+    /// Resources hold global data that does not belong to any particular entity (e.g. gravity or
+    /// the elapsed time) and are accessible to every system through [`Query::resource`]. The
+    /// resource is stored as a [`Arc<Mutex<T>>`] under the hood. Resources must be [`Send`] and
+    /// [`Sync`] so that the [`Snapshot`]s capturing them can be sent to other threads; resources
+    /// that aren't are inserted with [`insert_non_send_resource`].
+    ///
+    /// [`Query::resource`]: crate::system::Query::resource()
+    /// [`Snapshot`]: crate::snapshot::Snapshot
+    /// [`insert_non_send_resource`]: Self::insert_non_send_resource()
+    pub fn insert_resource<T: Any + Clone + Send + Sync>(&mut self, data: T) {
+        self.resources.insert(data);
+    }
+
+    /// Shares a resource that may be used outside of the world as well.
+    ///
+    /// Operates the same as [`insert_resource`] except the [`Arc`] is generated by the user.
+    ///
+    /// [`insert_resource`]: Self::insert_resource()
+    pub fn share_resource<T: Any + Clone + Send + Sync>(&mut self, data: Arc<Mutex<T>>) {
+        self.resources.share(data);
+    }
+
+    /// Returns a resource of a particular type if it exists.
+    pub fn resource<T: Any + 'static>(&self) -> Option<Arc<Mutex<T>>> {
+        self.resources.get::<T>()
+    }
+
+    /// Inserts a resource that is neither [`Send`] nor [`Clone`] (e.g. a window or GPU surface),
+    /// replacing any non-send resource of the same type.
+    ///
+    /// Non-send resources are pinned to the thread the world was built on. Since the world itself
+    /// can't be sent to another thread, every system that reads them through
+    /// [`Query::non_send_resource`] is guaranteed to run on that thread. Under a [`ThreadedApp`],
+    /// that is the simulation thread rather than the main thread. They are skipped by snapshots
+    /// and saves.
+    ///
+    /// [`Query::non_send_resource`]: crate::system::Query::non_send_resource()
+    /// [`ThreadedApp`]: crate::os::ThreadedApp
+    pub fn insert_non_send_resource<T: Any + 'static>(&mut self, data: T) {
+        self.resources.share_non_send(Arc::new(Mutex::new(data)));
+    }
+
+    /// Shares a non-send resource that may be used outside of the world as well.
+    ///
+    /// See [`insert_non_send_resource`].
+    ///
+    /// [`insert_non_send_resource`]: Self::insert_non_send_resource()
+    pub fn share_non_send_resource<T: Any + 'static>(&mut self, data: Arc<Mutex<T>>) {
+        self.resources.share_non_send(data);
+    }
+
+    /// Returns a non-send resource of a particular type if it exists.
+    pub fn non_send_resource<T: Any + 'static>(&self) -> Option<Arc<Mutex<T>>> {
+        self.resources.get_non_send::<T>()
+    }
+
+    /// Removes a non-send resource of a particular type from the world, returning it if it
+    /// existed.
+    pub fn remove_non_send_resource<T: Any + 'static>(&mut self) -> Option<Arc<Mutex<T>>> {
+        self.resources.remove_non_send::<T>()
+    }
+
+    /// Removes a resource of a particular type from the world, returning it if it existed.
+    pub fn remove_resource<T: Any + 'static>(&mut self) -> Option<Arc<Mutex<T>>> {
+        self.resources.remove::<T>()
+    }
+
+    /// Calls a function with a reference to a component of type `T` on an entity, returning its
+    /// result if the entity holds such a component.
+    ///
+    /// Intended for inspecting the world from outside of systems (e.g. in hooks or tests); systems
+    /// should operate on their [`Query`] instead.
+    pub fn with_component<T: Any + 'static, R>(
+        &self,
+        entity: Entity,
+        f: impl FnOnce(&T) -> R,
+    ) -> Option<R> {
+        let data = self
+            .components
+            .iter()
+            .find(|c| c.type_id == TypeId::of::<T>())?
+            .data
+            .get(&entity)?
+            .lock()
+            .unwrap();
+
+        data.downcast_ref::<T>().map(f)
+    }
+
+    /// Returns the [`TypeRegistry`] holding every type that can be reflected by name.
+    pub fn registry(&self) -> &TypeRegistry {
+        &self.registry
+    }
+
+    /// Returns the [`TypeRegistry`] mutably so that new types may be registered.
+    pub fn registry_mut(&mut self) -> &mut TypeRegistry {
+        &mut self.registry
+    }
+
+    /// Registers a type with the world's [`TypeRegistry`] under a stable name.
+    ///
+    /// Once registered, components of the type can be read, written, and created by name through
+    /// methods such as [`get_reflect`] and [`insert_reflect`].
+    ///
+    /// [`get_reflect`]: Self::get_reflect()
+    /// [`insert_reflect`]: Self::insert_reflect()
+    pub fn register_type<T: Reflect + Clone + Default + Send + Sync>(&mut self, name: &str) {
+        self.registry.register::<T>(name);
+        self.adopt_cloner(TypeId::of::<T>());
+    }
+
+    /// Registers a type that can't be reflected with the world's [`TypeRegistry`] so that it is
+    /// printed with its [`Debug`](std::fmt::Debug) implementation by [`dump`].
+    ///
+    /// [`dump`]: Self::dump()
+    pub fn register_debug_type<T: Any + std::fmt::Debug>(&mut self, name: &str) {
+        self.registry.register_debug::<T>(name);
+    }
+
+    /// Returns the names of all registered components held by an entity along with their values.
+    pub fn reflect_entity(&self, entity: Entity) -> Vec<(String, Value)> {
+        self.components
+            .iter()
+            .filter_map(|component| {
+                let registration = self.registry.get_by_id(component.type_id)?;
+                let data = component.data.get(&entity)?.lock().unwrap();
+
+                registration
+                    .reflect(&*data)
+                    .map(|data| (registration.name().to_string(), data.to_value()))
+            })
+            .collect()
+    }
+
+    /// Returns the names of all registered resources along with their values.
+    pub fn reflect_resources(&self) -> Vec<(String, Value)> {
+        self.resources
+            .iter_erased()
+            .filter_map(|(type_id, data)| {
+                let registration = self.registry.get_by_id(*type_id)?;
+                let data = data.lock().unwrap();
+
+                registration
+                    .reflect(&*data)
+                    .map(|data| (registration.name().to_string(), data.to_value()))
+            })
+            .collect()
+    }
+
+    /// Reads a field of a registered component on an entity.
+    ///
+    /// The component is referred to by the name it was registered with and the field by a
+    /// `.`-separated path (e.g. `translation.x`). An empty path reads the whole component.
+    pub fn get_reflect(&self, entity: Entity, name: &str, path: &str) -> Result<Value, ReflectError> {
+        self.with_reflect(entity, name, |data| data.get_path(path))
+    }
+
+    /// Writes a field of a registered component on an entity.
+    ///
+    /// See [`get_reflect`] for how components and fields are addressed. Writing a field counts as
+    /// replacing the component, so its [`Remove`] hooks and observers run before the write and its
+    /// [`Insert`] ones after it, keeping indices such as [`Names`] up to date.
+    ///
+    /// [`get_reflect`]: Self::get_reflect()
+    /// [`Remove`]: Lifecycle::Remove
+    /// [`Insert`]: Lifecycle::Insert
+    /// [`Names`]: entity::Names
+    pub fn set_reflect(
+        &mut self,
+        entity: Entity,
+        name: &str,
+        path: &str,
+        value: &Value,
+    ) -> Result<(), ReflectError> {
+        let type_id = self
+            .registry
+            .get(name)
+            .ok_or_else(|| ReflectError::UnknownType(name.to_string()))?
+            .type_id();
+        self.with_reflect(entity, name, |_| Ok(()))?;
+
+        self.trigger(type_id, Lifecycle::Remove, entity);
+        let result = self.with_reflect(entity, name, |data| data.set_path(path, value));
+
+        if let Some(component) = self.components.iter_mut().find(|c| c.type_id == type_id) {
+            component.touch();
+        }
+        self.trigger(type_id, Lifecycle::Insert, entity);
+
+        result
+    }
+
+    /// Creates a registered component from a [`Value`] and adds it to an entity.
+    ///
+    /// Fields missing from the value keep the default of the registered type.
+    pub fn insert_reflect(
+        &mut self,
+        entity: Entity,
+        name: &str,
+        value: &Value,
+    ) -> Result<(), ReflectError> {
+        let (registration, data) = self.create_reflect(name, value)?;
+        registration.insert(self, entity, data);
+        Ok(())
+    }
+
+    /// Creates a registered resource from a [`Value`] and inserts it into the world.
+    pub fn insert_reflect_resource(&mut self, name: &str, value: &Value) -> Result<(), ReflectError> {
+        let (registration, data) = self.create_reflect(name, value)?;
+        registration.insert_resource(self, data);
+        Ok(())
+    }
+
+    /// Creates a registered component or resource from a [`Value`] without adding it to the
+    /// world.
+    pub(crate) fn create_reflect(
+        &self,
+        name: &str,
+        value: &Value,
+    ) -> Result<(TypeRegistration, Box<dyn Reflect>), ReflectError> {
+        let registration = self
+            .registry
+            .get(name)
+            .ok_or_else(|| ReflectError::UnknownType(name.to_string()))?
+            .clone();
+        let data = registration.from_value(value)?;
+        Ok((registration, data))
+    }
+
+    fn with_reflect<R>(
+        &self,
+        entity: Entity,
+        name: &str,
+        f: impl FnOnce(&mut dyn Reflect) -> Result<R, ReflectError>,
+    ) -> Result<R, ReflectError> {
+        let registration = self
+            .registry
+            .get(name)
+            .ok_or_else(|| ReflectError::UnknownType(name.to_string()))?;
+        let data = self
+            .components
+            .iter()
+            .find(|component| component.type_id == registration.type_id())
+            .and_then(|component| component.data.get(&entity))
+            .ok_or_else(|| ReflectError::MissingComponent(name.to_string()))?;
+        let mut data = data.lock().unwrap();
+
+        f(registration
+            .reflect_mut(&mut *data)
+            .expect("registered type mismatch"))
     }
 }
 
@@ -191,12 +964,30 @@ impl<'a> World<'a> {
 /// tick's timestep.
 pub struct WorldBuilder {
     frequency: u16,
+    error_policy: ErrorPolicy,
+    diagnostics_interval: Option<Duration>,
+    seed: Option<u64>,
+    registry: TypeRegistry,
+    tags: Vec<fn(&mut World<'_>)>,
+    plugins: Vec<Box<dyn Plugin>>,
+    #[cfg(feature = "scene")]
+    scene: Option<scene::Scene>,
 }
 
 impl Default for WorldBuilder {
     /// Creates a world with a fixed frequency of 60 Hz.
     fn default() -> Self {
-        WorldBuilder { frequency: 60 }
+        WorldBuilder {
+            frequency: 60,
+            error_policy: ErrorPolicy::default(),
+            diagnostics_interval: None,
+            seed: None,
+            registry: TypeRegistry::default(),
+            tags: Vec::new(),
+            plugins: Vec::new(),
+            #[cfg(feature = "scene")]
+            scene: None,
+        }
     }
 }
 
@@ -214,18 +1005,157 @@ impl WorldBuilder {
         self
     }
 
+    /// Chooses what happens when a system returns an error.
+    ///
+    /// Errors are logged without stopping the system by default.
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Prints the world's [`Diagnostics`] every time the given interval has passed.
+    ///
+    /// [`Diagnostics`]: crate::diagnostics::Diagnostics
+    pub fn with_diagnostics_logging(mut self, interval: Duration) -> Self {
+        self.diagnostics_interval = Some(interval);
+        self
+    }
+
+    /// Seeds the world's [`Rng`] so that every run draws the same random numbers.
+    ///
+    /// The world is seeded from the current time otherwise.
+    ///
+    /// [`Rng`]: crate::random::Rng
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Registers a type with the world's [`TypeRegistry`] under a stable name.
+    pub fn with_type<T: Reflect + Clone + Default + Send + Sync>(mut self, name: &str) -> Self {
+        self.registry.register::<T>(name);
+        self
+    }
+
+    /// Records that components of type `T` can be cloned, so that they are captured by snapshots
+    /// and copied by prefabs.
+    ///
+    /// See [`TypeRegistry::register_clone`].
+    pub fn with_clone<T: Any + Clone + Send + Sync>(mut self) -> Self {
+        self.registry.register_clone::<T>();
+        self
+    }
+
+    /// Indexes a tag type from the start so that [`World::tagged`] finds every entity holding it,
+    /// including those given the tag as a plain component (e.g. by a scene or save).
+    ///
+    /// Tags are otherwise indexed the first time they are added with [`World::add_tag`].
+    ///
+    /// [`World::tagged`]: crate::World::tagged()
+    /// [`World::add_tag`]: crate::World::add_tag()
+    pub fn with_tag<T: 'static>(mut self) -> Self {
+        self.tags.push(entity::index_tag::<T>);
+        self
+    }
+
+    /// Adds a [`Plugin`] that will set up the world once it is built.
+    ///
+    /// Plugins are built in the order they were added, before any scene is instantiated.
+    pub fn with_plugin<P: Plugin + 'static>(mut self, plugin: P) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Populates the world with a [`Scene`] once it is built.
+    ///
+    /// Uses the fixed frequency of the scene if it specifies one. All components and resources
+    /// the scene refers to by name must be registered with [`with_type`] beforehand.
+    ///
+    /// [`Scene`]: crate::scene::Scene
+    /// [`with_type`]: Self::with_type()
+    #[cfg(feature = "scene")]
+    pub fn with_scene(mut self, scene: scene::Scene) -> Self {
+        if let Some(frequency) = scene.frequency {
+            self.frequency = frequency;
+        }
+
+        self.scene = Some(scene);
+        self
+    }
+
+    /// Generates a new world based on the prior configuration, failing if the scene it was given
+    /// cannot be instantiated.
+    #[cfg(feature = "scene")]
+    pub fn try_build<'a>(mut self) -> Result<World<'a>, scene::SceneError> {
+        let scene = self.scene.take();
+        let mut world = self.build();
+
+        if let Some(scene) = scene {
+            scene.instantiate(&mut world)?;
+        }
+
+        Ok(world)
+    }
+
     /// Generates a new world based on the prior configuration.
+    ///
+    /// The world starts out with a [`Diagnostics`], a [`TaskPool`], an [`Rng`], and a [`Console`]
+    /// resource.
+    ///
+    /// Panics if the world was given a scene that cannot be instantiated; see [`try_build`] for a
+    /// fallible alternative.
+    ///
+    /// [`try_build`]: Self::try_build()
     pub fn build<'a>(self) -> World<'a> {
-        World {
+        #[cfg(feature = "scene")]
+        if self.scene.is_some() {
+            return self.try_build().expect("Couldn't instantiate scene");
+        }
+
+        let (command_sender, commands) = mpsc::channel();
+        let mut world = World {
             entities: Vec::new(),
             components: Vec::new(),
+            archetypes: Archetypes::default(),
+            resources: Resources::new(),
+            changes: Changes::default(),
+            captures: RefCell::new(HashMap::new()),
+            error_policy: self.error_policy,
             fixed_systems: Vec::new(),
             dependent_systems: Vec::new(),
+            registry: self.registry,
+            observers: Observers::new(),
+            states: StateMachines::default(),
             period: 1.0 / f32::from(self.frequency),
-            current_id: 0,
+            current_id: Arc::new(AtomicU32::new(0)),
+            commands,
+            command_sender,
+            applying_commands: false,
             previous_time: Instant::now(),
             accumulator: 0.0,
+            paused: false,
+            time_scale: 1.0,
             phantom: std::marker::PhantomData,
+        };
+
+        world.index_names();
+        world.index_tags();
+        for index_tag in self.tags {
+            index_tag(&mut world);
         }
+        world.register_relation::<ChildOf>(OnDespawn::Despawn);
+
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.set_log_interval(self.diagnostics_interval);
+        world.insert_resource(diagnostics);
+        world.insert_resource(TaskPool::new(world.handle()));
+        world.insert_resource(self.seed.map(Rng::new).unwrap_or_else(Rng::from_time));
+        world.insert_resource(Console::new());
+
+        for plugin in self.plugins {
+            plugin.build(&mut world);
+        }
+
+        world
     }
 }
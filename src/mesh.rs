@@ -1,6 +1,8 @@
 use crate::{graphics::Renderable, texture::TextureBuilder};
 use bytemuck::NoUninit;
+use std::fmt;
 use std::io::{BufReader, Cursor};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU32, Ordering};
 use wgpu::util::DeviceExt;
 
@@ -54,6 +56,7 @@ pub(crate) struct InstanceInfo {
 
 impl InstanceInfo {
     pub(crate) fn new(device: &wgpu::Device, instances: Vec<Instance>) -> Self {
+        let _span = span!("create instance buffer", instances = instances.len());
         let instance_count = instances.len();
 
         let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
@@ -73,6 +76,13 @@ impl InstanceInfo {
 
     pub(crate) fn append(&mut self, device: &wgpu::Device, instance: Instance) {
         self.instances.push(instance);
+        self.write_buffer(device);
+        self.instance_count += 1;
+        self.instance_buffer_size += 1;
+    }
+
+    pub(crate) fn write_buffer(&mut self, device: &wgpu::Device) {
+        let _span = span!("recreate instance buffer", instances = self.instances.len());
         let instance_data = self
             .instances
             .iter()
@@ -88,8 +98,6 @@ impl InstanceInfo {
         // TODO: destroy?
 
         self.instance_buffer = instance_buffer;
-        self.instance_count += 1;
-        self.instance_buffer_size += 1;
     }
 
     pub(crate) fn remove(&mut self, id: InstanceId) {
@@ -153,6 +161,12 @@ impl Instance {
         self.rotation *= rotation;
     }
 
+    /// Gives the instance an id of its own so that it is drawn separately from the instance it was
+    /// copied from.
+    pub(crate) fn renew_id(&mut self) {
+        self.id = InstanceId(INSTANCES.fetch_add(1, Ordering::SeqCst));
+    }
+
     pub(crate) fn to_raw(&self) -> InstanceRaw {
         InstanceRaw {
             model: (self.translation.to_homogeneous()
@@ -173,11 +187,36 @@ pub struct Model {
     pub texture_builder: Option<TextureBuilder>,
 }
 
-fn load_res(file_name: &str) -> String {
-    let path = std::path::Path::new(env!("OUT_DIR"))
+/// An error produced while loading a model or texture from the res folder.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Io(PathBuf, std::io::Error),
+    /// The file was read but doesn't describe something that can be loaded (e.g. a malformed obj
+    /// file or an obj file holding more than one mesh).
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "couldn't read {}: {}", path.display(), e),
+            LoadError::Invalid(path, e) => write!(f, "couldn't load {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+pub(crate) fn res_path(file_name: &str) -> PathBuf {
+    std::path::Path::new(env!("OUT_DIR"))
         .join("res")
-        .join(file_name);
-    std::fs::read_to_string(&path).expect(&format!("Failed to read file: {:#?}", &path))
+        .join(file_name)
+}
+
+fn load_res(file_name: &str) -> Result<String, LoadError> {
+    let path = res_path(file_name);
+    std::fs::read_to_string(&path).map_err(|e| LoadError::Io(path, e))
 }
 
 impl Model {
@@ -191,8 +230,24 @@ impl Model {
     ///
     /// The obj file should be located in the res folder, which will need to be in the build
     /// directory of the program since the filename is relative to the build directory.
+    ///
+    /// Panics if the file can't be loaded; see [`try_from_obj`] for a fallible alternative.
+    ///
+    /// [`try_from_obj`]: Self::try_from_obj()
     pub fn from_obj(file_name: &str) -> Self {
-        let obj_text = load_res(file_name);
+        Self::try_from_obj(file_name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Creates a mesh from an obj file, returning an error if the file is missing or doesn't hold
+    /// exactly one textured mesh.
+    ///
+    /// See [`from_obj`] for where the file is looked for.
+    ///
+    /// [`from_obj`]: Self::from_obj()
+    pub fn try_from_obj(file_name: &str) -> Result<Self, LoadError> {
+        let _span = span!("load obj", file = file_name);
+        let obj_text = load_res(file_name)?;
+        let invalid = |e: String| LoadError::Invalid(res_path(file_name), e);
 
         let obj_cursor = Cursor::new(obj_text);
         let mut obj_reader = BufReader::new(obj_cursor);
@@ -205,11 +260,23 @@ impl Model {
                 ..Default::default()
             },
             move |p| {
-                let mat_text = load_res(p.to_str().unwrap());
+                let mat_text = p
+                    .to_str()
+                    .and_then(|p| load_res(p).ok())
+                    .ok_or(tobj::LoadError::OpenFileFailed)?;
                 tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text)))
             },
         )
-        .unwrap();
+        .map_err(|e| invalid(e.to_string()))?;
+
+        if models.len() != 1 {
+            // TODO: handle multiple meshes
+            return Err(invalid(format!("expected one mesh but found {}", models.len())));
+        }
+
+        if models.iter().any(|m| m.mesh.texcoords.is_empty()) {
+            return Err(invalid("the mesh has no texture coordinates".to_string()));
+        }
 
         let mut meshes = models
             .into_iter()
@@ -232,14 +299,10 @@ impl Model {
             })
             .collect::<Vec<_>>();
 
-        if meshes.len() > 1 {
-            panic!("can't do multiple meshes");
-        }
-
-        Self {
-            mesh: meshes.pop().unwrap(), // TODO: handle multiple meshes
+        Ok(Self {
+            mesh: meshes.pop().unwrap(),
             texture_builder: None
-        }
+        })
     }
 }
 
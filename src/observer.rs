@@ -0,0 +1,228 @@
+use crate::{
+    system::{QueryCache, SystemInterface},
+    Entity, Query, World,
+};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A point in the lifecycle of a component on a particular entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Lifecycle {
+    /// The component was added to an entity that did not previously hold a component of its type.
+    Add,
+    /// The component was added to an entity, either for the first time or replacing an existing
+    /// component of the same type.
+    Insert,
+    /// The component is about to be removed from an entity, either because it was removed, the
+    /// entity was despawned, or it is about to be replaced.
+    Remove,
+}
+
+pub(crate) type Hook<'a> = Rc<dyn Fn(&mut World<'a>, Entity) + 'a>;
+
+/// The hooks and observers registered with a world, keyed by the component type and lifecycle
+/// event that triggers them.
+pub(crate) struct Observers<'a> {
+    hooks: HashMap<(TypeId, Lifecycle), Vec<Hook<'a>>>,
+    systems: HashMap<(TypeId, Lifecycle), Vec<Observer<'a>>>,
+}
+
+/// An observer system along with its random stream (see [`World::rng_stream`]).
+///
+/// [`World::rng_stream`]: crate::World::rng_stream()
+struct Observer<'a> {
+    system: Box<dyn SystemInterface + 'a>,
+    stream: Option<String>,
+}
+
+impl<'a> Observers<'a> {
+    pub(crate) fn new() -> Self {
+        Self {
+            hooks: HashMap::new(),
+            systems: HashMap::new(),
+        }
+    }
+
+    /// Returns every observer system.
+    pub(crate) fn systems(&self) -> impl Iterator<Item = &(dyn SystemInterface + 'a)> {
+        self.systems
+            .values()
+            .flatten()
+            .map(|observer| &*observer.system)
+    }
+}
+
+impl<'a> World<'a> {
+    /// Registers a hook that is called whenever a component of type `T` is added to an entity
+    /// that did not previously hold one.
+    ///
+    /// Hooks are given full access to the world and are called immediately, which makes them
+    /// suitable for keeping invariants (e.g. indices) up to date. See [`Lifecycle`] for the exact
+    /// moment each hook is called.
+    pub fn on_add<T: 'static>(&mut self, hook: impl Fn(&mut World<'a>, Entity) + 'a) {
+        self.add_hook::<T>(Lifecycle::Add, hook);
+    }
+
+    /// Registers a hook that is called whenever a component of type `T` is added to an entity,
+    /// including when it replaces an existing component.
+    pub fn on_insert<T: 'static>(&mut self, hook: impl Fn(&mut World<'a>, Entity) + 'a) {
+        self.add_hook::<T>(Lifecycle::Insert, hook);
+    }
+
+    /// Registers a hook that is called right before a component of type `T` is removed from an
+    /// entity, including when it is about to be replaced.
+    ///
+    /// The component can still be accessed while the hook runs.
+    pub fn on_remove<T: 'static>(&mut self, hook: impl Fn(&mut World<'a>, Entity) + 'a) {
+        self.add_hook::<T>(Lifecycle::Remove, hook);
+    }
+
+    fn add_hook<T: 'static>(&mut self, event: Lifecycle, hook: impl Fn(&mut World<'a>, Entity) + 'a) {
+        self.observers
+            .hooks
+            .entry((TypeId::of::<T>(), event))
+            .or_default()
+            .push(Rc::new(hook));
+    }
+
+    /// Registers an observer system that runs whenever a lifecycle event occurs for a component
+    /// of type `T`.
+    ///
+    /// Observers run immediately after the hooks for the same event. Their query only ever
+    /// matches the entity that triggered the event, and only if it holds every component the
+    /// system operates on. An observer that operates on no components always matches it.
+    pub fn add_observer<T: 'static, S: SystemInterface + 'a>(&mut self, event: Lifecycle, system: S) {
+        let stream = self.rng_stream(system.name());
+        self.observers
+            .systems
+            .entry((TypeId::of::<T>(), event))
+            .or_default()
+            .push(Observer {
+                system: Box::new(system),
+                stream,
+            });
+    }
+
+    /// Calls every hook and observer registered for a lifecycle event.
+    pub(crate) fn trigger(&mut self, type_id: TypeId, event: Lifecycle, entity: Entity) {
+        let key = (type_id, event);
+
+        if let Some(hooks) = self.observers.hooks.get(&key).cloned() {
+            for hook in hooks {
+                hook(self, entity);
+            }
+        }
+
+        if let Some(systems) = self.observers.systems.get_mut(&key) {
+            for Observer { system, stream } in systems.iter_mut() {
+                let cache = QueryCache::single(system.components(), entity, &self.components);
+                let query = Query::new(
+                    &mut self.components,
+                    &cache,
+                    &self.resources,
+                    stream.as_deref(),
+                );
+
+                if !query.entities.is_empty() {
+                    if let Err(error) = system.try_execute(query) {
+                        error!("observer failed: {}", error);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lifecycle;
+    use crate::{type_ids, Entity, Query, System, World, WorldBuilder};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Clone, Debug)]
+    struct Health(u32);
+
+    #[derive(Clone, Debug)]
+    struct Armor;
+
+    #[derive(Clone, Debug, Default)]
+    struct Observed(Vec<Entity>);
+
+    type Log = Rc<RefCell<Vec<(Lifecycle, Option<u32>)>>>;
+
+    /// Records every lifecycle event of `Health` along with the health the entity held.
+    fn record(world: &mut World, log: &Log) {
+        for event in [Lifecycle::Add, Lifecycle::Insert, Lifecycle::Remove] {
+            let log = log.clone();
+            let hook = move |world: &mut World, entity| {
+                let health = world.with_component(entity, |health: &Health| health.0);
+                log.borrow_mut().push((event, health));
+            };
+
+            match event {
+                Lifecycle::Add => world.on_add::<Health>(hook),
+                Lifecycle::Insert => world.on_insert::<Health>(hook),
+                Lifecycle::Remove => world.on_remove::<Health>(hook),
+            }
+        }
+    }
+
+    #[test]
+    fn hooks_follow_the_component_lifecycle() {
+        let mut world = WorldBuilder::new().build();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        record(&mut world, &log);
+
+        let entity = world.new_entity();
+        world.add_component(entity, Health(3));
+        assert_eq!(
+            log.take(),
+            [(Lifecycle::Add, Some(3)), (Lifecycle::Insert, Some(3))]
+        );
+
+        world.add_component(entity, Health(5));
+        assert_eq!(
+            log.take(),
+            [(Lifecycle::Remove, Some(3)), (Lifecycle::Insert, Some(5))]
+        );
+
+        assert!(world.remove_component::<Health>(entity));
+        assert_eq!(log.take(), [(Lifecycle::Remove, Some(5))]);
+
+        world.add_component(entity, Health(1));
+        log.take();
+        world.despawn(entity);
+        assert_eq!(log.take(), [(Lifecycle::Remove, Some(1))]);
+    }
+
+    #[test]
+    fn observers_only_see_the_triggering_entity() {
+        fn observe(query: Query) {
+            let observed = query.resource::<Observed>().unwrap();
+            observed.lock().unwrap().0.extend(&query.entities);
+        }
+
+        let mut world = WorldBuilder::new().build();
+        world.insert_resource(Observed::default());
+        world.add_observer::<Health, _>(
+            Lifecycle::Add,
+            System::new(type_ids![Health, Armor], observe),
+        );
+
+        let unarmored = world.new_entity();
+        world.add_component(unarmored, Health(1));
+
+        let armored = world.new_entity();
+        world.add_component(armored, Armor);
+        world.add_component(armored, Health(2));
+
+        let other = world.new_entity();
+        world.add_component(other, Armor);
+        world.add_component(other, Health(3));
+        world.add_component(other, Health(4));
+
+        let observed = world.resource::<Observed>().unwrap();
+        assert_eq!(observed.lock().unwrap().0, [armored, other]);
+    }
+}
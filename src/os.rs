@@ -3,14 +3,19 @@ use winit::event::{DeviceEvent, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::{Window, WindowId};
 
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 
 use crate::{
     camera::Camera,
-    graphics::{Renderer, WgpuRenderer},
+    console::ConsolePrompt,
+    graphics::{RenderBuffer, RenderState, Renderer, WgpuRenderer},
     input::InputManager,
-    mesh::{Instance, Model},
-    system::ResourcedSystem,
+    mesh::{Instance, InstanceId, MeshId, Model},
+    observer::Lifecycle,
+    system::{BoxedError, Query, ResourcedSystem},
     World,
 };
 
@@ -19,6 +24,10 @@ use crate::{
 /// Responsible for managing the window, renderer, camera, user input, and world while 
 /// providing access to certain resourced systems that permit the management of the aforementioned
 /// resources.
+///
+/// Pressing the grave key (`` ` ``) opens the developer [`Console`] of the world.
+///
+/// [`Console`]: crate::console::Console
 pub struct App<'a, C, R = WgpuRenderer<'a>>
 where
     R: Renderer<'a>,
@@ -30,6 +39,9 @@ where
     pub world: World<'a>,
     input: Arc<Mutex<InputManager>>,
     camera: Arc<Mutex<C>>,
+    plugins: Vec<Box<dyn AppPlugin<'a, R> + 'a>>,
+    default_plugins: bool,
+    console: ConsolePrompt,
 }
 
 impl<'a, C: Camera + 'a> App<'a, C> {
@@ -41,16 +53,35 @@ impl<'a, C: Camera + 'a> App<'a, C> {
             renderer: None,
             camera,
             input: Arc::new(Mutex::new(InputManager::new())),
+            plugins: Vec::new(),
+            default_plugins: true,
+            console: ConsolePrompt::default(),
         }
     }
 
+    /// Adds an [`AppPlugin`] that will be built once the window and renderer have been created.
+    ///
+    /// Plugins are built in the order they were added, after the default plugins.
+    pub fn with_plugin<P: AppPlugin<'a, WgpuRenderer<'a>> + 'a>(mut self, plugin: P) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Prevents the default plugins ([`RenderPlugin`] and [`InputPlugin`]) from being added so
+    /// that they can be replaced.
+    pub fn without_default_plugins(mut self) -> Self {
+        self.default_plugins = false;
+        self
+    }
+
     /// Indicates the ready state of the app and launches it.
     ///
     /// Upon running, the app will create a new [`EventLoop`], which will propogate a resumed
     /// signal that should create the window and renderer allowing the app to be visible. At this
-    /// point, input will begin being managed and all entities with a [`Model`] and [`Instance`]
-    /// component will be rendered to the screen. Input can be handled through the [`InputManager`]
-    /// struct.
+    /// point, the window is inserted into the world as a non-send resource (as an [`Arc<Window>`])
+    /// and the plugins of the app are built. Unless disabled, the default plugins will begin
+    /// managing input and rendering all entities with a [`Model`] and [`Instance`] component to
+    /// the screen. Input can be handled through the [`InputManager`] struct.
     pub fn run(mut self) {
         let event_loop = EventLoop::new().expect("Couldn't create event loop");
         event_loop.set_control_flow(ControlFlow::Poll);
@@ -75,9 +106,11 @@ impl<'a, C: Camera + 'a> ApplicationHandler for App<'a, C> {
             .expect("no camera access")
             .resize(size.width as f32, size.height as f32);
 
-        self.renderer = Some(Arc::new(Mutex::new(futures::executor::block_on(
+        #[allow(clippy::arc_with_non_send_sync)]
+        let renderer = Arc::new(Mutex::new(futures::executor::block_on(
             WgpuRenderer::new(self.window.clone().unwrap(), self.camera.clone()),
-        ))));
+        )));
+        self.renderer = Some(renderer);
 
         let _ = self
             .window
@@ -85,60 +118,37 @@ impl<'a, C: Camera + 'a> ApplicationHandler for App<'a, C> {
             .unwrap()
             .set_cursor_grab(winit::window::CursorGrabMode::Locked); // TODO: X11
 
-        self.world.add_dependent_system(ResourcedSystem::new(
-            vec![
-                std::any::TypeId::of::<Model>(),
-                std::any::TypeId::of::<Instance>(),
-            ],
-            self.renderer.as_mut().unwrap().clone(),
-            |mut query, renderer| {
-                let instances = query.get_all::<Instance>();
+        self.world
+            .insert_non_send_resource(self.window.clone().unwrap());
 
-                query.all::<Model>(|models| {
-                    for (entity, model) in models {
-                        let instance: Instance = instances
-                            .get(&entity)
-                            .unwrap()
-                            .lock()
-                            .unwrap()
-                            .downcast_ref::<Instance>()
-                            .unwrap()
-                            .clone();
-                        renderer.lock().unwrap().attach(model, instance);
-                    }
-                });
-            },
-        ));
-
-        self.world.add_fixed_system(ResourcedSystem::new(
-            vec![std::any::TypeId::of::<InputManager>()],
-            self.input.clone(),
-            |mut query, input| {
-                // TODO: just reference the same input manager :skull:
-                query.all::<InputManager>(|mut input_managers| {
-                    let input = input.lock().unwrap();
-                    let keys = &input.keys;
-                    let analog_input = input.analog_input;
-
-                    for (_, input_manager) in input_managers.iter_mut() {
-                        input_manager.keys = keys.clone();
-                        input_manager.analog_input = analog_input;
-                    }
-                });
+        let context = AppContext {
+            window: self.window.clone().unwrap(),
+            renderer: self.renderer.clone().unwrap(),
+            input: self.input.clone(),
+        };
 
-                input.lock().unwrap().analog_input = (0.0, 0.0);
-            },
-        ));
+        if self.default_plugins {
+            RenderPlugin.build(&mut self.world, &context);
+            InputPlugin.build(&mut self.world, &context);
+        }
+
+        for plugin in &self.plugins {
+            plugin.build(&mut self.world, &context);
+        }
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
         match event {
             WindowEvent::CloseRequested => {
-                println!("The close button was pressed; stopping");
+                info!("The close button was pressed; stopping");
                 event_loop.exit();
             }
             WindowEvent::RedrawRequested => {
-                self.world.tick();
+                if let Err(error) = self.world.tick() {
+                    error!("{}; stopping", error);
+                    event_loop.exit();
+                    return;
+                }
 
                 self.renderer.as_mut().unwrap().lock().unwrap().render();
 
@@ -153,26 +163,23 @@ impl<'a, C: Camera + 'a> ApplicationHandler for App<'a, C> {
                     .resize(physical_size);
             }
             WindowEvent::KeyboardInput {
-                device_id: _,
+                device_id,
                 event,
-                is_synthetic,
+                is_synthetic: false,
             } => {
-                if !is_synthetic && !event.repeat {
-                    if event.state == winit::event::ElementState::Pressed {
-                        self.input.lock().unwrap().keys.insert(event.physical_key);
-                    } else if event.state == winit::event::ElementState::Released {
-                        self.input.lock().unwrap().keys.remove(&event.physical_key);
-                    }
+                let handled = self
+                    .console
+                    .key(&mut self.world, self.window.as_ref().unwrap(), &event);
+                if !handled {
+                    let event = WindowEvent::KeyboardInput {
+                        device_id,
+                        event,
+                        is_synthetic: false,
+                    };
+                    record_window_input(&self.input, event);
                 }
             }
-            WindowEvent::MouseInput {
-                device_id: _,
-                state: _,
-                button: _,
-            } => {
-                // TODO: handle mouse input
-            }
-            _ => (),
+            event => record_window_input(&self.input, event),
         }
     }
 
@@ -182,23 +189,495 @@ impl<'a, C: Camera + 'a> ApplicationHandler for App<'a, C> {
         _device_id: winit::event::DeviceId,
         event: winit::event::DeviceEvent,
     ) {
-        // TODO: ensure wayland gets mouse events
-        match event {
-            DeviceEvent::Motion { axis, value } => {
-                // TODO: is there a better way to do this?
-                match axis {
-                    0 => {
-                        self.input.lock().unwrap().analog_input.0 = value as f32;
-                    }
-                    1 => {
-                        self.input.lock().unwrap().analog_input.1 = value as f32;
+        record_device_input(&self.input, event);
+    }
+}
+
+/// Records the keys pressed and released in a window.
+fn record_window_input(input: &Mutex<InputManager>, event: WindowEvent) {
+    match event {
+        WindowEvent::KeyboardInput {
+            device_id: _,
+            event,
+            is_synthetic,
+        } if !is_synthetic && !event.repeat => {
+            if event.state == winit::event::ElementState::Pressed {
+                input.lock().unwrap().keys.insert(event.physical_key);
+            } else if event.state == winit::event::ElementState::Released {
+                input.lock().unwrap().keys.remove(&event.physical_key);
+            }
+        }
+        WindowEvent::MouseInput {
+            device_id: _,
+            state: _,
+            button: _,
+        } => {
+            // TODO: handle mouse input
+        }
+        _ => (),
+    }
+}
+
+/// Records the motion of the mouse.
+fn record_device_input(input: &Mutex<InputManager>, event: DeviceEvent) {
+    // TODO: ensure wayland gets mouse events
+    if let DeviceEvent::Motion { axis, value } = event {
+        // TODO: is there a better way to do this?
+        match axis {
+            0 => {
+                input.lock().unwrap().analog_input.0 = value as f32;
+            }
+            1 => {
+                input.lock().unwrap().analog_input.1 = value as f32;
+            }
+            _ => {
+                panic!("unknown axis");
+            }
+        }
+    }
+}
+
+/// The resources of an [`App`] that are available to plugins once the window has been created.
+pub struct AppContext<R> {
+    /// The window the app is displayed in.
+    pub window: Arc<Window>,
+    /// The renderer drawing to the window.
+    pub renderer: Arc<Mutex<R>>,
+    /// The input collected from the window since the last fixed step.
+    pub input: Arc<Mutex<InputManager>>,
+}
+
+/// A plugin for GUI applications that requires access to the window or renderer.
+///
+/// Operates the same as a [`Plugin`] except that it is only built once the [`App`] has created its
+/// window and renderer, which happens when the app is first resumed.
+///
+/// [`Plugin`]: crate::plugin::Plugin
+pub trait AppPlugin<'a, R: Renderer<'a>> {
+    /// Called once the window and renderer are available in order to set up the world.
+    fn build(&self, world: &mut World<'a>, context: &AppContext<R>);
+}
+
+/// The default plugin that renders every entity with a [`Model`] and [`Instance`] every frame.
+///
+/// Also detaches instances from the renderer whenever their [`Model`] or [`Instance`] is removed
+/// or replaced.
+pub struct RenderPlugin;
+
+impl<'a, R: Renderer<'a> + 'a> AppPlugin<'a, R> for RenderPlugin {
+    fn build(&self, world: &mut World<'a>, context: &AppContext<R>) {
+        fn detach<'a, R: Renderer<'a>>(
+            mut query: Query,
+            renderer: &Arc<Mutex<R>>,
+        ) -> Result<(), BoxedError> {
+            let instances = query.get_all::<Instance>();
+            let mut renderer = renderer.lock().map_err(|_| "renderer lock poisoned")?;
+
+            let mut result = Ok(());
+            query.all::<Model>(|models| {
+                result = models.into_iter().try_for_each(|(entity, model)| {
+                    let instance = instances
+                        .get(&entity)
+                        .ok_or("model without an instance")?
+                        .lock()
+                        .map_err(|_| "instance lock poisoned")?;
+                    renderer.detach(
+                        model,
+                        instance
+                            .downcast_ref::<Instance>()
+                            .ok_or("instance of the wrong type")?,
+                    );
+                    Ok(())
+                });
+            });
+            result
+        }
+
+        let components = vec![
+            std::any::TypeId::of::<Model>(),
+            std::any::TypeId::of::<Instance>(),
+        ];
+        world.add_observer::<Model, _>(
+            Lifecycle::Remove,
+            ResourcedSystem::new(components.clone(), context.renderer.clone(), detach)
+                .with_name("detach model"),
+        );
+        world.add_observer::<Instance, _>(
+            Lifecycle::Remove,
+            ResourcedSystem::new(components, context.renderer.clone(), detach)
+                .with_name("detach instance"),
+        );
+
+        world.add_dependent_system(ResourcedSystem::new(
+            vec![
+                std::any::TypeId::of::<Model>(),
+                std::any::TypeId::of::<Instance>(),
+            ],
+            context.renderer.clone(),
+            |mut query, renderer| -> Result<(), BoxedError> {
+                let instances = query.get_all::<Instance>();
+                let mut renderer = renderer.lock().map_err(|_| "renderer lock poisoned")?;
+
+                let mut result = Ok(());
+                query.all::<Model>(|models| {
+                    result = models.into_iter().try_for_each(|(entity, model)| {
+                        let instance: Instance = instances
+                            .get(&entity)
+                            .ok_or("model without an instance")?
+                            .lock()
+                            .map_err(|_| "instance lock poisoned")?
+                            .downcast_ref::<Instance>()
+                            .ok_or("instance of the wrong type")?
+                            .clone();
+                        renderer.attach(model, instance);
+                        Ok(())
+                    });
+                });
+                result
+            },
+        )
+        .with_name("render"));
+    }
+}
+
+/// The default plugin that copies the input collected by the window into every [`InputManager`]
+/// component on each fixed step.
+pub struct InputPlugin;
+
+impl<'a, R: Renderer<'a>> AppPlugin<'a, R> for InputPlugin {
+    fn build(&self, world: &mut World<'a>, context: &AppContext<R>) {
+        world.add_fixed_system(input_system(context.input.clone()));
+    }
+}
+
+/// Creates the system that copies the input collected by the window into every [`InputManager`].
+fn input_system(
+    input: Arc<Mutex<InputManager>>,
+) -> ResourcedSystem<Arc<Mutex<InputManager>>, Result<(), BoxedError>> {
+    ResourcedSystem::new(
+        vec![std::any::TypeId::of::<InputManager>()],
+        input,
+        |mut query, input| -> Result<(), BoxedError> {
+            let mut input = input.lock().map_err(|_| "input lock poisoned")?;
+
+            // TODO: just reference the same input manager :skull:
+            query.all::<InputManager>(|mut input_managers| {
+                for (_, input_manager) in input_managers.iter_mut() {
+                    input_manager.keys = input.keys.clone();
+                    input_manager.analog_input = input.analog_input;
+                }
+            });
+
+            input.analog_input = (0.0, 0.0);
+            Ok(())
+        },
+    )
+    .with_name("input")
+}
+
+/// A GUI application that runs its world on a dedicated simulation thread.
+///
+/// Operates like an [`App`] except that ticking the world never blocks the window: the simulation
+/// thread ticks the world after each of its fixed steps and whenever the renderer asks for a new
+/// frame, publishing the state needed to draw a frame (every entity with a [`Model`] and
+/// [`Instance`] along with the camera) at the end of each tick. Dependent systems therefore run as
+/// often as frames are drawn, as long as the simulation keeps up. The event loop keeps handling
+/// input and drawing the most recently published frame at the rate of the display, so the window
+/// stays responsive even when the simulation runs slower than the display.
+///
+/// Since worlds can't be sent between threads, the world is created on the simulation thread by a
+/// function handed to [`new`], which is called once the window exists. The window itself stays on
+/// the event loop's thread, so unlike in an [`App`] it is not available to the world as a
+/// resource; only input crosses over, being copied into every [`InputManager`] on each fixed step
+/// as with the default plugins of an [`App`]. [`AppPlugin`]s
+/// are not supported because the renderer lives on the event loop's thread; [`Plugin`]s may be
+/// added to the world when it is built instead.
+///
+/// For the same reason, non-send resources are pinned to the simulation thread rather than the
+/// main thread, and systems using them run there. Resources that must be used on the main thread
+/// (e.g. anything that touches the window or the event loop) are not supported.
+///
+/// The camera is shared between the two threads: the simulation moves it and every published
+/// frame carries a copy of it, which the renderer draws the frame through.
+///
+/// [`new`]: Self::new()
+/// [`Plugin`]: crate::plugin::Plugin
+pub struct ThreadedApp<C>
+where
+    C: Camera + Clone + Send + 'static,
+{
+    build: Option<Box<dyn FnOnce() -> World<'static> + Send>>,
+    window: Option<Arc<Window>>,
+    renderer: Option<WgpuRenderer<'static>>,
+    input: Arc<Mutex<InputManager>>,
+    camera: Arc<Mutex<C>>,
+    render_camera: Option<Arc<Mutex<C>>>,
+    frames: Arc<RenderBuffer<C>>,
+    frame: RenderState<C>,
+    attached: HashMap<InstanceId, (Arc<Model>, Instance)>,
+    running: Arc<AtomicBool>,
+    simulation: Option<JoinHandle<()>>,
+}
+
+impl<C: Camera + Clone + Send + 'static> ThreadedApp<C> {
+    /// Creates an app whose world is built by a function on the simulation thread and which is
+    /// seen through a camera.
+    pub fn new<F>(build: F, camera: Arc<Mutex<C>>) -> Self
+    where
+        F: FnOnce() -> World<'static> + Send + 'static,
+    {
+        Self {
+            build: Some(Box::new(build)),
+            window: None,
+            renderer: None,
+            input: Arc::new(Mutex::new(InputManager::new())),
+            camera,
+            render_camera: None,
+            frames: Arc::new(RenderBuffer::new()),
+            frame: RenderState::default(),
+            attached: HashMap::new(),
+            running: Arc::new(AtomicBool::new(true)),
+            simulation: None,
+        }
+    }
+
+    /// Indicates the ready state of the app and launches it.
+    ///
+    /// The simulation thread is started once the window has been created and is stopped when the
+    /// window is closed. If the world stops because of an error, the window is closed as well.
+    pub fn run(mut self) {
+        let event_loop = EventLoop::new().expect("Couldn't create event loop");
+        event_loop.set_control_flow(ControlFlow::Poll);
+
+        event_loop.run_app(&mut self).expect("Couldn't run app");
+        self.stop();
+    }
+
+    /// Asks the simulation thread to stop and waits for it to finish its current tick.
+    fn stop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(simulation) = self.simulation.take() {
+            if simulation.join().is_err() {
+                error!("The simulation thread panicked");
+            }
+        }
+    }
+
+    /// Starts ticking the world on its own thread.
+    fn spawn_simulation(&mut self) {
+        let build = self.build.take().expect("simulation already started");
+        let stale = Arc::new(Mutex::new(HashSet::new()));
+        let publisher = Publisher {
+            frames: self.frames.clone(),
+            camera: self.camera.clone(),
+            models: Mutex::new(HashMap::new()),
+            stale: stale.clone(),
+            state: Mutex::new(RenderState::default()),
+        };
+        let frames = self.frames.clone();
+        let input = self.input.clone();
+        let running = self.running.clone();
+
+        let simulation = std::thread::Builder::new()
+            .name("simulation".to_string())
+            .spawn(move || {
+                let mut world = build();
+                world.on_insert::<Model>(move |world, entity| {
+                    if let Some(mesh) = world.with_component(entity, |model: &Model| model.mesh.id) {
+                        stale.lock().unwrap().insert(mesh);
                     }
-                    _ => {
-                        panic!("unknown axis");
+                });
+                world.add_fixed_system(input_system(input));
+                world.add_dependent_system(
+                    ResourcedSystem::new(
+                        vec![
+                            std::any::TypeId::of::<Model>(),
+                            std::any::TypeId::of::<Instance>(),
+                        ],
+                        publisher,
+                        publish,
+                    )
+                    .with_name("publish frame"),
+                );
+
+                // the world is ticked after every fixed step and whenever the renderer asks for a
+                // frame, so frames are published as often as they are drawn
+                let mut requests = 0;
+                while running.load(Ordering::Acquire) {
+                    if let Err(error) = world.tick() {
+                        error!("{}; stopping", error);
+                        break;
                     }
+
+                    frames.wait_for_request(&mut requests, world.time_until_step());
                 }
+
+                running.store(false, Ordering::Release);
+            })
+            .expect("Couldn't spawn simulation thread");
+
+        self.simulation = Some(simulation);
+    }
+
+    /// Draws the most recently published frame, detaching any instance that has disappeared since
+    /// the previous one.
+    fn render(&mut self) {
+        let renderer = self.renderer.as_mut().unwrap();
+
+        if self.frames.take(&mut self.frame) {
+            let mut previous = std::mem::take(&mut self.attached);
+            for (model, instance) in &self.frame.instances {
+                previous.remove(&instance.id);
+                self.attached
+                    .insert(instance.id, (model.clone(), instance.clone()));
+            }
+            for (model, instance) in previous.values() {
+                renderer.detach(&**model, instance);
+            }
+
+            if let (Some(camera), Some(render_camera)) =
+                (self.frame.camera.as_ref(), self.render_camera.as_ref())
+            {
+                *render_camera.lock().unwrap() = camera.clone();
             }
-            _ => {}
         }
+
+        for (model, instance) in &self.frame.instances {
+            renderer.attach(&**model, instance.clone());
+        }
+        renderer.render();
     }
 }
+
+impl<C: Camera + Clone + Send + 'static> ApplicationHandler for ThreadedApp<C> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+
+        let window = Arc::new(
+            event_loop
+                .create_window(Window::default_attributes())
+                .unwrap(),
+        );
+        window.set_cursor_visible(false);
+        let _ = window.set_cursor_grab(winit::window::CursorGrabMode::Locked); // TODO: X11
+
+        let size = window.inner_size();
+        let render_camera = {
+            let mut camera = self.camera.lock().expect("no camera access");
+            camera.resize(size.width as f32, size.height as f32);
+            Arc::new(Mutex::new(camera.clone()))
+        };
+
+        self.renderer = Some(futures::executor::block_on(WgpuRenderer::new(
+            window.clone(),
+            render_camera.clone(),
+        )));
+        self.render_camera = Some(render_camera);
+        self.window = Some(window);
+
+        self.spawn_simulation();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                info!("The close button was pressed; stopping");
+                self.stop();
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => {
+                if !self.running.load(Ordering::Acquire) {
+                    self.stop();
+                    event_loop.exit();
+                    return;
+                }
+
+                self.render();
+
+                self.window.as_ref().unwrap().request_redraw();
+            }
+            WindowEvent::Resized(physical_size) => {
+                // the simulation's camera is resized too so that the frames it publishes match
+                self.camera
+                    .lock()
+                    .unwrap()
+                    .resize(physical_size.width as f32, physical_size.height as f32);
+                self.renderer.as_mut().unwrap().resize(physical_size);
+            }
+            event => record_window_input(&self.input, event),
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: winit::event::DeviceId,
+        event: winit::event::DeviceEvent,
+    ) {
+        record_device_input(&self.input, event);
+    }
+}
+
+/// The resource of the system that publishes frames from the simulation thread.
+struct Publisher<C> {
+    frames: Arc<RenderBuffer<C>>,
+    camera: Arc<Mutex<C>>,
+    /// Every model in the last published frame, shared between frames so that meshes and
+    /// textures are only copied once.
+    models: Mutex<HashMap<MeshId, Arc<Model>>>,
+    /// The meshes whose models have been replaced since the last frame, which need to be copied
+    /// again.
+    stale: Arc<Mutex<HashSet<MeshId>>>,
+    /// The frame being filled in.
+    state: Mutex<RenderState<C>>,
+}
+
+/// Publishes every entity with a [`Model`] and [`Instance`] along with the camera as a frame.
+///
+/// Models are copied again when they are replaced and forgotten once no entity uses them. Changes
+/// made to a model in place are not published.
+fn publish<C: Camera + Clone>(query: Query, publisher: &Publisher<C>) -> Result<(), BoxedError> {
+    let mut models = publisher.models.lock().map_err(|_| "model lock poisoned")?;
+    let mut stale = publisher.stale.lock().map_err(|_| "model lock poisoned")?;
+    let mut published = HashMap::new();
+    let mut state = publisher.state.lock().map_err(|_| "frame lock poisoned")?;
+    state.instances.clear();
+
+    // both components are only read so that snapshots keep reusing their copies
+    let mut result = Ok(());
+    query.read_all::<Instance>(|instances| {
+        query.read_all::<Model>(|entities| {
+            result = entities.into_iter().try_for_each(|(entity, model)| {
+                let instance = (*instances
+                    .get(&entity)
+                    .ok_or("model without an instance")?)
+                .clone();
+                let model = published
+                    .entry(model.mesh.id)
+                    .or_insert_with(|| match models.remove(&model.mesh.id) {
+                        Some(previous) if !stale.contains(&model.mesh.id) => previous,
+                        _ => Arc::new(model.clone()),
+                    })
+                    .clone();
+
+                state.instances.push((model, instance));
+                Ok::<(), BoxedError>(())
+            });
+        });
+    });
+    result?;
+    *models = published;
+    stale.clear();
+
+    state.camera = Some(
+        publisher
+            .camera
+            .lock()
+            .map_err(|_| "camera lock poisoned")?
+            .clone(),
+    );
+    publisher.frames.publish(&mut state);
+    Ok(())
+}
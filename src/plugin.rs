@@ -0,0 +1,19 @@
+use crate::World;
+
+/// A reusable bundle of functionality that can be added to a world.
+///
+/// Plugins package the systems, resources, and registered types that make up a feature (e.g. a
+/// camera controller or a physics simulation) so that they can be shared between programs rather
+/// than copied into each of them. Plugins may be added through [`WorldBuilder::with_plugin`] or
+/// directly with [`World::add_plugin`].
+///
+/// For plugins that need access to the window or renderer of a GUI application, see
+/// [`AppPlugin`].
+///
+/// [`WorldBuilder::with_plugin`]: crate::WorldBuilder::with_plugin()
+/// [`World::add_plugin`]: crate::World::add_plugin()
+/// [`AppPlugin`]: crate::os::AppPlugin
+pub trait Plugin {
+    /// Called once when the plugin is added in order to set up the world.
+    fn build(&self, world: &mut World<'_>);
+}
@@ -0,0 +1,454 @@
+use crate::{
+    component::Cloner,
+    mesh::Instance,
+    reflect::{ReflectError, Value},
+    relation::{ChildOf, Relation, Relations, Retarget},
+    Entity, World,
+};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A component held by a prefab, ready to be copied onto every entity spawned from it.
+#[derive(Clone)]
+struct PrefabComponent {
+    type_id: TypeId,
+    cloner: Cloner,
+    data: Arc<dyn Any + Send + Sync>,
+    /// For relations captured from the world whose target was captured as well, the position of
+    /// the target among the entities spawned from the prefab (in the order they are spawned).
+    target: Option<(usize, Retarget)>,
+}
+
+/// An entity reached while capturing a prefab along with the descendants that were first reached
+/// through it.
+struct Captured {
+    entity: Entity,
+    children: Vec<Captured>,
+}
+
+/// A reusable description of an entity, its components, and its children, from which any number
+/// of copies can be spawned.
+///
+/// Prefabs are built up in code or captured from an existing entity with [`from_entity`], and
+/// spawned with [`World::spawn_prefab`]. Every component is cloned for each copy, so changing one
+/// copy never affects the others or the prefab. Any [`Instance`] is given an id of its own so that
+/// every copy is drawn separately.
+///
+/// ```
+/// # use aspeng::{Prefab, WorldBuilder};
+/// # #[derive(Clone, Default)]
+/// # struct Velocity {
+/// #     x: f32,
+/// # }
+/// # #[derive(Clone, Default)]
+/// # struct Trail;
+/// # #[derive(Clone)]
+/// # struct Position {
+/// #     x: f32,
+/// # }
+/// # let mut world = WorldBuilder::new().build();
+/// let ball = Prefab::new()
+///     .with(Velocity { x: 0.0 })
+///     .with_child(Prefab::new().with(Trail));
+///
+/// for x in 0..10 {
+///     world.spawn_prefab(&ball.clone().with(Position { x: x as f32 }));
+/// }
+/// ```
+///
+/// Prefabs may also be described in scene files, in which case they are spawned with
+/// [`Scene::spawn`](crate::scene::Scene::spawn()).
+///
+/// [`from_entity`]: Self::from_entity()
+/// [`World::spawn_prefab`]: crate::World::spawn_prefab()
+/// [`Instance`]: crate::mesh::Instance
+#[derive(Clone, Default)]
+pub struct Prefab {
+    components: Vec<PrefabComponent>,
+    children: Vec<Prefab>,
+}
+
+impl std::fmt::Debug for Prefab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Prefab")
+            .field("components", &self.components.len())
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Prefab {
+    /// Creates a prefab with no components or children.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a component to the prefab, replacing any component of the same type.
+    ///
+    /// Components of its type become clonable (see [`World::register_clone`]) in every world the
+    /// prefab is spawned into.
+    ///
+    /// [`World::register_clone`]: crate::World::register_clone()
+    pub fn with<T: Any + Clone + Send + Sync>(mut self, component: T) -> Self {
+        let type_id = TypeId::of::<T>();
+        self.components.retain(|c| c.type_id != type_id);
+        self.components.push(PrefabComponent {
+            type_id,
+            cloner: Cloner::of::<T>(),
+            data: Arc::new(component),
+            target: None,
+        });
+        self
+    }
+
+    /// Adds a child, which is spawned along with every copy of the prefab and related to it
+    /// with [`ChildOf`].
+    pub fn with_child(mut self, child: Prefab) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Returns the children of the prefab.
+    pub fn children(&self) -> &[Prefab] {
+        &self.children
+    }
+
+    /// Captures an entity and its children as a prefab.
+    ///
+    /// Components that aren't clonable (see [`World::register_clone`]) are skipped, as is the
+    /// relation of the entity to its own parent. Entities are captured once even if they are
+    /// reached again through a cycle of [`ChildOf`] relations. Relations between captured
+    /// entities point at the corresponding copies when the prefab is spawned, while relations to
+    /// other entities keep their target. Returns `None` if the entity doesn't exist.
+    ///
+    /// [`World::register_clone`]: crate::World::register_clone()
+    pub fn from_entity(world: &World<'_>, entity: Entity) -> Option<Prefab> {
+        if !world.contains(entity) {
+            return None;
+        }
+
+        let mut indices = HashMap::new();
+        let captured = Self::collect(world, entity, &mut indices);
+        let relations = world.resource::<Relations>();
+        let relations = relations.as_ref().map(|relations| relations.lock().unwrap());
+
+        Some(Self::capture(world, &captured, &indices, relations.as_deref()))
+    }
+
+    /// Numbers an entity and its descendants in the order they are spawned, skipping any entity
+    /// that has already been reached.
+    fn collect(world: &World<'_>, entity: Entity, indices: &mut HashMap<Entity, usize>) -> Captured {
+        indices.insert(entity, indices.len());
+
+        let mut children = Vec::new();
+        for child in world.children(entity) {
+            if !indices.contains_key(&child) {
+                children.push(Self::collect(world, child, indices));
+            }
+        }
+
+        Captured { entity, children }
+    }
+
+    fn capture(
+        world: &World<'_>,
+        captured: &Captured,
+        indices: &HashMap<Entity, usize>,
+        relations: Option<&Relations>,
+    ) -> Prefab {
+        let components = world
+            .components
+            .iter()
+            .filter(|c| c.type_id != TypeId::of::<Relation<ChildOf>>())
+            .filter_map(|c| {
+                let cloner = c.cloner?;
+                let data = c.data.get(&captured.entity)?;
+                let data = (cloner.freeze)(&*data.lock().unwrap());
+                let target = relations
+                    .and_then(|relations| relations.retarget(c.type_id))
+                    .and_then(|retarget| {
+                        let target = (retarget.get)(&*data)?;
+                        Some((*indices.get(&target)?, retarget))
+                    });
+
+                Some(PrefabComponent {
+                    type_id: c.type_id,
+                    cloner,
+                    data,
+                    target,
+                })
+            })
+            .collect();
+
+        let children = captured
+            .children
+            .iter()
+            .map(|child| Self::capture(world, child, indices, relations))
+            .collect();
+
+        Prefab {
+            components,
+            children,
+        }
+    }
+
+    /// Returns the number of entities spawned from the prefab.
+    fn len(&self) -> usize {
+        1 + self.children.iter().map(Prefab::len).sum::<usize>()
+    }
+}
+
+/// Prefabs registered under a name, held by the world as a resource.
+///
+/// Allows prefabs to be spawned by name (e.g. by the `spawn` command of the [`Console`]).
+///
+/// [`Console`]: crate::console::Console
+#[derive(Clone, Debug, Default)]
+pub struct Prefabs {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl Prefabs {
+    /// Returns the prefab registered under a name.
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Returns the name of every registered prefab.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs.keys().map(String::as_str)
+    }
+}
+
+impl World<'_> {
+    /// Spawns a copy of an entity, returning the copy.
+    ///
+    /// Every clonable component (see [`register_clone`]) is copied, including components that
+    /// were shared with the entity (the copy gets data of its own rather than sharing it too),
+    /// while other components are skipped. Children are copied along with the
+    /// entity, and a copy of a child is a child of the same parent. Returns `None` if the entity
+    /// doesn't exist.
+    ///
+    /// [`register_clone`]: Self::register_clone()
+    pub fn clone_entity(&mut self, entity: Entity) -> Option<Entity> {
+        let prefab = Prefab::from_entity(self, entity)?;
+        let copy = self.spawn_prefab(&prefab);
+        if let Some(parent) = self.parent(entity) {
+            self.add_child(parent, copy);
+        }
+
+        Some(copy)
+    }
+
+    /// Spawns an entity and its children from a prefab, returning the entity.
+    pub fn spawn_prefab(&mut self, prefab: &Prefab) -> Entity {
+        // every entity exists before any component is added so that relations between them can
+        // point at their targets right away
+        let entities = (0..prefab.len()).map(|_| self.new_entity()).collect::<Vec<_>>();
+        self.fill_prefab(prefab, &entities, &mut 0)
+    }
+
+    /// Adds the components of a prefab and its children to entities that were spawned for them.
+    fn fill_prefab(&mut self, prefab: &Prefab, entities: &[Entity], next: &mut usize) -> Entity {
+        let entity = entities[*next];
+        *next += 1;
+
+        for component in &prefab.components {
+            let data = (component.cloner.thaw)(&*component.data);
+            if component.type_id == TypeId::of::<Instance>() {
+                if let Some(instance) = data.lock().unwrap().downcast_mut::<Instance>() {
+                    instance.renew_id();
+                }
+            }
+            if let Some((index, retarget)) = component.target {
+                (retarget.set)(&mut *data.lock().unwrap(), entities[index]);
+            }
+
+            self.share_component_by_id(entity, component.type_id, data, Some(component.cloner));
+        }
+
+        for child in &prefab.children {
+            let child = self.fill_prefab(child, entities, next);
+            self.add_child(entity, child);
+        }
+
+        entity
+    }
+
+    /// Spawns an entity from a prefab with some of its components overridden, returning the
+    /// entity.
+    ///
+    /// The overrides are a map of registered component names to values (e.g.
+    /// `{"Velocity": {"x": 2.0}}`). Components the prefab already holds only have the given fields
+    /// changed, while other components are created with the defaults of their type for any
+    /// missing fields. Nothing is spawned if the overrides aren't a map, and the entity is
+    /// despawned again if an override can't be applied.
+    pub fn spawn_prefab_with(
+        &mut self,
+        prefab: &Prefab,
+        overrides: &Value,
+    ) -> Result<Entity, ReflectError> {
+        let Value::Map(overrides) = overrides else {
+            return Err(ReflectError::TypeMismatch {
+                expected: "map of component names to values",
+                found: overrides.kind(),
+            });
+        };
+
+        let entity = self.spawn_prefab(prefab);
+        if let Err(e) = self.apply_overrides(entity, overrides) {
+            self.despawn(entity);
+            return Err(e);
+        }
+
+        Ok(entity)
+    }
+
+    /// Registers a prefab under a name in the world's [`Prefabs`], replacing any prefab of the
+    /// same name.
+    pub fn register_prefab(&mut self, name: &str, prefab: Prefab) {
+        match self.resource::<Prefabs>() {
+            Some(prefabs) => {
+                prefabs
+                    .lock()
+                    .unwrap()
+                    .prefabs
+                    .insert(name.to_string(), prefab);
+            }
+            None => {
+                let mut prefabs = Prefabs::default();
+                prefabs.prefabs.insert(name.to_string(), prefab);
+                self.insert_resource(prefabs);
+            }
+        }
+    }
+
+    fn apply_overrides(
+        &mut self,
+        entity: Entity,
+        overrides: &[(String, Value)],
+    ) -> Result<(), ReflectError> {
+        for (name, value) in overrides {
+            if self.get_reflect(entity, name, "").is_ok() {
+                self.set_reflect(entity, name, "", value)?;
+            } else {
+                self.insert_reflect(entity, name, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Prefab;
+    use crate::{
+        assert_component,
+        reflect::{ReflectError, Value},
+        testing::Fixture,
+        World, WorldBuilder,
+    };
+
+    #[derive(Clone, Debug, Default)]
+    struct Velocity {
+        x: f32,
+        y: f32,
+    }
+
+    crate::reflect_struct!(Velocity { x, y });
+
+    #[derive(Clone, Debug, Default)]
+    struct Spin {
+        rate: f32,
+    }
+
+    crate::reflect_struct!(Spin { rate });
+
+    fn with_types<'a>() -> World<'a> {
+        Fixture::from_builder(
+            WorldBuilder::new()
+                .with_seed(0)
+                .with_type::<Velocity>("Velocity")
+                .with_type::<Spin>("Spin"),
+        )
+        .build()
+    }
+
+    /// Describes an override of a single field of a component.
+    fn overrides(component: &str, field: &str, value: f64) -> Value {
+        Value::Map(vec![(
+            component.to_string(),
+            Value::Map(vec![(field.to_string(), Value::Float(value))]),
+        )])
+    }
+
+    #[test]
+    fn overrides_change_only_the_given_fields() {
+        let mut world = with_types();
+        let ball = Prefab::new().with(Velocity { x: 1.0, y: 2.0 });
+
+        let fast = world
+            .spawn_prefab_with(&ball, &overrides("Velocity", "x", 5.0))
+            .unwrap();
+        let plain = world.spawn_prefab(&ball);
+
+        assert_component!(world, fast, Velocity, |v| v.x == 5.0 && v.y == 2.0);
+        assert_component!(world, plain, Velocity, |v| v.x == 1.0 && v.y == 2.0);
+    }
+
+    #[test]
+    fn overrides_add_missing_components_with_defaults() {
+        let mut world = with_types();
+        let ball = Prefab::new().with(Velocity::default());
+
+        let spinning = world
+            .spawn_prefab_with(&ball, &overrides("Spin", "rate", 3.0))
+            .unwrap();
+
+        assert_component!(world, spinning, Spin, |s| s.rate == 3.0);
+        assert_component!(world, spinning, Velocity);
+    }
+
+    #[test]
+    fn failed_overrides_despawn_the_copy() {
+        let mut world = with_types();
+        let ball = Prefab::new()
+            .with(Velocity::default())
+            .with_child(Prefab::new().with(Spin::default()));
+        let before = world.entities.len();
+
+        assert!(world
+            .spawn_prefab_with(&ball, &overrides("Unknown", "x", 1.0))
+            .is_err());
+        assert_eq!(world.entities.len(), before);
+    }
+
+    #[test]
+    fn overrides_must_be_a_map() {
+        let mut world = with_types();
+        let before = world.entities.len();
+
+        let result = world.spawn_prefab_with(&Prefab::new(), &Value::Float(1.0));
+        assert!(matches!(result, Err(ReflectError::TypeMismatch { found: "float", .. })));
+        assert_eq!(world.entities.len(), before);
+    }
+
+    #[test]
+    fn captured_prefabs_keep_their_hierarchy() {
+        let mut world = with_types();
+        let parent = world.new_entity();
+        let child = world.new_entity();
+        world.add_component(parent, Velocity { x: 1.0, y: 0.0 });
+        world.add_component(child, Spin { rate: 2.0 });
+        world.add_child(parent, child);
+
+        let copy = world.spawn_prefab(&Prefab::from_entity(&world, parent).unwrap());
+        let children = world.children(copy);
+
+        assert_eq!(children.len(), 1);
+        assert_ne!(children[0], child);
+        assert_component!(world, children[0], Spin, |s| s.rate == 2.0);
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{system::Query, World};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of reproducible randomness, held by the world as a resource.
+///
+/// The resource is seeded by [`WorldBuilder::with_seed`] and hands out independent streams of
+/// random numbers keyed by name. Every named system gets a stream of its own through
+/// [`Query::with_rng`], so the numbers a system draws only depend on the seed, the system's name,
+/// and how many numbers that system drew before, never on which other systems ran or in which
+/// order. Systems must be given a name with [`System::with_name`] to draw random numbers, since a
+/// stream keyed by the position of a system would change whenever other systems are added; unnamed
+/// systems get no stream at all. Systems that share a name still get separate streams, told apart
+/// by the order in which they were added.
+///
+/// The state of every stream is captured by [`Snapshot`]s, so restoring a snapshot also rewinds the
+/// randomness drawn since.
+///
+/// ```
+/// # use aspeng::{type_ids, Query, System, WorldBuilder};
+/// # struct Velocity;
+/// # let mut world = WorldBuilder::new().with_seed(7).build();
+/// fn jitter(query: Query) {
+///     let kick = query.with_rng(|rng| rng.range_f32(-1.0, 1.0)).unwrap();
+///     assert!((-1.0..1.0).contains(&kick));
+/// }
+///
+/// world.add_fixed_system(System::new(type_ids![Velocity], jitter).with_name("jitter"));
+/// # world.step_n(3).unwrap();
+/// ```
+///
+/// [`WorldBuilder::with_seed`]: crate::WorldBuilder::with_seed()
+/// [`Query::with_rng`]: crate::system::Query::with_rng()
+/// [`System::with_name`]: crate::system::System::with_name()
+/// [`Snapshot`]: crate::snapshot::Snapshot
+#[derive(Clone, Debug)]
+pub struct Rng {
+    seed: u64,
+    streams: HashMap<String, RngStream>,
+}
+
+impl Rng {
+    /// Creates a source of randomness from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Creates a source of randomness seeded from the current time.
+    ///
+    /// The seed can be read back with [`seed`] so that a run can be reproduced later.
+    ///
+    /// [`seed`]: Self::seed()
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(nanos)
+    }
+
+    /// Returns the seed every stream is derived from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the stream with a particular name, starting it if it hasn't been used yet.
+    pub fn stream(&mut self, name: &str) -> &mut RngStream {
+        let seed = self.seed;
+        self.streams
+            .entry(name.to_string())
+            .or_insert_with(|| RngStream::new(seed ^ fnv1a(name)))
+    }
+}
+
+/// A single stream of random numbers generated with xoshiro256**.
+///
+/// Streams are fast and statistically sound but not cryptographically secure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RngStream {
+    state: [u64; 4],
+}
+
+impl RngStream {
+    /// Creates a stream from a seed.
+    ///
+    /// Similar seeds still produce unrelated streams since the seed is spread over the state with
+    /// splitmix64.
+    pub fn new(seed: u64) -> Self {
+        let mut seed = seed;
+        let state = [(); 4].map(|_| splitmix64(&mut seed));
+        Self { state }
+    }
+
+    /// Returns a random number uniformly distributed over every `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+
+        result
+    }
+
+    /// Returns a random number uniformly distributed over every `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a random number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Returns a random number in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Returns a random number in `[min, max)`.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Returns a random number in `[min, max)`.
+    pub fn range_f64(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+
+    /// Returns a random index in `[0, len)`.
+    ///
+    /// Panics if `len` is zero.
+    pub fn index(&mut self, len: usize) -> usize {
+        assert!(len > 0, "can't pick an index out of an empty range");
+        // multiplying rather than taking the remainder avoids favouring small indices
+        ((u128::from(self.next_u64()) * len as u128) >> 64) as usize
+    }
+
+    /// Returns `true` with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Returns a random element of a slice, or `None` if it is empty.
+    pub fn choose<'t, T>(&mut self, items: &'t [T]) -> Option<&'t T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.index(items.len()))
+        }
+    }
+
+    /// Shuffles a slice in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.index(i + 1));
+        }
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Hashes a stream name in a way that never changes between runs, platforms, or compiler versions.
+fn fnv1a(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+impl Query<'_> {
+    /// Runs a function with the random stream of the system running the query.
+    ///
+    /// Returns `None` if the world has no [`Rng`] resource or the system has no name (see
+    /// [`Rng`]).
+    pub fn with_rng<R>(&self, f: impl FnOnce(&mut RngStream) -> R) -> Option<R> {
+        let stream = self.stream()?;
+        let rng = self.resource::<Rng>()?;
+        let mut rng = rng.lock().unwrap();
+        Some(f(rng.stream(stream)))
+    }
+}
+
+impl World<'_> {
+    /// Returns the random stream of a system that is about to be added with a particular name.
+    ///
+    /// The first system with a name draws from the stream of that name, while later systems with
+    /// the same name are numbered in the order they were added (e.g. `jitter #2`). Unnamed
+    /// systems have no stream.
+    pub(crate) fn rng_stream(&self, name: Option<&str>) -> Option<String> {
+        let name = name?;
+        let previous = self
+            .fixed_systems
+            .iter()
+            .chain(&self.dependent_systems)
+            .chain(self.states.systems())
+            .map(|system| system.system.name())
+            .chain(self.observers.systems().map(|system| system.name()))
+            .filter(|other| *other == Some(name))
+            .count();
+
+        Some(match previous {
+            0 => name.to_string(),
+            previous => format!("{} #{}", name, previous + 1),
+        })
+    }
+
+    /// Runs a function with a named random stream outside of any system (e.g. while setting up
+    /// the world).
+    ///
+    /// Returns `None` if the world has no [`Rng`] resource.
+    pub fn with_rng<R>(&self, stream: &str, f: impl FnOnce(&mut RngStream) -> R) -> Option<R> {
+        let rng = self.resource::<Rng>()?;
+        let mut rng = rng.lock().unwrap();
+        Some(f(rng.stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::Fixture, Query, System, World, WorldBuilder};
+
+    #[derive(Clone, Debug, Default)]
+    struct Rolls(Vec<u64>);
+
+    fn roll(query: Query) {
+        let value = query.with_rng(|rng| rng.next_u64()).unwrap();
+        query.resource::<Rolls>().unwrap().lock().unwrap().0.push(value);
+    }
+
+    fn draw(query: Query) {
+        query.with_rng(|rng| rng.next_u64()).unwrap();
+    }
+
+    fn rolls(world: &World<'_>) -> Vec<u64> {
+        world.resource::<Rolls>().unwrap().lock().unwrap().0.clone()
+    }
+
+    fn seeded<'a>(seed: u64) -> World<'a> {
+        Fixture::from_builder(WorldBuilder::new().with_seed(seed))
+            .with_resource(Rolls::default())
+            .build()
+    }
+
+    #[test]
+    fn the_same_seed_draws_the_same_numbers() {
+        let mut first = seeded(3);
+        let mut second = seeded(3);
+        let mut third = seeded(4);
+        for world in [&mut first, &mut second, &mut third] {
+            world.add_fixed_system(System::new(vec![], roll).with_name("dice"));
+            world.step_n(5).unwrap();
+        }
+
+        assert_eq!(rolls(&first).len(), 5);
+        assert_eq!(rolls(&first), rolls(&second));
+        assert_ne!(rolls(&first), rolls(&third));
+    }
+
+    #[test]
+    fn streams_do_not_depend_on_other_systems() {
+        let mut alone = seeded(3);
+        alone.add_fixed_system(System::new(vec![], roll).with_name("dice"));
+
+        let mut crowded = seeded(3);
+        crowded.add_fixed_system(System::new(vec![], draw).with_name("coin"));
+        crowded.add_fixed_system(System::new(vec![], roll).with_name("dice"));
+        crowded.add_fixed_system(System::new(vec![], draw).with_name("card"));
+
+        alone.step_n(5).unwrap();
+        crowded.step_n(5).unwrap();
+        assert_eq!(rolls(&alone), rolls(&crowded));
+    }
+
+    #[test]
+    fn systems_sharing_a_name_draw_from_separate_streams() {
+        let mut world = seeded(3);
+        world.add_fixed_system(System::new(vec![], roll).with_name("dice"));
+        assert_eq!(world.rng_stream(Some("dice")).as_deref(), Some("dice #2"));
+        world.add_fixed_system(System::new(vec![], roll).with_name("dice"));
+
+        world.step_n(1).unwrap();
+        let rolls = rolls(&world);
+        assert_eq!(rolls.len(), 2);
+        assert_ne!(rolls[0], rolls[1]);
+        assert_eq!(world.rng_stream(None), None);
+    }
+
+    #[test]
+    fn unnamed_systems_have_no_stream() {
+        fn unnamed(query: Query) {
+            assert_eq!(query.with_rng(|rng| rng.next_u64()), None);
+            query.resource::<Rolls>().unwrap().lock().unwrap().0.push(0);
+        }
+
+        let mut world = seeded(3);
+        world.add_fixed_system(System::new(vec![], unnamed));
+        world.step_n(1).unwrap();
+        assert_eq!(rolls(&world), [0]);
+    }
+}
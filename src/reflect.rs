@@ -0,0 +1,1342 @@
+use crate::{
+    camera::FlyCamera,
+    component::Cloner,
+    entity::Name,
+    input::InputManager,
+    mesh::{Instance, Model},
+    relation::{ChildOf, Relation},
+    Entity, World,
+};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A dynamically typed value produced and consumed by reflection.
+///
+/// Acts as the common language between reflected types and everything that needs to inspect them
+/// without knowing their concrete type (e.g. config files, inspectors, and save games). Maps keep
+/// the order in which their fields were declared.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    /// The absence of a value.
+    #[default]
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// Any signed or unsigned integer.
+    Int(i64),
+    /// Any floating point number.
+    Float(f64),
+    /// A string of text.
+    String(String),
+    /// A reference to another entity.
+    Entity(Entity),
+    /// An ordered list of values.
+    List(Vec<Value>),
+    /// A list of named values, typically the fields of a struct.
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the value of a field if this value is a map containing it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float if it is numeric.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an integer if it is an integer or a float without a fractional part
+    /// that fits in an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|i| i64::try_from(i).ok())
+    }
+
+    /// Returns the value as an integer wide enough for every integer type that can be reflected.
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            Value::Int(i) => Some(i128::from(*i)),
+            Value::Float(f) if f.is_finite() && f.fract() == 0.0 && f.abs() < 2f64.powi(127) => {
+                Some(*f as i128)
+            }
+            _ => None,
+        }
+    }
+
+    /// Describes the kind of value held, which is used when reporting mismatched types.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Entity(_) => "entity",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Parses a value from the short-hand used when typing values by hand (e.g. `3`, `-0.5`,
+    /// `true`, or `hello`).
+    ///
+    /// Anything that is not a number, boolean, or `null` is treated as a string. Integers that
+    /// don't fit in an `i64` and floats that aren't finite (e.g. `1e400` or `nan`) are rejected.
+    pub fn parse(text: &str) -> Result<Self, ReflectError> {
+        let text = text.trim();
+        let out_of_range = |expected| ReflectError::OutOfRange {
+            expected,
+            found: text.to_string(),
+        };
+
+        if text == "null" {
+            Ok(Value::Null)
+        } else if let Ok(b) = text.parse::<bool>() {
+            Ok(Value::Bool(b))
+        } else if let Ok(i) = text.parse::<i128>() {
+            i64::try_from(i)
+                .map(Value::Int)
+                .map_err(|_| out_of_range("i64"))
+        } else if let Ok(f) = text.parse::<f64>() {
+            if f.is_finite() {
+                Ok(Value::Float(f))
+            } else {
+                Err(out_of_range("f64"))
+            }
+        } else {
+            Ok(Value::String(text.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::Entity(e) => write!(f, "{}", e),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(fields) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", k, v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// An error produced when reflecting over a type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReflectError {
+    /// The requested field does not exist on the reflected type.
+    UnknownField(String),
+    /// No type has been registered under the given name.
+    UnknownType(String),
+    /// The entity does not hold the requested component.
+    MissingComponent(String),
+    /// The registered type cannot be created from a value because it has no default.
+    NotConstructible(String),
+    /// The value provided cannot be stored in the reflected type.
+    TypeMismatch {
+        /// The type that was being written to.
+        expected: &'static str,
+        /// The kind of value that was provided.
+        found: &'static str,
+    },
+    /// The value provided is of the right kind but can't be represented by the reflected type
+    /// (e.g. a negative number for an unsigned integer or a float that isn't finite).
+    OutOfRange {
+        /// The type that was being written to.
+        expected: &'static str,
+        /// The value that was provided.
+        found: String,
+    },
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectError::UnknownField(field) => write!(f, "unknown field `{}`", field),
+            ReflectError::UnknownType(name) => write!(f, "no type registered as `{}`", name),
+            ReflectError::MissingComponent(name) => {
+                write!(f, "entity does not have a `{}` component", name)
+            }
+            ReflectError::NotConstructible(name) => {
+                write!(f, "`{}` cannot be created from a value", name)
+            }
+            ReflectError::TypeMismatch { expected, found } => {
+                write!(f, "expected a value for `{}` but found {}", expected, found)
+            }
+            ReflectError::OutOfRange { expected, found } => {
+                write!(f, "{} is out of range for `{}`", found, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReflectError {}
+
+/// Provides field-level access to a type at runtime.
+///
+/// Reflected types can be converted to and from a [`Value`] and have their fields enumerated,
+/// read, and written by name. Primitives, strings, entities, vectors, and the nalgebra types used
+/// by the engine are implemented out of the box; user structs can be implemented with the
+/// [`reflect_struct`] macro.
+///
+/// [`reflect_struct`]: crate::reflect_struct
+pub trait Reflect: Any {
+    /// Returns the names of all fields of the type in declaration order.
+    fn fields(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Returns a field of the type by name.
+    fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+        None
+    }
+    /// Returns a mutable field of the type by name.
+    fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+        None
+    }
+    /// Converts the data into a dynamic value.
+    fn to_value(&self) -> Value;
+    /// Overwrites the data with a dynamic value.
+    ///
+    /// Maps may be partial, in which case only the fields that are present are overwritten. If the
+    /// value can't be written, the data is left unchanged.
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError>;
+    /// Upcasts the type to [`Any`].
+    fn as_any(&self) -> &dyn Any;
+    /// Upcasts the type to [`Any`] mutably.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl dyn Reflect {
+    /// Returns a nested field following a `.`-separated path (e.g. `translation.x` or `points.3`).
+    ///
+    /// An empty path returns the value itself.
+    pub fn path(&self, path: &str) -> Result<&dyn Reflect, ReflectError> {
+        path.split('.')
+            .filter(|s| !s.is_empty())
+            .try_fold(self, |current, name| {
+                current
+                    .field(name)
+                    .ok_or_else(|| ReflectError::UnknownField(name.to_string()))
+            })
+    }
+
+    /// Returns a mutable nested field following a `.`-separated path.
+    pub fn path_mut(&mut self, path: &str) -> Result<&mut dyn Reflect, ReflectError> {
+        path.split('.')
+            .filter(|s| !s.is_empty())
+            .try_fold(self, |current, name| {
+                current
+                    .field_mut(name)
+                    .ok_or_else(|| ReflectError::UnknownField(name.to_string()))
+            })
+    }
+
+    /// Reads the value of a nested field.
+    pub fn get_path(&self, path: &str) -> Result<Value, ReflectError> {
+        Ok(self.path(path)?.to_value())
+    }
+
+    /// Writes the value of a nested field.
+    pub fn set_path(&mut self, path: &str, value: &Value) -> Result<(), ReflectError> {
+        self.path_mut(path)?.set_value(value)
+    }
+
+    /// Attempts to downcast the reflected value to a concrete type.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.as_any().downcast_ref::<T>()
+    }
+}
+
+fn mismatch<T: ?Sized>(value: &Value) -> ReflectError {
+    ReflectError::TypeMismatch {
+        expected: std::any::type_name::<T>(),
+        found: value.kind(),
+    }
+}
+
+fn out_of_range<T: ?Sized>(value: &Value) -> ReflectError {
+    ReflectError::OutOfRange {
+        expected: std::any::type_name::<T>(),
+        found: value.to_string(),
+    }
+}
+
+/// Implements [`Reflect`] for integers.
+///
+/// Unsigned integers above `i64::MAX` can't be held by [`Value::Int`] and are reflected as the
+/// nearest float instead.
+macro_rules! reflect_int {
+    ($($ty:ty),*) => {
+        $(
+            impl Reflect for $ty {
+                fn to_value(&self) -> Value {
+                    i64::try_from(*self as i128)
+                        .map(Value::Int)
+                        .unwrap_or(Value::Float(*self as f64))
+                }
+
+                fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+                    let i = value.as_i128().ok_or_else(|| mismatch::<$ty>(value))?;
+                    *self = <$ty>::try_from(i).map_err(|_| out_of_range::<$ty>(value))?;
+                    Ok(())
+                }
+
+                fn as_any(&self) -> &dyn Any {
+                    self
+                }
+
+                fn as_any_mut(&mut self) -> &mut dyn Any {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! reflect_float {
+    ($($ty:ty),*) => {
+        $(
+            impl Reflect for $ty {
+                fn to_value(&self) -> Value {
+                    Value::Float(*self as f64)
+                }
+
+                fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+                    let f = value.as_f64().ok_or_else(|| mismatch::<$ty>(value))? as $ty;
+                    if !f.is_finite() {
+                        return Err(out_of_range::<$ty>(value));
+                    }
+
+                    *self = f;
+                    Ok(())
+                }
+
+                fn as_any(&self) -> &dyn Any {
+                    self
+                }
+
+                fn as_any_mut(&mut self) -> &mut dyn Any {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+reflect_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+reflect_float!(f32, f64);
+
+impl Reflect for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        match value {
+            Value::Bool(b) => {
+                *self = *b;
+                Ok(())
+            }
+            _ => Err(mismatch::<bool>(value)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Reflect for String {
+    fn to_value(&self) -> Value {
+        Value::String(self.clone())
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        match value {
+            Value::String(s) => {
+                *self = s.clone();
+                Ok(())
+            }
+            _ => Err(mismatch::<String>(value)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Reflect for Entity {
+    fn to_value(&self) -> Value {
+        Value::Entity(*self)
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        match value {
+            Value::Entity(e) => {
+                *self = *e;
+                Ok(())
+            }
+            _ => Err(mismatch::<Entity>(value)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Reflect + Default> Reflect for Option<T> {
+    fn fields(&self) -> Vec<String> {
+        self.as_ref().map(|v| v.fields()).unwrap_or_default()
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        self.as_ref().and_then(|v| v.field(name))
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        self.as_mut().and_then(|v| v.field_mut(name))
+    }
+
+    fn to_value(&self) -> Value {
+        self.as_ref().map(|v| v.to_value()).unwrap_or(Value::Null)
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        match self {
+            _ if *value == Value::Null => *self = None,
+            Some(inner) => inner.set_value(value)?,
+            None => {
+                let mut inner = T::default();
+                inner.set_value(value)?;
+                *self = Some(inner);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Reflect + Default + Clone> Reflect for Vec<T> {
+    fn fields(&self) -> Vec<String> {
+        (0..self.len()).map(|i| i.to_string()).collect()
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        name.parse::<usize>()
+            .ok()
+            .and_then(|i| self.get(i))
+            .map(|v| v as &dyn Reflect)
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        name.parse::<usize>()
+            .ok()
+            .and_then(|i| self.get_mut(i))
+            .map(|v| v as &mut dyn Reflect)
+    }
+
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(Reflect::to_value).collect())
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        match value {
+            Value::List(values) => {
+                let mut updated = self.iter().take(values.len()).cloned().collect::<Vec<_>>();
+                updated.resize_with(values.len(), T::default);
+                updated
+                    .iter_mut()
+                    .zip(values)
+                    .try_for_each(|(item, value)| item.set_value(value))?;
+                *self = updated;
+                Ok(())
+            }
+            _ => Err(mismatch::<Self>(value)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Reflect + Clone, const N: usize> Reflect for [T; N] {
+    fn fields(&self) -> Vec<String> {
+        (0..N).map(|i| i.to_string()).collect()
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        name.parse::<usize>()
+            .ok()
+            .and_then(|i| self.get(i))
+            .map(|v| v as &dyn Reflect)
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        name.parse::<usize>()
+            .ok()
+            .and_then(|i| self.get_mut(i))
+            .map(|v| v as &mut dyn Reflect)
+    }
+
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(Reflect::to_value).collect())
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        match value {
+            Value::List(values) if values.len() == N => {
+                let mut updated = self.clone();
+                updated
+                    .iter_mut()
+                    .zip(values)
+                    .try_for_each(|(item, value)| item.set_value(value))?;
+                *self = updated;
+                Ok(())
+            }
+            _ => Err(mismatch::<Self>(value)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<A: Reflect, B: Reflect + Clone> Reflect for (A, B) {
+    fn fields(&self) -> Vec<String> {
+        vec!["0".to_string(), "1".to_string()]
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        match name {
+            "0" => Some(&self.0),
+            "1" => Some(&self.1),
+            _ => None,
+        }
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        match name {
+            "0" => Some(&mut self.0),
+            "1" => Some(&mut self.1),
+            _ => None,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::List(vec![self.0.to_value(), self.1.to_value()])
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        match value {
+            Value::List(values) if values.len() == 2 => {
+                let mut second = self.1.clone();
+                second.set_value(&values[1])?;
+                self.0.set_value(&values[0])?;
+                self.1 = second;
+                Ok(())
+            }
+            _ => Err(mismatch::<Self>(value)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Implements [`Reflect`] for a struct with named fields.
+///
+/// The struct must implement [`Clone`] and every listed field must itself implement [`Reflect`].
+/// Fields that are left out are invisible to reflection and keep their value when the struct is
+/// written to.
+///
+/// ```
+/// #[derive(Clone, Default)]
+/// struct Velocity {
+///     x: f32,
+///     y: f32,
+///     z: f32,
+/// }
+///
+/// aspeng::reflect_struct!(Velocity { x, y, z });
+/// ```
+///
+/// [`Reflect`]: crate::reflect::Reflect
+#[macro_export]
+macro_rules! reflect_struct {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::reflect::Reflect for $ty {
+            fn fields(&self) -> Vec<String> {
+                vec![$(stringify!($field).to_string()),*]
+            }
+
+            fn field(&self, name: &str) -> Option<&dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn field_mut(&mut self, name: &str) -> Option<&mut dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn to_value(&self) -> $crate::reflect::Value {
+                $crate::reflect::Value::Map(vec![
+                    $((
+                        stringify!($field).to_string(),
+                        $crate::reflect::Reflect::to_value(&self.$field),
+                    )),*
+                ])
+            }
+
+            fn set_value(
+                &mut self,
+                value: &$crate::reflect::Value,
+            ) -> Result<(), $crate::reflect::ReflectError> {
+                $crate::reflect::set_fields(self, value)
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+        }
+    };
+}
+
+/// Writes every entry of a map (or, in field order, a list) into the fields of a reflected type.
+///
+/// The entries are written to a copy of the target, which only replaces the target once every
+/// entry was written, so a value with an unknown field or a mismatched entry changes nothing.
+/// Used to implement [`Reflect::set_value`] for structs; see [`reflect_struct`].
+///
+/// [`reflect_struct`]: crate::reflect_struct
+pub fn set_fields<T: Reflect + Clone>(target: &mut T, value: &Value) -> Result<(), ReflectError> {
+    let mut updated = target.clone();
+    write_fields(&mut updated, value)?;
+    *target = updated;
+    Ok(())
+}
+
+fn write_fields<T: Reflect>(target: &mut T, value: &Value) -> Result<(), ReflectError> {
+    match value {
+        Value::Map(entries) => entries.iter().try_for_each(|(name, value)| {
+            target
+                .field_mut(name)
+                .ok_or_else(|| ReflectError::UnknownField(name.clone()))?
+                .set_value(value)
+        }),
+        Value::List(values) => {
+            let fields = target.fields();
+            if fields.len() != values.len() {
+                return Err(mismatch::<T>(value));
+            }
+
+            fields.iter().zip(values).try_for_each(|(name, value)| {
+                target
+                    .field_mut(name)
+                    .ok_or_else(|| ReflectError::UnknownField(name.clone()))?
+                    .set_value(value)
+            })
+        }
+        _ => Err(mismatch::<T>(value)),
+    }
+}
+
+reflect_struct!(nalgebra::Vector3<f32> { x, y, z });
+reflect_struct!(nalgebra::Point3<f32> { x, y, z });
+
+impl Reflect for nalgebra::Translation3<f32> {
+    fn fields(&self) -> Vec<String> {
+        self.vector.fields()
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        self.vector.field(name)
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        self.vector.field_mut(name)
+    }
+
+    fn to_value(&self) -> Value {
+        self.vector.to_value()
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        self.vector.set_value(value)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Reflect for nalgebra::Scale3<f32> {
+    fn fields(&self) -> Vec<String> {
+        self.vector.fields()
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        self.vector.field(name)
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        self.vector.field_mut(name)
+    }
+
+    fn to_value(&self) -> Value {
+        self.vector.to_value()
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        self.vector.set_value(value)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Unit quaternions are reflected as a whole in the order `[i, j, k, w]` and renormalized when
+/// written so they remain valid rotations.
+impl Reflect for nalgebra::UnitQuaternion<f32> {
+    fn to_value(&self) -> Value {
+        let q = self.quaternion();
+        [q.i, q.j, q.k, q.w].to_value()
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        let mut coords = [0.0f32; 4];
+        coords
+            .set_value(value)
+            .map_err(|_| mismatch::<Self>(value))?;
+        *self = nalgebra::UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(
+            coords[3], coords[0], coords[1], coords[2],
+        ));
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+reflect_struct!(Instance {
+    translation,
+    scale,
+    rotation
+});
+reflect_struct!(FlyCamera {
+    eye,
+    dir,
+    up,
+    aspect,
+    fovy,
+    znear,
+    zfar
+});
+reflect_struct!(InputManager { analog_input });
+
+impl Reflect for Name {
+    fn to_value(&self) -> Value {
+        self.0.to_value()
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        self.0.set_value(value)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Describes a type registered with a [`TypeRegistry`].
+///
+/// Holds the stable name of the type along with type-erased functions that allow it to be
+/// reflected, created, and inserted into a world without knowing the concrete type.
+#[derive(Clone)]
+pub struct TypeRegistration {
+    name: String,
+    type_id: TypeId,
+    type_name: &'static str,
+    default: Option<fn() -> Box<dyn Reflect>>,
+    reflect: fn(&dyn Any) -> Option<&dyn Reflect>,
+    reflect_mut: fn(&mut dyn Any) -> Option<&mut dyn Reflect>,
+    insert: fn(&mut World<'_>, Entity, Box<dyn Reflect>),
+    insert_resource: fn(&mut World<'_>, Box<dyn Reflect>),
+}
+
+impl TypeRegistration {
+    fn of<T: Reflect + Clone + Send + Sync>(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            default: None,
+            reflect: |data| data.downcast_ref::<T>().map(|v| v as &dyn Reflect),
+            reflect_mut: |data| data.downcast_mut::<T>().map(|v| v as &mut dyn Reflect),
+            insert: |world, entity, data| {
+                let data = data
+                    .as_any()
+                    .downcast_ref::<T>()
+                    .expect("registered type mismatch")
+                    .clone();
+                world.add_component(entity, data);
+            },
+            insert_resource: |world, data| {
+                let data = data
+                    .as_any()
+                    .downcast_ref::<T>()
+                    .expect("registered type mismatch")
+                    .clone();
+                world.insert_resource(data);
+            },
+        }
+    }
+
+    /// The stable name under which the type was registered.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The [`TypeId`] of the registered type.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// The full Rust path of the registered type as given by [`std::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Creates a default instance of the type if it was registered with a default.
+    pub fn default_value(&self) -> Option<Box<dyn Reflect>> {
+        self.default.map(|default| default())
+    }
+
+    /// Creates an instance of the type from a dynamic value, starting from its default.
+    pub fn from_value(&self, value: &Value) -> Result<Box<dyn Reflect>, ReflectError> {
+        let mut data = self
+            .default_value()
+            .ok_or_else(|| ReflectError::NotConstructible(self.name.clone()))?;
+        data.set_value(value)?;
+        Ok(data)
+    }
+
+    /// Views type-erased data as a reflected value if it is of the registered type.
+    pub fn reflect<'d>(&self, data: &'d dyn Any) -> Option<&'d dyn Reflect> {
+        (self.reflect)(data)
+    }
+
+    /// Views type-erased data as a mutable reflected value if it is of the registered type.
+    pub fn reflect_mut<'d>(&self, data: &'d mut dyn Any) -> Option<&'d mut dyn Reflect> {
+        (self.reflect_mut)(data)
+    }
+
+    pub(crate) fn insert(&self, world: &mut World<'_>, entity: Entity, data: Box<dyn Reflect>) {
+        (self.insert)(world, entity, data)
+    }
+
+    pub(crate) fn insert_resource(&self, world: &mut World<'_>, data: Box<dyn Reflect>) {
+        (self.insert_resource)(world, data)
+    }
+}
+
+impl fmt::Debug for TypeRegistration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeRegistration")
+            .field("name", &self.name)
+            .field("type_name", &self.type_name)
+            .finish()
+    }
+}
+
+/// A registry of reflected types, indexed both by name and by [`TypeId`].
+///
+/// Every world holds its own registry, which is pre-populated with the engine's reflectable
+/// components ([`Instance`], [`FlyCamera`], [`InputManager`], [`Name`], and the [`ChildOf`]
+/// relation). User components must be registered before they can be printed, edited, or loaded by
+/// name.
+///
+/// Types that don't implement [`Reflect`] may still be registered with [`register_debug`] so that
+/// they show up in a [`WorldDump`] through their [`Debug`] implementation.
+///
+/// The registry also records which component types can be cloned, which decides whether they are
+/// captured by snapshots and copied by prefabs. Reflected types are always clonable; others are
+/// registered with [`register_clone`].
+///
+/// [`register_debug`]: Self::register_debug()
+/// [`register_clone`]: Self::register_clone()
+/// [`WorldDump`]: crate::dump::WorldDump
+/// [`Debug`]: std::fmt::Debug
+///
+/// [`Instance`]: crate::mesh::Instance
+/// [`FlyCamera`]: crate::camera::FlyCamera
+/// [`InputManager`]: crate::input::InputManager
+/// [`Name`]: crate::entity::Name
+/// [`ChildOf`]: crate::relation::ChildOf
+#[derive(Clone, Debug)]
+pub struct TypeRegistry {
+    registrations: Vec<TypeRegistration>,
+    by_name: HashMap<String, usize>,
+    by_id: HashMap<TypeId, usize>,
+    debug: HashMap<TypeId, (String, DebugFn)>,
+    cloners: HashMap<TypeId, Cloner>,
+}
+
+/// Formats type-erased data with the [`Debug`](fmt::Debug) implementation of its type.
+pub(crate) type DebugFn = fn(&dyn Any) -> String;
+
+impl Default for TypeRegistry {
+    /// Creates a registry containing the engine's reflectable components.
+    fn default() -> Self {
+        let mut registry = TypeRegistry::empty();
+        registry.register_without_default::<Instance>("Instance");
+        registry.register::<FlyCamera>("FlyCamera");
+        registry.register::<InputManager>("InputManager");
+        registry.register::<Name>("Name");
+        registry.register::<Relation<ChildOf>>("ChildOf");
+        registry.register_clone::<Model>();
+        registry
+    }
+}
+
+impl TypeRegistry {
+    /// Creates a registry containing the engine's reflectable components.
+    pub fn new() -> Self {
+        TypeRegistry::default()
+    }
+
+    /// Creates a registry without any types registered.
+    pub fn empty() -> Self {
+        Self {
+            registrations: Vec::new(),
+            by_name: HashMap::new(),
+            by_id: HashMap::new(),
+            debug: HashMap::new(),
+            cloners: HashMap::new(),
+        }
+    }
+
+    /// Registers a type under a stable name.
+    ///
+    /// Registering the same type again replaces its name. Names should be unique; registering a
+    /// different type under a name that is already taken causes the old type to no longer be
+    /// reachable by name.
+    pub fn register<T: Reflect + Clone + Default + Send + Sync>(&mut self, name: &str) {
+        let mut registration = TypeRegistration::of::<T>(name);
+        registration.default = Some(|| Box::new(T::default()));
+        self.insert(registration);
+        self.register_clone::<T>();
+    }
+
+    /// Registers a type that has no sensible default under a stable name.
+    ///
+    /// The type can be inspected and edited by name but not created from a [`Value`] (e.g. an
+    /// [`Instance`], which must reference an existing mesh).
+    ///
+    /// [`Instance`]: crate::mesh::Instance
+    pub fn register_without_default<T: Reflect + Clone + Send + Sync>(&mut self, name: &str) {
+        self.insert(TypeRegistration::of::<T>(name));
+        self.register_clone::<T>();
+    }
+
+    /// Records that a component type can be cloned, so that it is captured by snapshots and
+    /// copied by prefabs.
+    ///
+    /// Components of types that aren't registered this way (or through [`register`]) are left
+    /// out of both, which allows types that can't be cloned (e.g. GPU handles) to be components.
+    /// The type must be [`Send`] and [`Sync`] so that snapshots can be sent to other threads.
+    ///
+    /// [`register`]: Self::register()
+    pub fn register_clone<T: Any + Clone + Send + Sync>(&mut self) {
+        self.cloners.insert(TypeId::of::<T>(), Cloner::of::<T>());
+    }
+
+    /// Returns the cloner of a type registered with [`register_clone`].
+    ///
+    /// [`register_clone`]: Self::register_clone()
+    pub(crate) fn cloner(&self, type_id: TypeId) -> Option<Cloner> {
+        self.cloners.get(&type_id).copied()
+    }
+
+    fn insert(&mut self, registration: TypeRegistration) {
+        let name = registration.name.clone();
+        let type_id = registration.type_id;
+
+        let index = match self.by_id.get(&registration.type_id) {
+            Some(&index) => {
+                self.by_name.remove(&self.registrations[index].name);
+                self.registrations[index] = registration;
+                index
+            }
+            None => {
+                self.registrations.push(registration);
+                self.registrations.len() - 1
+            }
+        };
+
+        self.by_name.insert(name, index);
+        self.by_id.insert(type_id, index);
+    }
+
+    /// Registers a type that can't be reflected under a name so that it can at least be printed
+    /// with its [`Debug`](fmt::Debug) implementation.
+    ///
+    /// Reflected types are always printed through reflection, which allows their fields to be
+    /// compared one by one, so this only needs to be called for types that aren't registered
+    /// otherwise.
+    pub fn register_debug<T: Any + fmt::Debug>(&mut self, name: &str) {
+        self.debug.insert(
+            TypeId::of::<T>(),
+            (name.to_string(), |data| {
+                data.downcast_ref::<T>()
+                    .map(|data| format!("{:?}", data))
+                    .unwrap_or_default()
+            }),
+        );
+    }
+
+    /// Returns the name and formatter of a type registered with [`register_debug`].
+    ///
+    /// [`register_debug`]: Self::register_debug()
+    pub(crate) fn get_debug(&self, type_id: TypeId) -> Option<&(String, DebugFn)> {
+        self.debug.get(&type_id)
+    }
+
+    /// Returns the registration for a type by its stable name.
+    pub fn get(&self, name: &str) -> Option<&TypeRegistration> {
+        self.by_name.get(name).map(|&i| &self.registrations[i])
+    }
+
+    /// Returns the registration for a type by its [`TypeId`].
+    pub fn get_by_id(&self, type_id: TypeId) -> Option<&TypeRegistration> {
+        self.by_id.get(&type_id).map(|&i| &self.registrations[i])
+    }
+
+    /// Returns every registered type in the order it was registered.
+    pub fn iter(&self) -> impl Iterator<Item = &TypeRegistration> {
+        self.registrations.iter()
+    }
+}
+
+/// Values are serialized as their natural counterparts (e.g. maps as maps and lists as
+/// sequences), with the exception of entities, which are written as `{"$entity": id}` so that they
+/// can be told apart from plain integers.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Entity(e) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("$entity", &e.id())?;
+                map.end()
+            }
+            Value::List(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            Value::Map(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ValueVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ValueVisitor {
+            type Value = Value;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "any value")
+            }
+
+            fn visit_unit<E>(self) -> Result<Value, E> {
+                Ok(Value::Null)
+            }
+
+            fn visit_none<E>(self) -> Result<Value, E> {
+                Ok(Value::Null)
+            }
+
+            fn visit_some<D: serde::Deserializer<'de>>(self, d: D) -> Result<Value, D::Error> {
+                serde::Deserialize::deserialize(d)
+            }
+
+            fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+                Ok(Value::Bool(b))
+            }
+
+            fn visit_i64<E>(self, i: i64) -> Result<Value, E> {
+                Ok(Value::Int(i))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, u: u64) -> Result<Value, E> {
+                i64::try_from(u)
+                    .map(Value::Int)
+                    .map_err(|_| E::custom("integer out of range"))
+            }
+
+            fn visit_f64<E>(self, f: f64) -> Result<Value, E> {
+                Ok(Value::Float(f))
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+                Ok(Value::String(s.to_string()))
+            }
+
+            fn visit_string<E>(self, s: String) -> Result<Value, E> {
+                Ok(Value::String(s))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+                let mut values = Vec::new();
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(Value::List(values))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+                let mut fields: Vec<(String, Value)> = Vec::new();
+                while let Some((key, value)) = map.next_entry()? {
+                    fields.push((key, value));
+                }
+
+                match fields.as_slice() {
+                    [(key, Value::Int(id))] if key == "$entity" => u32::try_from(*id)
+                        .map(|id| Value::Entity(Entity::new(id)))
+                        .map_err(|_| serde::de::Error::custom("entity id out of range")),
+                    _ => Ok(Value::Map(fields)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reflect, ReflectError, Value};
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Ship {
+        speed: f32,
+        crew: u8,
+        cargo: Vec<u32>,
+    }
+
+    crate::reflect_struct!(Ship { speed, crew, cargo });
+
+    fn map(entries: &[(&str, Value)]) -> Value {
+        Value::Map(
+            entries
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parsing_short_hand() {
+        assert_eq!(Value::parse(" 3 "), Ok(Value::Int(3)));
+        assert_eq!(Value::parse("-0.5"), Ok(Value::Float(-0.5)));
+        assert_eq!(Value::parse("true"), Ok(Value::Bool(true)));
+        assert_eq!(Value::parse("null"), Ok(Value::Null));
+        assert_eq!(Value::parse("hello"), Ok(Value::String("hello".to_string())));
+        assert_eq!(Value::parse("9223372036854775807"), Ok(Value::Int(i64::MAX)));
+    }
+
+    #[test]
+    fn parsing_rejects_values_out_of_range() {
+        for text in ["9223372036854775808", "1e400", "nan", "-inf"] {
+            assert!(
+                matches!(Value::parse(text), Err(ReflectError::OutOfRange { .. })),
+                "{} was accepted",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn integers_are_range_checked() {
+        let mut crew = 5u8;
+        assert!(matches!(
+            crew.set_value(&Value::Int(300)),
+            Err(ReflectError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            crew.set_value(&Value::Float(1.5)),
+            Err(ReflectError::TypeMismatch { .. })
+        ));
+        assert_eq!(crew, 5);
+
+        crew.set_value(&Value::Float(7.0)).unwrap();
+        assert_eq!(crew, 7);
+        assert_eq!(u64::MAX.to_value(), Value::Float(u64::MAX as f64));
+    }
+
+    #[test]
+    fn floats_must_be_finite() {
+        let mut speed = 1.0f32;
+        assert!(speed.set_value(&Value::Float(1e300)).is_err());
+        assert!(speed.set_value(&Value::Float(f64::NAN)).is_err());
+        assert_eq!(speed, 1.0);
+    }
+
+    #[test]
+    fn failed_writes_change_nothing() {
+        let original = Ship {
+            speed: 1.0,
+            crew: 3,
+            cargo: vec![1, 2],
+        };
+        let mut ship = original.clone();
+
+        let bogus = map(&[("speed", Value::Float(2.0)), ("bogus", Value::Int(1))]);
+        assert_eq!(
+            ship.set_value(&bogus),
+            Err(ReflectError::UnknownField("bogus".to_string()))
+        );
+        let cargo = map(&[(
+            "cargo",
+            Value::List(vec![Value::Int(4), Value::Int(5), Value::Int(-1)]),
+        )]);
+        assert!(ship.set_value(&cargo).is_err());
+        assert_eq!(ship, original);
+
+        let mut pair = (1u8, 2u8);
+        assert!(pair
+            .set_value(&Value::List(vec![Value::Int(3), Value::Int(-1)]))
+            .is_err());
+        assert_eq!(pair, (1, 2));
+
+        let mut maybe = None::<Ship>;
+        assert!(maybe.set_value(&bogus).is_err());
+        assert_eq!(maybe, None);
+    }
+
+    #[test]
+    fn paths_reach_nested_fields() {
+        let mut ship = Ship::default();
+        let ship = &mut ship as &mut dyn Reflect;
+
+        ship.set_path("cargo", &Value::List(vec![Value::Int(7)])).unwrap();
+        ship.set_path("cargo.0", &Value::Int(8)).unwrap();
+        assert_eq!(ship.get_path("cargo.0"), Ok(Value::Int(8)));
+        assert_eq!(
+            ship.get_path("cargo.1"),
+            Err(ReflectError::UnknownField("1".to_string()))
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use crate::{
+    observer::Lifecycle,
+    reflect::{set_fields, Reflect, ReflectError, Value},
+    Entity, World,
+};
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+
+/// A typed connection from the entity holding it to another entity (e.g. a spring between two
+/// masses or a wire between two nodes).
+///
+/// The kind of relation is given by `R`, which may also hold data about the connection (e.g. the
+/// stiffness of the spring). Since relations are components, an entity may only hold one relation
+/// of each kind, but any number of entities may relate to the same target.
+///
+/// Relations should be added with [`World::add_relation`] so that the world can keep track of
+/// their targets. Whenever a target is despawned, every relation pointing at it is cleaned up
+/// according to the [`OnDespawn`] policy of its kind, so that no relation is ever left pointing
+/// at an entity that no longer exists.
+///
+/// The target can only be changed through [`World::set_target`] (or by replacing the relation),
+/// which keeps the index of targets up to date.
+///
+/// [`World::add_relation`]: crate::World::add_relation()
+/// [`World::set_target`]: crate::World::set_target()
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Relation<R> {
+    target: Entity,
+    /// The data describing the relation.
+    pub data: R,
+}
+
+impl<R: Default> Relation<R> {
+    /// Creates a relation to a target that holds no particular data.
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            data: R::default(),
+        }
+    }
+}
+
+impl<R> Relation<R> {
+    /// Creates a relation to a target described by some data.
+    pub fn with_data(target: Entity, data: R) -> Self {
+        Self { target, data }
+    }
+
+    /// Returns the entity on the other end of the relation.
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+}
+
+impl<R: Reflect + Clone> Reflect for Relation<R> {
+    fn fields(&self) -> Vec<String> {
+        vec!["target".to_string(), "data".to_string()]
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        match name {
+            "target" => Some(&self.target),
+            "data" => Some(&self.data),
+            _ => None,
+        }
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        match name {
+            "target" => Some(&mut self.target),
+            "data" => Some(&mut self.data),
+            _ => None,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Map(vec![
+            ("target".to_string(), self.target.to_value()),
+            ("data".to_string(), self.data.to_value()),
+        ])
+    }
+
+    fn set_value(&mut self, value: &Value) -> Result<(), ReflectError> {
+        set_fields(self, value)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The relation of a child entity to its parent (e.g. a wheel to its car).
+///
+/// Every world registers the relation with [`OnDespawn::Despawn`], so children are despawned
+/// along with their parent. Children are added with [`World::add_child`] or by spawning a
+/// [`Prefab`] with children.
+///
+/// [`World::add_child`]: crate::World::add_child()
+/// [`Prefab`]: crate::prefab::Prefab
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChildOf;
+
+crate::reflect_struct!(ChildOf {});
+
+/// What happens to a relation when its target is despawned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnDespawn {
+    /// The relation is removed from the entity holding it, which triggers any [`on_remove`]
+    /// hooks and observers registered for the relation so that the entity may react.
+    ///
+    /// [`on_remove`]: crate::World::on_remove()
+    #[default]
+    Remove,
+    /// The entity holding the relation is despawned along with its target (e.g. for children
+    /// that should not outlive their parent).
+    Despawn,
+}
+
+/// The index of every relation by its target, held by the world as a resource.
+#[derive(Clone, Debug, Default)]
+pub struct Relations {
+    sources: HashMap<Entity, HashMap<TypeId, HashSet<Entity>>>,
+    policies: HashMap<TypeId, OnDespawn>,
+    targets: HashMap<TypeId, Retarget>,
+}
+
+/// Reads and changes the target of a type-erased relation of a particular kind.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Retarget {
+    pub(crate) get: fn(&dyn Any) -> Option<Entity>,
+    pub(crate) set: fn(&mut dyn Any, Entity),
+}
+
+impl Retarget {
+    fn of<R: Any>() -> Self {
+        Self {
+            get: |data| data.downcast_ref::<Relation<R>>().map(Relation::target),
+            set: |data, target| {
+                if let Some(relation) = data.downcast_mut::<Relation<R>>() {
+                    relation.target = target;
+                }
+            },
+        }
+    }
+}
+
+impl Relations {
+    /// Returns every entity holding a relation of kind `R` to a target.
+    pub fn sources<R: 'static>(&self, target: Entity) -> Vec<Entity> {
+        self.sources
+            .get(&target)
+            .and_then(|kinds| kinds.get(&TypeId::of::<Relation<R>>()))
+            .map(|sources| sources.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns how to reach the target of a registered kind of relation, given its component
+    /// type.
+    pub(crate) fn retarget(&self, type_id: TypeId) -> Option<Retarget> {
+        self.targets.get(&type_id).copied()
+    }
+
+    fn insert(&mut self, type_id: TypeId, source: Entity, target: Entity) {
+        self.sources
+            .entry(target)
+            .or_default()
+            .entry(type_id)
+            .or_default()
+            .insert(source);
+    }
+
+    fn remove(&mut self, type_id: TypeId, source: Entity, target: Entity) {
+        if let Some(kinds) = self.sources.get_mut(&target) {
+            if let Some(sources) = kinds.get_mut(&type_id) {
+                sources.remove(&source);
+            }
+        }
+    }
+}
+
+impl World<'_> {
+    /// Registers a kind of relation with the world along with what should happen to relations of
+    /// that kind when their target is despawned.
+    ///
+    /// Called automatically with [`OnDespawn::Remove`] the first time a relation of the kind is
+    /// added through [`add_relation`], so it only needs to be called to choose another policy or
+    /// before relations are loaded from a scene or save (which add them as plain components).
+    ///
+    /// [`add_relation`]: Self::add_relation()
+    pub fn register_relation<R: Any + Clone + Send + Sync>(&mut self, policy: OnDespawn) {
+        let relations = match self.resource::<Relations>() {
+            Some(relations) => relations,
+            None => {
+                self.insert_resource(Relations::default());
+                self.resource::<Relations>().unwrap()
+            }
+        };
+
+        let type_id = TypeId::of::<Relation<R>>();
+        let registered = {
+            let mut relations = relations.lock().unwrap();
+            relations.targets.insert(type_id, Retarget::of::<R>());
+            relations.policies.insert(type_id, policy).is_some()
+        };
+        if registered {
+            return;
+        }
+
+        self.register_clone::<Relation<R>>();
+
+        self.on_insert::<Relation<R>>(|world, source| {
+            let target = world.with_component(source, |relation: &Relation<R>| relation.target);
+            if let (Some(target), Some(relations)) = (target, world.resource::<Relations>()) {
+                relations
+                    .lock()
+                    .unwrap()
+                    .insert(TypeId::of::<Relation<R>>(), source, target);
+            }
+        });
+        self.on_remove::<Relation<R>>(|world, source| {
+            let target = world.with_component(source, |relation: &Relation<R>| relation.target);
+            if let (Some(target), Some(relations)) = (target, world.resource::<Relations>()) {
+                relations
+                    .lock()
+                    .unwrap()
+                    .remove(TypeId::of::<Relation<R>>(), source, target);
+            }
+        });
+    }
+
+    /// Relates an entity to a target, replacing any relation of the same kind it already holds.
+    pub fn add_relation<R>(&mut self, source: Entity, relation: Relation<R>)
+    where
+        R: Any + Clone + Send + Sync,
+    {
+        let registered = self.resource::<Relations>().is_some_and(|relations| {
+            relations
+                .lock()
+                .unwrap()
+                .policies
+                .contains_key(&TypeId::of::<Relation<R>>())
+        });
+        if !registered {
+            self.register_relation::<R>(OnDespawn::default());
+        }
+
+        self.add_component(source, relation);
+    }
+
+    /// Returns the target of the relation of kind `R` held by an entity.
+    pub fn target<R: Any>(&self, source: Entity) -> Option<Entity> {
+        self.with_component(source, |relation: &Relation<R>| relation.target)
+    }
+
+    /// Points the relation of kind `R` held by an entity at a new target, returning whether the
+    /// entity holds such a relation.
+    ///
+    /// Like replacing the relation, this runs its [`on_remove`] hooks and observers before the
+    /// change and its [`on_insert`] ones after it. Systems can retarget relations by queueing a
+    /// call through a [`WorldHandle`].
+    ///
+    /// [`on_remove`]: Self::on_remove()
+    /// [`on_insert`]: Self::on_insert()
+    /// [`WorldHandle`]: crate::command::WorldHandle
+    pub fn set_target<R: Any>(&mut self, source: Entity, target: Entity) -> bool {
+        let type_id = TypeId::of::<Relation<R>>();
+        let data = self
+            .components
+            .iter()
+            .find(|c| c.type_id == type_id)
+            .and_then(|component| component.data.get(&source).cloned());
+        let Some(data) = data else {
+            return false;
+        };
+
+        self.trigger(type_id, Lifecycle::Remove, source);
+
+        if let Some(relation) = data.lock().unwrap().downcast_mut::<Relation<R>>() {
+            relation.target = target;
+        }
+        if let Some(component) = self.components.iter_mut().find(|c| c.type_id == type_id) {
+            component.touch();
+        }
+
+        self.trigger(type_id, Lifecycle::Insert, source);
+        true
+    }
+
+    /// Returns every entity holding a relation of kind `R` to a target.
+    pub fn sources<R: 'static>(&self, target: Entity) -> Vec<Entity> {
+        self.resource::<Relations>()
+            .map(|relations| relations.lock().unwrap().sources::<R>(target))
+            .unwrap_or_default()
+    }
+
+    /// Makes an entity a child of another, so that it is despawned along with its parent.
+    pub fn add_child(&mut self, parent: Entity, child: Entity) {
+        self.add_relation(child, Relation::<ChildOf>::new(parent));
+    }
+
+    /// Returns the parent of an entity, if it is a child.
+    pub fn parent(&self, child: Entity) -> Option<Entity> {
+        self.target::<ChildOf>(child)
+    }
+
+    /// Returns every child of an entity.
+    pub fn children(&self, parent: Entity) -> Vec<Entity> {
+        self.sources::<ChildOf>(parent)
+    }
+
+    /// Removes the relations pointing at an entity from the entities holding them that satisfy a
+    /// predicate, without applying the [`OnDespawn`] policy of the relations.
+    pub(crate) fn detach_relations(&mut self, target: Entity, detach: impl Fn(Entity) -> bool) {
+        let Some(relations) = self.resource::<Relations>() else {
+            return;
+        };
+
+        let related = relations
+            .lock()
+            .unwrap()
+            .sources
+            .get(&target)
+            .into_iter()
+            .flatten()
+            .flat_map(|(type_id, sources)| sources.iter().map(move |source| (*type_id, *source)))
+            .filter(|(_, source)| detach(*source))
+            .collect::<Vec<_>>();
+
+        for (type_id, source) in related {
+            self.remove_component_by_id(type_id, source);
+        }
+    }
+
+    /// Cleans up every relation pointing at an entity that has just been despawned.
+    pub(crate) fn despawn_relations(&mut self, target: Entity) {
+        let Some(relations) = self.resource::<Relations>() else {
+            return;
+        };
+
+        let related = {
+            let mut relations = relations.lock().unwrap();
+            let sources = relations.sources.remove(&target).unwrap_or_default();
+
+            sources
+                .into_iter()
+                .flat_map(|(type_id, sources)| {
+                    let policy = relations.policies.get(&type_id).copied().unwrap_or_default();
+                    sources
+                        .into_iter()
+                        .map(move |source| (type_id, source, policy))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (type_id, source, policy) in related {
+            match policy {
+                OnDespawn::Remove => {
+                    self.remove_component_by_id(type_id, source);
+                }
+                OnDespawn::Despawn => self.despawn(source),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OnDespawn, Relation};
+    use crate::testing::Fixture;
+
+    #[derive(Clone, Debug, Default)]
+    struct Likes;
+
+    #[test]
+    fn despawning_a_target_removes_relations_to_it() {
+        let mut world = Fixture::new().build();
+        let fan = world.new_entity();
+        let star = world.new_entity();
+        world.add_relation(fan, Relation::<Likes>::new(star));
+        assert_eq!(world.sources::<Likes>(star), vec![fan]);
+
+        world.despawn(star);
+        assert!(world.contains(fan));
+        assert_eq!(world.target::<Likes>(fan), None);
+        assert!(world.sources::<Likes>(star).is_empty());
+    }
+
+    #[test]
+    fn despawning_a_target_despawns_dependents() {
+        let mut world = Fixture::new().build();
+        world.register_relation::<Likes>(OnDespawn::Despawn);
+        let fan = world.new_entity();
+        let star = world.new_entity();
+        world.add_relation(fan, Relation::<Likes>::new(star));
+
+        world.despawn(star);
+        assert!(!world.contains(fan));
+    }
+
+    #[test]
+    fn despawning_a_parent_despawns_its_descendants() {
+        let mut world = Fixture::new().build();
+        let parent = world.new_entity();
+        let child = world.new_entity();
+        let grandchild = world.new_entity();
+        let other = world.new_entity();
+        world.add_child(parent, child);
+        world.add_child(child, grandchild);
+        assert_eq!(world.children(parent), vec![child]);
+
+        world.despawn(parent);
+        assert!(!world.contains(child));
+        assert!(!world.contains(grandchild));
+        assert!(world.contains(other));
+    }
+
+    #[test]
+    fn retargeting_updates_the_index() {
+        let mut world = Fixture::new().build();
+        let fan = world.new_entity();
+        let first = world.new_entity();
+        let second = world.new_entity();
+        world.add_relation(fan, Relation::<Likes>::new(first));
+
+        assert!(world.set_target::<Likes>(fan, second));
+        assert!(world.sources::<Likes>(first).is_empty());
+        assert_eq!(world.sources::<Likes>(second), vec![fan]);
+
+        world.despawn(second);
+        assert_eq!(world.target::<Likes>(fan), None);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::component::Cloner;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+/// Global data that belongs to the world rather than to any particular entity.
+///
+/// Each type may only be stored once. Resources are stored as an [`Arc<Mutex<T>>`] under the hood
+/// so that they can be handed out to systems without downcasting.
+///
+/// Non-send resources (e.g. a window or GPU surface) are stored separately. They need not be
+/// [`Send`] or [`Clone`] but may only be accessed from the thread the world was built on, which is
+/// the main thread for an [`App`] but the simulation thread for a [`ThreadedApp`].
+///
+/// [`App`]: crate::os::App
+/// [`ThreadedApp`]: crate::os::ThreadedApp
+pub struct Resources {
+    data: HashMap<TypeId, Box<dyn Any>>,
+    erased: HashMap<TypeId, Arc<Mutex<dyn Any>>>,
+    non_send: HashMap<TypeId, Box<dyn Any>>,
+    names: HashMap<TypeId, &'static str>,
+    pub(crate) cloners: HashMap<TypeId, ResourceCloner>,
+    thread: ThreadId,
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Self {
+            data: HashMap::new(),
+            erased: HashMap::new(),
+            non_send: HashMap::new(),
+            names: HashMap::new(),
+            cloners: HashMap::new(),
+            thread: thread::current().id(),
+        }
+    }
+}
+
+/// Duplicates resources, including ones that have been removed since they were copied.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ResourceCloner {
+    pub(crate) cloner: Cloner,
+    pub(crate) reinsert: fn(&mut Resources, &dyn Any),
+}
+
+impl Resources {
+    pub(crate) fn new() -> Self {
+        Resources::default()
+    }
+
+    pub(crate) fn insert<T: Any + Clone + Send + Sync>(&mut self, value: T) {
+        self.share(Arc::new(Mutex::new(value)));
+    }
+
+    pub(crate) fn share<T: Any + Clone + Send + Sync>(&mut self, value: Arc<Mutex<T>>) {
+        self.cloners.insert(
+            TypeId::of::<T>(),
+            ResourceCloner {
+                cloner: Cloner::of::<T>(),
+                reinsert: |resources, data| {
+                    resources.insert(data.downcast_ref::<T>().unwrap().clone())
+                },
+            },
+        );
+        self.erased.insert(TypeId::of::<T>(), value.clone());
+        self.data.insert(TypeId::of::<T>(), Box::new(value));
+        self.names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
+    }
+
+    pub(crate) fn remove<T: Any>(&mut self) -> Option<Arc<Mutex<T>>> {
+        self.names.remove(&TypeId::of::<T>());
+        self.erased.remove(&TypeId::of::<T>());
+        self.cloners.remove(&TypeId::of::<T>());
+        self.data
+            .remove(&TypeId::of::<T>())
+            .and_then(|data| data.downcast::<Arc<Mutex<T>>>().ok())
+            .map(|data| *data)
+    }
+
+    pub(crate) fn share_non_send<T: Any>(&mut self, value: Arc<Mutex<T>>) {
+        self.check_thread::<T>();
+        self.non_send.insert(TypeId::of::<T>(), Box::new(value));
+        self.names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
+    }
+
+    pub(crate) fn remove_non_send<T: Any>(&mut self) -> Option<Arc<Mutex<T>>> {
+        self.check_thread::<T>();
+        self.names.remove(&TypeId::of::<T>());
+        self.non_send
+            .remove(&TypeId::of::<T>())
+            .and_then(|data| data.downcast::<Arc<Mutex<T>>>().ok())
+            .map(|data| *data)
+    }
+
+    /// Returns a non-send resource of a particular type if it exists.
+    ///
+    /// Panics if called from any thread other than the one the world was built on.
+    pub fn get_non_send<T: Any>(&self) -> Option<Arc<Mutex<T>>> {
+        self.check_thread::<T>();
+        self.non_send
+            .get(&TypeId::of::<T>())
+            .and_then(|data| data.downcast_ref::<Arc<Mutex<T>>>())
+            .cloned()
+    }
+
+    fn check_thread<T>(&self) {
+        assert_eq!(
+            thread::current().id(),
+            self.thread,
+            "non-send resource `{}` accessed off the thread the world was built on",
+            std::any::type_name::<T>()
+        );
+    }
+
+    /// Returns a resource of a particular type if it exists.
+    pub fn get<T: Any>(&self) -> Option<Arc<Mutex<T>>> {
+        self.data
+            .get(&TypeId::of::<T>())
+            .and_then(|data| data.downcast_ref::<Arc<Mutex<T>>>())
+            .cloned()
+    }
+
+    /// Returns every resource without downcasting it, keyed by its [`TypeId`].
+    pub(crate) fn iter_erased(&self) -> impl Iterator<Item = (&TypeId, &Arc<Mutex<dyn Any>>)> {
+        self.erased.iter()
+    }
+
+    pub(crate) fn get_erased(&self, type_id: &TypeId) -> Option<&Arc<Mutex<dyn Any>>> {
+        self.erased.get(type_id)
+    }
+
+    /// Returns whether a resource of a particular type exists.
+    pub fn contains<T: Any>(&self) -> bool {
+        self.data.contains_key(&TypeId::of::<T>())
+    }
+}
+
+impl fmt::Debug for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names.values()).finish()
+    }
+}
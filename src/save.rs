@@ -0,0 +1,431 @@
+use crate::{
+    reflect::{Reflect, ReflectError, TypeRegistration, Value},
+    Entity, World,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+
+/// An error produced while saving or loading a world.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The save file could not be read or written.
+    Io(std::io::Error),
+    /// The save could not be encoded.
+    Encode(String),
+    /// The save could not be decoded.
+    Decode(String),
+    /// A saved component or resource could not be recreated.
+    Reflect(ReflectError),
+    /// A saved value refers to an entity that is not part of the save.
+    UnknownEntity(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "couldn't access save: {}", e),
+            SaveError::Encode(e) => write!(f, "couldn't encode save: {}", e),
+            SaveError::Decode(e) => write!(f, "couldn't decode save: {}", e),
+            SaveError::Reflect(e) => write!(f, "{}", e),
+            SaveError::UnknownEntity(id) => write!(f, "no entity {} in the save", id),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<ReflectError> for SaveError {
+    fn from(e: ReflectError) -> Self {
+        SaveError::Reflect(e)
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+/// The encoding used when writing a save to or reading it from a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveFormat {
+    /// Human-readable JSON.
+    Json,
+    /// Compact binary encoding based on MessagePack.
+    Binary,
+}
+
+/// A single entity along with its registered components in a [`WorldSave`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedEntity {
+    /// The id the entity had in the world it was saved from.
+    pub id: u32,
+    /// Every registered component keyed by its registered name.
+    #[serde(with = "ordered_map")]
+    pub components: Vec<(String, Value)>,
+}
+
+/// The persistent state of a world.
+///
+/// Holds every entity along with all components and resources that were registered with the
+/// world's [`TypeRegistry`] and can be created from a [`Value`]. Components that cannot (such as
+/// [`Instance`], which refers to a mesh on the GPU) and unregistered types are not saved and must
+/// be reattached after loading.
+///
+/// [`TypeRegistry`]: crate::reflect::TypeRegistry
+/// [`Instance`]: crate::mesh::Instance
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WorldSave {
+    /// Every entity in the world.
+    pub entities: Vec<SavedEntity>,
+    /// Every resource keyed by its registered name.
+    #[serde(with = "ordered_map")]
+    pub resources: Vec<(String, Value)>,
+}
+
+impl WorldSave {
+    /// Encodes the save in the given format.
+    ///
+    /// Fails if a saved value holds a float that isn't finite (e.g. a velocity that became NaN),
+    /// since such a value could be written but never loaded again.
+    pub fn to_bytes(&self, format: SaveFormat) -> Result<Vec<u8>, SaveError> {
+        self.check_finite()?;
+
+        match format {
+            SaveFormat::Json => {
+                serde_json::to_vec_pretty(self).map_err(|e| SaveError::Encode(e.to_string()))
+            }
+            SaveFormat::Binary => {
+                rmp_serde::to_vec(self).map_err(|e| SaveError::Encode(e.to_string()))
+            }
+        }
+    }
+
+    /// Returns an error naming the first value that holds a float that isn't finite.
+    fn check_finite(&self) -> Result<(), SaveError> {
+        for saved in &self.entities {
+            for (name, value) in &saved.components {
+                if let Some(path) = non_finite(value) {
+                    return Err(SaveError::Encode(format!(
+                        "`{}{}` of entity {} is not finite",
+                        name, path, saved.id
+                    )));
+                }
+            }
+        }
+
+        for (name, value) in &self.resources {
+            if let Some(path) = non_finite(value) {
+                return Err(SaveError::Encode(format!("`{}{}` is not finite", name, path)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes a save from the given format.
+    pub fn from_bytes(bytes: &[u8], format: SaveFormat) -> Result<Self, SaveError> {
+        match format {
+            SaveFormat::Json => {
+                serde_json::from_slice(bytes).map_err(|e| SaveError::Decode(e.to_string()))
+            }
+            SaveFormat::Binary => {
+                rmp_serde::from_slice(bytes).map_err(|e| SaveError::Decode(e.to_string()))
+            }
+        }
+    }
+}
+
+impl World<'_> {
+    /// Captures every registered component and resource of the world in a [`WorldSave`].
+    pub fn save(&self) -> WorldSave {
+        let constructible = |(name, _): &(String, Value)| {
+            self.registry
+                .get(name)
+                .is_some_and(|registration| registration.default_value().is_some())
+        };
+
+        WorldSave {
+            entities: self
+                .entities
+                .iter()
+                .map(|entity| SavedEntity {
+                    id: entity.id(),
+                    components: self
+                        .reflect_entity(*entity)
+                        .into_iter()
+                        .filter(constructible)
+                        .collect(),
+                })
+                .collect(),
+            resources: self
+                .reflect_resources()
+                .into_iter()
+                .filter(constructible)
+                .collect(),
+        }
+    }
+
+    /// Spawns every entity of a [`WorldSave`] into the world and inserts its resources.
+    ///
+    /// Saved entities are given new ids so that they cannot collide with the entities already
+    /// present. Every reference to a saved entity held by a component or resource is remapped to
+    /// the new entity. Returns the mapping from the saved entities to the spawned ones.
+    ///
+    /// Every component and resource is recreated before anything is added, so a save that can't be
+    /// loaded leaves the world as it was.
+    pub fn load(&mut self, save: &WorldSave) -> Result<HashMap<Entity, Entity>, SaveError> {
+        let first = self
+            .current_id
+            .fetch_add(save.entities.len() as u32, Ordering::Relaxed);
+        let mapping = save
+            .entities
+            .iter()
+            .zip(first..)
+            .map(|(saved, id)| (Entity::new(saved.id), Entity::new(id)))
+            .collect::<HashMap<_, _>>();
+
+        let resources = save
+            .resources
+            .iter()
+            .map(|(name, value)| self.recreate(name, value, &mapping))
+            .collect::<Result<Vec<_>, _>>()?;
+        let entities = save
+            .entities
+            .iter()
+            .map(|saved| {
+                let components = saved
+                    .components
+                    .iter()
+                    .map(|(name, value)| self.recreate(name, value, &mapping))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((mapping[&Entity::new(saved.id)], components))
+            })
+            .collect::<Result<Vec<_>, SaveError>>()?;
+
+        for (registration, data) in resources {
+            registration.insert_resource(self, data);
+        }
+
+        for (entity, _) in &entities {
+            self.entities.push(*entity);
+        }
+
+        for (entity, components) in entities {
+            for (registration, data) in components {
+                registration.insert(self, entity, data);
+            }
+        }
+
+        Ok(mapping)
+    }
+
+    /// Creates a registered component or resource from a saved value without adding it to the
+    /// world.
+    fn recreate(
+        &self,
+        name: &str,
+        value: &Value,
+        mapping: &HashMap<Entity, Entity>,
+    ) -> Result<(TypeRegistration, Box<dyn Reflect>), SaveError> {
+        Ok(self.create_reflect(name, &remap(value, mapping)?)?)
+    }
+
+    /// Saves the world to a file in the given format.
+    pub fn save_to_file(&self, path: impl AsRef<Path>, format: SaveFormat) -> Result<(), SaveError> {
+        std::fs::write(path, self.save().to_bytes(format)?)?;
+        Ok(())
+    }
+
+    /// Loads a save from a file in the given format into the world.
+    ///
+    /// See [`load`] for how saved entities are added to the world.
+    ///
+    /// [`load`]: Self::load()
+    pub fn load_from_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        format: SaveFormat,
+    ) -> Result<HashMap<Entity, Entity>, SaveError> {
+        let _span = span!("load save", path = %path.as_ref().display());
+        let save = WorldSave::from_bytes(&std::fs::read(path)?, format)?;
+        self.load(&save)
+    }
+}
+
+/// Replaces every reference to a saved entity with the entity it was loaded as.
+///
+/// References to [`Entity::PLACEHOLDER`] are left untouched.
+fn remap(value: &Value, mapping: &HashMap<Entity, Entity>) -> Result<Value, SaveError> {
+    Ok(match value {
+        Value::Entity(e) if *e == Entity::PLACEHOLDER => value.clone(),
+        Value::Entity(e) => Value::Entity(
+            *mapping
+                .get(e)
+                .ok_or(SaveError::UnknownEntity(e.id()))?,
+        ),
+        Value::List(values) => Value::List(
+            values
+                .iter()
+                .map(|v| remap(v, mapping))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(fields) => Value::Map(
+            fields
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), remap(v, mapping)?)))
+                .collect::<Result<_, SaveError>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// Returns the path to the first float within a value that isn't finite (e.g. `.translation.x`).
+fn non_finite(value: &Value) -> Option<String> {
+    match value {
+        Value::Float(f) if !f.is_finite() => Some(String::new()),
+        Value::List(values) => values
+            .iter()
+            .enumerate()
+            .find_map(|(i, v)| non_finite(v).map(|path| format!(".{}{}", i, path))),
+        Value::Map(fields) => fields
+            .iter()
+            .find_map(|(k, v)| non_finite(v).map(|path| format!(".{}{}", k, path))),
+        _ => None,
+    }
+}
+
+/// Writes named values as a map rather than a list of pairs while keeping their order.
+mod ordered_map {
+    use crate::reflect::Value;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        entries: &[(String, Value)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(entries.iter().map(|(k, v)| (k, v)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, Value)>, D::Error> {
+        match serde::Deserialize::deserialize(deserializer)? {
+            Value::Map(entries) => Ok(entries),
+            value => Err(serde::de::Error::custom(format!(
+                "expected a map but found {}",
+                value.kind()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SaveError, SaveFormat, WorldSave};
+    use crate::{assert_component, relation::ChildOf, Entity, Name, Prefab, WorldBuilder};
+
+    #[derive(Clone, Debug, Default)]
+    struct Velocity {
+        x: f32,
+    }
+
+    crate::reflect_struct!(Velocity { x });
+
+    #[derive(Clone, Debug, Default)]
+    struct Follow {
+        leader: Entity,
+        distance: f32,
+    }
+
+    crate::reflect_struct!(Follow { leader, distance });
+
+    #[derive(Clone, Debug, Default)]
+    struct Secret;
+
+    /// Saves a world with a leader, a follower, and a child of the leader.
+    fn saved() -> WorldSave {
+        let mut world = WorldBuilder::new().with_type::<Follow>("Follow").build();
+        let leader = world.spawn_prefab(&Prefab::new().with(Name::new("leader")));
+        let follower = world.new_entity();
+        world.add_component(
+            follower,
+            Follow {
+                leader,
+                distance: 2.0,
+            },
+        );
+        world.add_component(follower, Secret);
+        let child = world.new_entity();
+        world.add_child(leader, child);
+
+        world.save()
+    }
+
+    #[test]
+    fn loading_remaps_saved_entities() {
+        let mut world = WorldBuilder::new().with_type::<Follow>("Follow").build();
+        let existing = (0..5).map(|_| world.new_entity()).collect::<Vec<_>>();
+
+        let mapping = world.load(&saved()).unwrap();
+        assert_eq!(mapping.len(), 3);
+        assert!(mapping.values().all(|entity| !existing.contains(entity)));
+
+        let leader = mapping[&Entity::new(0)];
+        let follower = mapping[&Entity::new(1)];
+        let child = mapping[&Entity::new(2)];
+        assert_eq!(world.find_by_name("leader"), Some(leader));
+        assert_component!(world, follower, Follow, |f| f.leader == leader && f.distance == 2.0);
+        assert_eq!(world.with_component(follower, |_: &Secret| ()), None);
+        assert_eq!(world.target::<ChildOf>(child), Some(leader));
+        assert_eq!(world.children(leader), vec![child]);
+    }
+
+    #[test]
+    fn saves_survive_encoding() {
+        let save = saved();
+        for format in [SaveFormat::Json, SaveFormat::Binary] {
+            let decoded = WorldSave::from_bytes(&save.to_bytes(format).unwrap(), format).unwrap();
+            let mut world = WorldBuilder::new().with_type::<Follow>("Follow").build();
+            let mapping = world.load(&decoded).unwrap();
+
+            let leader = mapping[&Entity::new(0)];
+            assert_component!(world, mapping[&Entity::new(1)], Follow, |f| f.leader == leader);
+        }
+    }
+
+    #[test]
+    fn failed_loads_leave_the_world_untouched() {
+        let mut save = saved();
+        save.entities.remove(0);
+        let mut world = WorldBuilder::new().with_type::<Follow>("Follow").build();
+        assert!(matches!(world.load(&save), Err(SaveError::UnknownEntity(0))));
+
+        let mut unregistered = WorldBuilder::new().build();
+        assert!(matches!(unregistered.load(&saved()), Err(SaveError::Reflect(_))));
+
+        for world in [world, unregistered] {
+            assert!(world.entities.is_empty());
+            assert_eq!(world.find_by_name("leader"), None);
+        }
+    }
+
+    #[test]
+    fn non_finite_floats_fail_to_encode() {
+        let mut world = WorldBuilder::new().with_type::<Velocity>("Velocity").build();
+        let ball = world.new_entity();
+        world.add_component(ball, Velocity { x: f32::NAN });
+
+        for format in [SaveFormat::Json, SaveFormat::Binary] {
+            match world.save().to_bytes(format) {
+                Err(SaveError::Encode(message)) => {
+                    assert_eq!(message, "`Velocity.x` of entity 0 is not finite")
+                }
+                other => panic!("expected an encoding error but got {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,648 @@
+use crate::{
+    camera::FlyCamera,
+    mesh::{res_path, Instance, LoadError, Model},
+    reflect::{Reflect, ReflectError, TypeRegistration, Value},
+    texture::TextureBuilder,
+    Entity, World,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// An error produced while loading or instantiating a scene.
+#[derive(Debug)]
+pub enum SceneError {
+    /// The scene file could not be read.
+    Io(std::io::Error),
+    /// The scene file could not be parsed.
+    Parse(String),
+    /// The scene file has an extension other than `.json` or `.ron`.
+    UnknownFormat(String),
+    /// A component or resource could not be created from its description.
+    Reflect(ReflectError),
+    /// A model or texture could not be loaded.
+    Asset(LoadError),
+    /// An entity describes an instance without describing the model it instances.
+    ///
+    /// The entity is given by its index in the scene followed by the index of every child leading
+    /// to it (a copy spawned with [`Scene::spawn`] has an index of zero).
+    MissingModel(Vec<usize>),
+    /// A value refers to an entity that is not part of the scene.
+    UnknownEntity(u32),
+    /// An entity is based on a prefab that is not part of the scene.
+    UnknownPrefab(String),
+    /// A prefab is based on itself, directly or through other prefabs.
+    RecursivePrefab(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "couldn't read scene: {}", e),
+            SceneError::Parse(e) => write!(f, "couldn't parse scene: {}", e),
+            SceneError::UnknownFormat(ext) => write!(f, "unknown scene format `{}`", ext),
+            SceneError::Reflect(e) => write!(f, "{}", e),
+            SceneError::Asset(e) => write!(f, "{}", e),
+            SceneError::MissingModel(path) => {
+                write!(f, "entity {}", path[0])?;
+                for child in &path[1..] {
+                    write!(f, " > child {}", child)?;
+                }
+                write!(f, " has an instance but no model")
+            }
+            SceneError::UnknownEntity(i) => write!(f, "no entity {} in the scene", i),
+            SceneError::UnknownPrefab(name) => write!(f, "no prefab `{}` in the scene", name),
+            SceneError::RecursivePrefab(name) => write!(f, "prefab `{}` is based on itself", name),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<ReflectError> for SceneError {
+    fn from(e: ReflectError) -> Self {
+        SceneError::Reflect(e)
+    }
+}
+
+impl From<LoadError> for SceneError {
+    fn from(e: LoadError) -> Self {
+        SceneError::Asset(e)
+    }
+}
+
+/// Models already loaded by a scene, keyed by the obj file and texture they were loaded from.
+type Models = HashMap<(String, Option<String>), Model>;
+
+/// An entity of a scene along with its children, whose components have all been created but not
+/// yet added to a world.
+struct Built {
+    entity: Entity,
+    instance: Option<Instance>,
+    model: Option<Model>,
+    camera: bool,
+    components: Vec<(TypeRegistration, Box<dyn Reflect>)>,
+    children: Vec<Built>,
+}
+
+/// Describes the model of an entity by the files it should be loaded from.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SceneModel {
+    /// The obj file, relative to the res folder, from which the mesh is loaded.
+    pub obj: String,
+    /// An optional image, relative to the res folder, from which the texture is loaded.
+    #[serde(default)]
+    pub texture: Option<String>,
+}
+
+/// Describes a single entity of a scene.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SceneEntity {
+    /// The model of the entity. Entities describing the same files share a mesh.
+    pub model: Option<SceneModel>,
+    /// The fields of the [`Instance`] of the model (`translation`, `scale`, and `rotation`).
+    ///
+    /// [`Instance`]: crate::mesh::Instance
+    pub instance: Option<Value>,
+    /// Whether the camera of the scene should be shared with the entity as a component.
+    pub camera: bool,
+    /// Any other components keyed by the name they were registered with in the [`TypeRegistry`].
+    ///
+    /// [`TypeRegistry`]: crate::reflect::TypeRegistry
+    pub components: Value,
+    /// The name of a prefab of the scene the entity is based on.
+    ///
+    /// The model and camera of the prefab are used unless the entity describes its own, the
+    /// fields of its instance and components are overridden by any the entity describes, and its
+    /// children are spawned before the entity's own.
+    pub prefab: Option<String>,
+    /// Entities spawned along with the entity and related to it with [`ChildOf`].
+    ///
+    /// [`ChildOf`]: crate::relation::ChildOf
+    pub children: Vec<SceneEntity>,
+}
+
+/// A declarative description of a world.
+///
+/// Describes entities and their components, resources, the camera, and the fixed frequency in a
+/// json-like file so that simple programs need little more than their systems to be written in
+/// Rust. Scenes may be written in JSON or RON:
+///
+/// ```ron
+/// (
+///     frequency: 60,
+///     camera: { "eye": [2.0, 3.0, 4.0], "dir": [1.0, 0.0, 0.0] },
+///     resources: { "Gravity": { "y": -9.8 } },
+///     entities: [
+///         (
+///             model: (obj: "sphere.obj", texture: "hello_texture.jpg"),
+///             instance: { "translation": [0.0, 3.0, 0.0] },
+///             components: { "Velocity": { "x": 1.0 } },
+///         ),
+///     ],
+/// )
+/// ```
+///
+/// Any value of the form `{"$entity": n}` refers to the `n`th entity of the scene and is
+/// replaced by the spawned entity when the scene is instantiated.
+///
+/// Entities that are spawned many times may be described once as a prefab and then refer to it by
+/// name, overriding only what differs. Prefabs may themselves be based on other prefabs and may
+/// have children:
+///
+/// ```ron
+/// (
+///     prefabs: {
+///         "ball": (
+///             model: (obj: "sphere.obj"),
+///             instance: { "scale": [0.5, 0.5, 0.5] },
+///             components: { "Velocity": { "x": 0.0 } },
+///             children: [(components: { "Trail": { "length": 20 } })],
+///         ),
+///     },
+///     entities: [
+///         (prefab: "ball", instance: { "translation": [0.0, 3.0, 0.0] }),
+///         (prefab: "ball", components: { "Velocity": { "x": 1.0 } }),
+///     ],
+/// )
+/// ```
+///
+/// Prefabs can also be spawned after the scene has been instantiated with [`spawn`].
+///
+/// [`spawn`]: Self::spawn()
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    /// The number of times per second fixed systems should be called.
+    pub frequency: Option<u16>,
+    /// The fields of the [`FlyCamera`] used to view the scene.
+    ///
+    /// [`FlyCamera`]: crate::camera::FlyCamera
+    pub camera: Option<Value>,
+    /// Resources keyed by the name they were registered with in the [`TypeRegistry`].
+    ///
+    /// [`TypeRegistry`]: crate::reflect::TypeRegistry
+    pub resources: Value,
+    /// Every entity in the scene.
+    pub entities: Vec<SceneEntity>,
+    /// Descriptions of entities keyed by the name other entities refer to them by.
+    pub prefabs: HashMap<String, SceneEntity>,
+    #[serde(skip)]
+    camera_handle: Option<Arc<Mutex<FlyCamera>>>,
+    #[serde(skip)]
+    models: Arc<Mutex<Models>>,
+}
+
+impl Scene {
+    /// Loads a scene from a file in the res folder.
+    ///
+    /// The format is determined by the extension of the file, which must be either `.json` or
+    /// `.ron`.
+    pub fn load(file_name: &str) -> Result<Self, SceneError> {
+        let _span = span!("load scene", file = file_name);
+        let text = std::fs::read_to_string(res_path(file_name)).map_err(SceneError::Io)?;
+
+        match std::path::Path::new(file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("json") => Scene::from_json(&text),
+            Some("ron") => Scene::from_ron(&text),
+            ext => Err(SceneError::UnknownFormat(ext.unwrap_or_default().to_string())),
+        }
+    }
+
+    /// Parses a scene written in JSON.
+    pub fn from_json(text: &str) -> Result<Self, SceneError> {
+        serde_json::from_str::<Scene>(text)
+            .map_err(|e| SceneError::Parse(e.to_string()))?
+            .prepared()
+    }
+
+    /// Parses a scene written in RON.
+    pub fn from_ron(text: &str) -> Result<Self, SceneError> {
+        ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_str::<Scene>(text)
+            .map_err(|e| SceneError::Parse(e.to_string()))?
+            .prepared()
+    }
+
+    fn prepared(mut self) -> Result<Self, SceneError> {
+        let mut camera = FlyCamera::default();
+        if let Some(value) = &self.camera {
+            camera.set_value(value)?;
+        }
+
+        self.camera_handle = Some(Arc::new(Mutex::new(camera)));
+        Ok(self)
+    }
+
+    /// Returns the camera described by the scene.
+    ///
+    /// The same camera is shared with every entity marked with `camera`, so it should be handed
+    /// to the [`App`] in order for those entities to control the view.
+    ///
+    /// [`App`]: crate::App
+    pub fn camera(&self) -> Arc<Mutex<FlyCamera>> {
+        self.camera_handle
+            .clone()
+            .unwrap_or_else(|| Arc::new(Mutex::new(FlyCamera::default())))
+    }
+
+    /// Spawns every entity of the scene into a world and inserts its resources.
+    ///
+    /// Returns the spawned entities in the order they are described in the scene, not including
+    /// their children. Every component and resource other than models, instances, and the camera
+    /// must have been registered with the world's [`TypeRegistry`].
+    ///
+    /// Every model, component, and resource is created before anything is added, so a scene that
+    /// can't be instantiated leaves the world as it was.
+    ///
+    /// [`TypeRegistry`]: crate::reflect::TypeRegistry
+    pub fn instantiate(&self, world: &mut World<'_>) -> Result<Vec<Entity>, SceneError> {
+        let _span = span!("instantiate scene", entities = self.entities.len());
+        let resources = entries(&self.resources)
+            .map(|(name, value)| world.create_reflect(name, value))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let entities = self
+            .entities
+            .iter()
+            .map(|_| world.reserve_entity())
+            .collect::<Vec<_>>();
+        let built = self
+            .entities
+            .iter()
+            .zip(&entities)
+            .enumerate()
+            .map(|(index, (description, &entity))| {
+                let description = self.resolve(description, &mut Vec::new())?;
+                self.build(world, &description, entity, &[index], &entities)
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        for (registration, data) in resources {
+            registration.insert_resource(world, data);
+        }
+
+        for built in &built {
+            add_entities(built, &mut world.entities);
+        }
+
+        for built in built {
+            self.insert(world, built);
+        }
+
+        Ok(entities)
+    }
+
+    /// Spawns a single entity described in the same way as the entities of the scene, returning
+    /// the entity.
+    ///
+    /// This is mostly useful for spawning copies of a prefab of the scene while the world is
+    /// running, overriding whatever differs between them:
+    ///
+    /// ```
+    /// # use aspeng::{scene::{Scene, SceneEntity}, Value, WorldBuilder};
+    /// # #[derive(Clone, Default)]
+    /// # struct Velocity {
+    /// #     x: f32,
+    /// # }
+    /// # aspeng::reflect_struct!(Velocity { x });
+    /// # let mut world = WorldBuilder::new().with_type::<Velocity>("Velocity").build();
+    /// # let scene = Scene::from_ron(r#"(prefabs: {"ball": (components: {"Velocity": {"x": 0.0}})})"#)?;
+    /// let velocity = Value::Map(vec![("x".to_string(), Value::Float(1.0))]);
+    /// let ball = scene.spawn(&mut world, &SceneEntity {
+    ///     prefab: Some("ball".to_string()),
+    ///     components: Value::Map(vec![("Velocity".to_string(), velocity)]),
+    ///     ..Default::default()
+    /// })?;
+    /// # assert_eq!(world.with_component(ball, |v: &Velocity| v.x), Some(1.0));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// Since the entities of the scene may have been despawned in the meantime, the description
+    /// can't refer to them with `{"$entity": n}`.
+    pub fn spawn(
+        &self,
+        world: &mut World<'_>,
+        description: &SceneEntity,
+    ) -> Result<Entity, SceneError> {
+        let description = self.resolve(description, &mut Vec::new())?;
+        let built = self.build(world, &description, world.reserve_entity(), &[0], &[])?;
+        let entity = built.entity;
+        add_entities(&built, &mut world.entities);
+        self.insert(world, built);
+
+        Ok(entity)
+    }
+
+    /// Merges an entity with the prefabs it is based on, along with all of its children.
+    fn resolve(
+        &self,
+        description: &SceneEntity,
+        visiting: &mut Vec<String>,
+    ) -> Result<SceneEntity, SceneError> {
+        let mut resolved = match &description.prefab {
+            Some(name) => {
+                if visiting.contains(name) {
+                    return Err(SceneError::RecursivePrefab(name.clone()));
+                }
+
+                let prefab = self
+                    .prefabs
+                    .get(name)
+                    .ok_or_else(|| SceneError::UnknownPrefab(name.clone()))?;
+                visiting.push(name.clone());
+                let base = self.resolve(prefab, visiting)?;
+                visiting.pop();
+
+                SceneEntity {
+                    model: description.model.clone().or(base.model),
+                    instance: match (base.instance, &description.instance) {
+                        (Some(base), Some(instance)) => Some(merge(base, instance)),
+                        (base, instance) => instance.clone().or(base),
+                    },
+                    camera: description.camera || base.camera,
+                    components: merge(base.components, &description.components),
+                    prefab: None,
+                    children: base
+                        .children
+                        .into_iter()
+                        .chain(description.children.iter().cloned())
+                        .collect(),
+                }
+            }
+            None => description.clone(),
+        };
+
+        resolved.children = resolved
+            .children
+            .iter()
+            .map(|child| self.resolve(child, &mut Vec::new()))
+            .collect::<Result<_, _>>()?;
+
+        Ok(resolved)
+    }
+
+    /// Loads the model described by the files, reusing the model already loaded from the same
+    /// files.
+    fn load_model(&self, model: &SceneModel) -> Result<Model, SceneError> {
+        let key = (model.obj.clone(), model.texture.clone());
+        if let Some(loaded) = self.models.lock().unwrap().get(&key) {
+            return Ok(loaded.clone());
+        }
+
+        let mut loaded = Model::try_from_obj(&model.obj)?;
+        if let Some(texture) = &model.texture {
+            loaded = loaded.with_tex(TextureBuilder::try_from_image(texture)?);
+        }
+
+        self.models.lock().unwrap().insert(key, loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Creates the components of a resolved entity and its children without adding anything to
+    /// the world.
+    ///
+    /// The path is the index of the entity in the scene followed by the index of every child
+    /// leading to it.
+    fn build(
+        &self,
+        world: &World<'_>,
+        description: &SceneEntity,
+        entity: Entity,
+        path: &[usize],
+        entities: &[Entity],
+    ) -> Result<Built, SceneError> {
+        let model = match &description.model {
+            Some(model) => Some(self.load_model(model)?),
+            None => None,
+        };
+
+        let instance = match &description.instance {
+            Some(value) => {
+                let model = model
+                    .as_ref()
+                    .ok_or_else(|| SceneError::MissingModel(path.to_vec()))?;
+                let mut instance = Instance::new(&model.mesh);
+                instance.set_value(&remap(value, entities)?)?;
+                Some(instance)
+            }
+            None => None,
+        };
+
+        let components = entries(&description.components)
+            .map(|(name, value)| Ok(world.create_reflect(name, &remap(value, entities)?)?))
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        let children = description
+            .children
+            .iter()
+            .enumerate()
+            .map(|(i, child)| {
+                let path = [path, &[i]].concat();
+                self.build(world, child, world.reserve_entity(), &path, entities)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Built {
+            entity,
+            instance,
+            model,
+            camera: description.camera,
+            components,
+            children,
+        })
+    }
+
+    /// Adds the components of a built entity to the world, then those of its children.
+    fn insert(&self, world: &mut World<'_>, built: Built) {
+        let entity = built.entity;
+        if let Some(instance) = built.instance {
+            world.add_component(entity, instance);
+        }
+
+        if let Some(model) = built.model {
+            world.add_component(entity, model);
+        }
+
+        if built.camera {
+            world.share_component(entity, self.camera());
+        }
+
+        for (registration, data) in built.components {
+            registration.insert(world, entity, data);
+        }
+
+        for child in built.children {
+            world.add_child(entity, child.entity);
+            self.insert(world, child);
+        }
+    }
+}
+
+/// Adds a built entity and all of its children to the entities of a world, so that every entity
+/// of a scene exists before any component refers to it.
+fn add_entities(built: &Built, entities: &mut Vec<Entity>) {
+    entities.push(built.entity);
+    for child in &built.children {
+        add_entities(child, entities);
+    }
+}
+
+fn entries(value: &Value) -> impl Iterator<Item = (&str, &Value)> {
+    let fields: &[(String, Value)] = match value {
+        Value::Map(fields) => fields,
+        _ => &[],
+    };
+
+    fields.iter().map(|(k, v)| (k.as_str(), v))
+}
+
+/// Overrides the fields of a value with those of another, keeping any fields the other leaves out.
+fn merge(base: Value, other: &Value) -> Value {
+    match (base, other) {
+        (Value::Map(mut fields), Value::Map(others)) => {
+            for (name, value) in others {
+                match fields.iter_mut().find(|(field, _)| field == name) {
+                    Some((_, field)) => *field = merge(std::mem::take(field), value),
+                    None => fields.push((name.clone(), value.clone())),
+                }
+            }
+            Value::Map(fields)
+        }
+        (base, Value::Null) => base,
+        (_, other) => other.clone(),
+    }
+}
+
+/// Replaces every reference to an entity by its index in the scene with the spawned entity.
+fn remap(value: &Value, entities: &[Entity]) -> Result<Value, SceneError> {
+    Ok(match value {
+        Value::Entity(e) => Value::Entity(
+            *entities
+                .get(e.id() as usize)
+                .ok_or(SceneError::UnknownEntity(e.id()))?,
+        ),
+        Value::List(values) => Value::List(
+            values
+                .iter()
+                .map(|v| remap(v, entities))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(fields) => Value::Map(
+            fields
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), remap(v, entities)?)))
+                .collect::<Result<_, SceneError>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scene, SceneError};
+    use crate::{relation::ChildOf, Entity, World, WorldBuilder};
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Follow {
+        leader: Entity,
+    }
+
+    crate::reflect_struct!(Follow { leader });
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Gravity {
+        y: f32,
+    }
+
+    crate::reflect_struct!(Gravity { y });
+
+    fn world<'a>() -> World<'a> {
+        WorldBuilder::new()
+            .with_type::<Follow>("Follow")
+            .with_type::<Gravity>("Gravity")
+            .build()
+    }
+
+    #[test]
+    fn entities_refer_to_each_other_by_index() {
+        let scene = Scene::from_ron(
+            r#"(
+                resources: {"Gravity": {"y": -9.8}},
+                entities: [
+                    (components: {"Follow": {"leader": {"$entity": 1}}}),
+                    (children: [(), ()]),
+                ],
+            )"#,
+        )
+        .unwrap();
+        let mut world = world();
+
+        let entities = scene.instantiate(&mut world).unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(
+            world.with_component(entities[0], |f: &Follow| f.leader),
+            Some(entities[1])
+        );
+        assert_eq!(world.children(entities[1]).len(), 2);
+        assert_eq!(
+            world.resource::<Gravity>().map(|g| g.lock().unwrap().y),
+            Some(-9.8)
+        );
+    }
+
+    #[test]
+    fn failed_scenes_leave_the_world_untouched() {
+        let scene = Scene::from_ron(
+            r#"(
+                resources: {"Gravity": {"y": -9.8}},
+                entities: [
+                    (children: [()]),
+                    (components: {"Unknown": {}}),
+                ],
+            )"#,
+        )
+        .unwrap();
+        let mut world = world();
+
+        assert!(matches!(
+            scene.instantiate(&mut world),
+            Err(SceneError::Reflect(_))
+        ));
+        assert!(world.entities.is_empty());
+        assert!(world.resource::<Gravity>().is_none());
+        for id in 0..3 {
+            assert_eq!(world.target::<ChildOf>(Entity::new(id)), None);
+        }
+    }
+
+    #[test]
+    fn missing_models_are_reported_by_path() {
+        let scene = Scene::from_ron(
+            r#"(
+                entities: [
+                    (),
+                    (children: [(), (children: [(instance: {"scale": [2.0, 2.0, 2.0]})])]),
+                ],
+            )"#,
+        )
+        .unwrap();
+        let mut world = world();
+
+        match scene.instantiate(&mut world) {
+            Err(SceneError::MissingModel(path)) => assert_eq!(path, vec![1, 1, 0]),
+            other => panic!("expected a missing model but got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(
+            SceneError::MissingModel(vec![1, 1, 0]).to_string(),
+            "entity 1 > child 1 > child 0 has an instance but no model"
+        );
+        assert!(world.entities.is_empty());
+    }
+}
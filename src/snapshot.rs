@@ -0,0 +1,469 @@
+use crate::{
+    component::Cloner,
+    console::Console,
+    diagnostics::Diagnostics,
+    entity::{Names, Tags},
+    observer::Lifecycle,
+    prefab::Prefabs,
+    relation::Relations,
+    resource::ResourceCloner,
+    task::TaskPool,
+    Entity, World,
+};
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// The state of every entity, component, and resource of a world at a particular point in time.
+///
+/// Snapshots are taken with [`World::snapshot`] and applied with [`World::restore`], which makes
+/// them suitable for rolling back a simulation (e.g. for rollback netcode or after a detected
+/// instability). The captured data is immutable and shared between clones of a snapshot, so
+/// cloning a snapshot in order to branch off several alternatives is cheap. Components that
+/// haven't been written to since the previous snapshot share their data with it as well. Since
+/// clonable components and resources are [`Send`] and [`Sync`], so are snapshots, which lets them
+/// be restored into worlds on other threads (e.g. to evaluate alternative parameters in
+/// parallel).
+///
+/// Systems are not part of a snapshot, nor is the time that has accumulated towards the next
+/// fixed step. Resources that belong to the engine rather than the simulation (the
+/// [`Console`], [`Diagnostics`], [`TaskPool`], and [`Prefabs`], along with the [`Names`],
+/// [`Tags`], and [`Relations`] indices, which are kept up to date by the components they index)
+/// are left out.
+///
+/// [`World::snapshot`]: crate::World::snapshot()
+/// [`World::restore`]: crate::World::restore()
+#[derive(Clone)]
+pub struct Snapshot {
+    entities: Vec<Entity>,
+    current_id: u32,
+    components: Vec<CapturedComponent>,
+    resources: Vec<CapturedResource>,
+}
+
+/// Every component of a single type, copied when the store was at a particular version.
+#[derive(Clone)]
+pub(crate) struct CapturedComponent {
+    type_id: TypeId,
+    cloner: Cloner,
+    version: u64,
+    data: Arc<HashMap<Entity, Arc<dyn Any + Send + Sync>>>,
+}
+
+#[derive(Clone)]
+struct CapturedResource {
+    type_id: TypeId,
+    cloner: ResourceCloner,
+    data: Arc<dyn Any + Send + Sync>,
+}
+
+/// Returns whether a resource belongs to the engine and is therefore left out of snapshots.
+fn is_engine_resource(type_id: TypeId) -> bool {
+    [
+        TypeId::of::<Console>(),
+        TypeId::of::<Diagnostics>(),
+        TypeId::of::<TaskPool>(),
+        TypeId::of::<Prefabs>(),
+        TypeId::of::<Names>(),
+        TypeId::of::<Tags>(),
+        TypeId::of::<Relations>(),
+    ]
+    .contains(&type_id)
+}
+
+impl Snapshot {
+    /// Returns every entity that existed when the snapshot was taken.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Returns every captured component of an entity along with its type.
+    pub(crate) fn captured(&self, entity: Entity) -> impl Iterator<Item = (TypeId, &dyn Any)> {
+        self.components.iter().filter_map(move |captured| {
+            let data = captured.data.get(&entity)?;
+            Some((captured.type_id, &**data as &dyn Any))
+        })
+    }
+}
+
+impl std::fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("entities", &self.entities)
+            .field("components", &self.components.len())
+            .field("resources", &self.resources.len())
+            .finish()
+    }
+}
+
+impl World<'_> {
+    /// Captures the current state of the world in memory.
+    ///
+    /// Every component and resource is copied, so later changes to the world do not affect the
+    /// snapshot. Components of a type that hasn't been written to since the previous snapshot
+    /// reuse the copies made back then, so taking snapshots often is cheap when little changes
+    /// between them. A type counts as written to once a system was given write access to it (e.g.
+    /// through [`Query::all`] or [`Query::get`]), but not when systems only [read] it. Components
+    /// that aren't clonable (see [`register_clone`]) and non-send resources are skipped.
+    ///
+    /// [`register_clone`]: Self::register_clone()
+    /// [`Query::all`]: crate::system::Query::all()
+    /// [`Query::get`]: crate::system::Query::get()
+    /// [read]: crate::system::Query::read()
+    pub fn snapshot(&self) -> Snapshot {
+        let mut captures = self.captures.borrow_mut();
+
+        Snapshot {
+            entities: self.entities.clone(),
+            current_id: self.current_id.load(Ordering::Relaxed),
+            components: self
+                .components
+                .iter()
+                .filter_map(|component| {
+                    let cloner = component.cloner?;
+                    if let Some(captured) = captures.get(&component.type_id) {
+                        if captured.version == component.version && !component.shared {
+                            return Some(captured.clone());
+                        }
+                    }
+
+                    let data = component
+                        .data
+                        .iter()
+                        .map(|(entity, data)| (*entity, (cloner.freeze)(&*data.lock().unwrap())))
+                        .collect();
+                    let captured = CapturedComponent {
+                        type_id: component.type_id,
+                        cloner,
+                        version: component.version,
+                        data: Arc::new(data),
+                    };
+
+                    captures.insert(component.type_id, captured.clone());
+                    Some(captured)
+                })
+                .collect(),
+            resources: self
+                .resources
+                .cloners
+                .iter()
+                .filter(|(type_id, _)| !is_engine_resource(**type_id))
+                .filter_map(|(type_id, cloner)| {
+                    let data = self.resources.get_erased(type_id)?.lock().unwrap();
+                    Some(CapturedResource {
+                        type_id: *type_id,
+                        cloner: *cloner,
+                        data: (cloner.cloner.freeze)(&*data),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the world to the state captured by a snapshot.
+    ///
+    /// Entities spawned since the snapshot was taken are despawned and entities that were present
+    /// are given back the components they held at the time. Components are added, removed, and
+    /// replaced the same way they would be otherwise, so every hook and observer sees the
+    /// changes (e.g. the renderer stops drawing despawned entities). Components that still exist
+    /// are overwritten in place between their [`Remove`] and [`Insert`] events, so any [`Arc`]
+    /// shared with the world (e.g. through [`share_component`]) keeps pointing at the live data.
+    /// Components of a type that hasn't been written to since the snapshot was taken are left
+    /// alone.
+    ///
+    /// Resources inserted after the snapshot was taken are left untouched, as are components that
+    /// aren't clonable (see [`register_clone`]) on entities that still exist. Entity ids
+    /// are never handed out twice, even if they were reserved after the snapshot was taken.
+    ///
+    /// The same snapshot may be restored any number of times.
+    ///
+    /// [`Remove`]: crate::observer::Lifecycle::Remove
+    /// [`Insert`]: crate::observer::Lifecycle::Insert
+    /// [`share_component`]: Self::share_component()
+    /// [`register_clone`]: Self::register_clone()
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let _span = span!("restore snapshot");
+        let existed = snapshot.entities.iter().copied().collect::<HashSet<_>>();
+        let spawned = self
+            .entities
+            .iter()
+            .copied()
+            .filter(|entity| !existed.contains(entity))
+            .collect::<Vec<_>>();
+
+        // entities that are kept may have been related to entities spawned since (e.g. made their
+        // children), which must not drag them along when those are despawned
+        for &entity in &spawned {
+            self.detach_relations(entity, |source| existed.contains(&source));
+        }
+
+        for entity in spawned {
+            if self.contains(entity) {
+                self.despawn(entity);
+            }
+        }
+
+        self.entities = snapshot.entities.clone();
+        self.current_id
+            .fetch_max(snapshot.current_id, Ordering::Relaxed);
+
+        // types that didn't exist yet are removed from every entity
+        let captured = snapshot
+            .components
+            .iter()
+            .map(|captured| captured.type_id)
+            .collect::<HashSet<_>>();
+        let added = self
+            .components
+            .iter()
+            .filter(|c| c.cloner.is_some() && !captured.contains(&c.type_id))
+            .flat_map(|c| c.data.keys().map(move |entity| (c.type_id, entity)))
+            .collect::<Vec<_>>();
+        for (type_id, entity) in added {
+            self.remove_component_by_id(type_id, entity);
+        }
+
+        for captured in &snapshot.components {
+            self.restore_component(captured);
+        }
+
+        for CapturedResource {
+            type_id,
+            cloner,
+            data,
+        } in &snapshot.resources
+        {
+            match self.resources.get_erased(type_id) {
+                Some(live) => (cloner.cloner.overwrite)(&**data, &mut *live.lock().unwrap()),
+                None => (cloner.reinsert)(&mut self.resources, &**data),
+            }
+        }
+    }
+
+    /// Gives every entity back the component of a type it held when it was captured.
+    fn restore_component(&mut self, captured: &CapturedComponent) {
+        let CapturedComponent {
+            type_id,
+            cloner,
+            data,
+            ..
+        } = captured;
+
+        match self.components.iter_mut().find(|c| c.type_id == *type_id) {
+            Some(component) if component.version == captured.version && !component.shared => return,
+            Some(component) => {
+                component.cloner.get_or_insert(*cloner);
+            }
+            None => self
+                .components
+                .push(crate::Component::new(*type_id).with_cloner(*cloner)),
+        }
+
+        let held = |world: &World<'_>, entity: &Entity| {
+            world
+                .components
+                .iter()
+                .any(|c| c.type_id == *type_id && c.data.contains_key(entity))
+        };
+
+        let removed = self
+            .components
+            .iter()
+            .find(|c| c.type_id == *type_id)
+            .map(|c| c.data.keys().filter(|e| !data.contains_key(e)).collect::<Vec<_>>())
+            .unwrap_or_default();
+        for entity in removed {
+            self.remove_component_by_id(*type_id, entity);
+        }
+
+        for (entity, value) in data.iter() {
+            if !self.contains(*entity) {
+                // despawned by a hook while restoring
+                continue;
+            }
+
+            if held(self, entity) {
+                self.trigger(*type_id, Lifecycle::Remove, *entity);
+            }
+
+            let live = self
+                .components
+                .iter()
+                .find(|c| c.type_id == *type_id)
+                .and_then(|c| c.data.get(entity))
+                .cloned();
+            match live {
+                Some(live) => {
+                    (cloner.overwrite)(&**value, &mut *live.lock().unwrap());
+                    self.trigger(*type_id, Lifecycle::Insert, *entity);
+                }
+                None => {
+                    self.share_component_by_id(*entity, *type_id, (cloner.thaw)(&**value), Some(*cloner))
+                }
+            }
+        }
+
+        // the store holds exactly the captured data again, so the next snapshot can share it
+        if let Some(component) = self.components.iter_mut().find(|c| c.type_id == *type_id) {
+            component.touch();
+            self.captures.borrow_mut().insert(
+                *type_id,
+                CapturedComponent {
+                    version: component.version,
+                    ..captured.clone()
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assert_component, testing::Fixture, type_ids, Entity, Prefab, Query, System, World,
+    };
+
+    #[derive(Clone, Debug, Default)]
+    struct Body {
+        x: f32,
+        speed: f32,
+    }
+
+    fn movement(mut query: Query) {
+        query.each(|body: &mut Body| body.x += body.speed);
+    }
+
+    fn rolling_ball<'a>() -> World<'a> {
+        Fixture::new()
+            .with_entity("ball", Prefab::new().with(Body { x: 0.0, speed: 1.0 }))
+            .with_fixed_system(System::new(type_ids![Body], movement))
+            .build()
+    }
+
+    fn body_copy(snapshot: &super::Snapshot, entity: Entity) -> *const () {
+        snapshot
+            .captured(entity)
+            .find_map(|(_, data)| data.downcast_ref::<Body>())
+            .map(|body| body as *const Body as *const ())
+            .unwrap()
+    }
+
+    #[test]
+    fn restore_rewinds_components() {
+        let mut world = rolling_ball();
+        let ball = world.find_by_name("ball").unwrap();
+
+        world.step_n(5).unwrap();
+        let snapshot = world.snapshot();
+        world.step_n(5).unwrap();
+        assert_component!(world, ball, Body, |b| b.x == 10.0);
+
+        world.restore(&snapshot);
+        assert_component!(world, ball, Body, |b| b.x == 5.0);
+
+        world.step_n(1).unwrap();
+        assert_component!(world, ball, Body, |b| b.x == 6.0);
+    }
+
+    #[test]
+    fn restore_undoes_spawns_and_despawns() {
+        let mut world = rolling_ball();
+        let ball = world.find_by_name("ball").unwrap();
+        let snapshot = world.snapshot();
+
+        let late = world.new_entity();
+        world.add_component(late, Body::default());
+        world.despawn(ball);
+        assert_eq!(world.find_by_name("ball"), None);
+
+        world.restore(&snapshot);
+        assert!(!world.contains(late));
+        assert_eq!(world.find_by_name("ball"), Some(ball));
+        assert_component!(world, ball, Body, |b| b.speed == 1.0);
+
+        let next = world.new_entity();
+        assert_ne!(next, late);
+    }
+
+    #[test]
+    fn restore_keeps_entities_related_to_later_spawns() {
+        // not clonable, so it is lost if the child is despawned and recreated from the snapshot
+        #[derive(Debug)]
+        struct Handle;
+
+        let mut world = Fixture::new()
+            .with_entity("child", Prefab::new().with(Body::default()))
+            .build();
+        let child = world.find_by_name("child").unwrap();
+        world.add_component(child, Handle);
+        let snapshot = world.snapshot();
+
+        let parent = world.new_entity();
+        world.add_child(parent, child);
+
+        world.restore(&snapshot);
+        assert!(!world.contains(parent));
+        assert!(world.contains(child));
+        assert_eq!(world.parent(child), None);
+        assert_component!(world, child, Body, |b| b.x == 0.0);
+        assert_component!(world, child, Handle);
+    }
+
+    #[test]
+    fn restore_rewinds_randomness() {
+        let mut world = Fixture::new().build();
+        let snapshot = world.snapshot();
+        let first = world.with_rng("dice", |rng| rng.next_u64()).unwrap();
+
+        world.restore(&snapshot);
+        assert_eq!(world.with_rng("dice", |rng| rng.next_u64()), Some(first));
+    }
+
+    #[test]
+    fn snapshots_reuse_components_that_were_only_read() {
+        fn inspect(query: Query) {
+            query.read_all::<Body>(|bodies| assert_eq!(bodies.len(), 1));
+        }
+
+        let mut world = Fixture::new()
+            .with_entity("ball", Prefab::new().with(Body::default()))
+            .with_fixed_system(System::new(type_ids![Body], inspect))
+            .build();
+        let ball = world.find_by_name("ball").unwrap();
+
+        let first = world.snapshot();
+        world.step_n(3).unwrap();
+        let second = world.snapshot();
+        assert_eq!(body_copy(&first, ball), body_copy(&second, ball));
+
+        world.add_fixed_system(System::new(type_ids![Body], movement));
+        world.step_n(1).unwrap();
+        let third = world.snapshot();
+        assert_ne!(body_copy(&second, ball), body_copy(&third, ball));
+    }
+
+    #[test]
+    fn snapshots_can_be_restored_on_other_threads() {
+        let mut world = rolling_ball();
+        let ball = world.find_by_name("ball").unwrap();
+        world.step_n(4).unwrap();
+        let snapshot = world.snapshot();
+
+        std::thread::scope(|scope| {
+            let branches = [1.0, 3.0].map(|speed| {
+                let snapshot = snapshot.clone();
+                scope.spawn(move || {
+                    let mut world = rolling_ball();
+                    world.restore(&snapshot);
+                    world.add_component(ball, Body { x: 4.0, speed });
+                    world.step_n(2).unwrap();
+                    world.with_component(ball, |body: &Body| body.x)
+                })
+            });
+
+            let results = branches.map(|branch| branch.join().unwrap());
+            assert_eq!(results, [Some(6.0), Some(10.0)]);
+        });
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{
+    resource::Resources,
+    system::{ScheduledSystem, SystemError, SystemInterface},
+    World,
+};
+use std::any::{Any, TypeId};
+
+/// The current state of some part of an application (e.g. whether it is loading, running, paused,
+/// or showing results), held by the world as a resource.
+///
+/// States are inserted with [`World::insert_state`]. Systems may be added that only run while the
+/// world is in a particular state ([`World::add_fixed_system_in`] and
+/// [`World::add_dependent_system_in`]) or once whenever it enters or leaves one
+/// ([`World::on_enter`] and [`World::on_exit`]), so that e.g. a pause menu and the simulation can
+/// live in the same world without every system checking a flag.
+///
+/// Transitions are requested with [`set`] (from a system through
+/// [`Query::resource`](crate::system::Query::resource())) or [`World::set_state`], and take
+/// place at the start of the next [`World::tick`]: the systems registered for leaving the old
+/// state run, then the systems registered for entering the new one, and only then the regular
+/// systems of the tick. Requesting the state the world is already in does nothing. The systems
+/// registered for entering the initial state run at the start of the first tick.
+///
+/// [`World::insert_state`]: crate::World::insert_state()
+/// [`World::add_fixed_system_in`]: crate::World::add_fixed_system_in()
+/// [`World::add_dependent_system_in`]: crate::World::add_dependent_system_in()
+/// [`World::on_enter`]: crate::World::on_enter()
+/// [`World::on_exit`]: crate::World::on_exit()
+/// [`World::set_state`]: crate::World::set_state()
+/// [`World::tick`]: crate::World::tick()
+/// [`set`]: Self::set()
+#[derive(Clone, Debug, PartialEq)]
+pub struct State<S> {
+    current: S,
+    next: Option<S>,
+    entered: bool,
+}
+
+impl<S> State<S> {
+    /// Returns the state the world is currently in.
+    pub fn get(&self) -> &S {
+        &self.current
+    }
+
+    /// Requests a transition to another state, replacing any transition requested earlier in the
+    /// same tick.
+    pub fn set(&mut self, next: S) {
+        self.next = Some(next);
+    }
+
+    /// Returns the state that will be entered at the start of the next tick, if any.
+    pub fn pending(&self) -> Option<&S> {
+        self.next.as_ref()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Transition {
+    Enter,
+    Exit,
+}
+
+/// Takes the pending transitions of a kind of state, returning the states left and entered.
+type Advance = fn(&Resources) -> Vec<(Transition, Box<dyn Any>)>;
+
+/// Decides whether a type-erased state is the one a system was registered for.
+type Matcher<'a> = Box<dyn Fn(&dyn Any) -> bool + 'a>;
+
+/// A system that runs when a state of a particular value is entered or left.
+struct StateSystem<'a> {
+    type_id: TypeId,
+    transition: Transition,
+    matches: Matcher<'a>,
+    system: ScheduledSystem<'a>,
+}
+
+/// Every kind of state known to a world along with the systems that run on its transitions.
+#[derive(Default)]
+pub(crate) struct StateMachines<'a> {
+    kinds: Vec<(TypeId, Advance)>,
+    systems: Vec<StateSystem<'a>>,
+}
+
+impl<'a> StateMachines<'a> {
+    /// Returns every system that runs on a transition.
+    pub(crate) fn systems(&self) -> impl Iterator<Item = &ScheduledSystem<'a>> {
+        self.systems.iter().map(|system| &system.system)
+    }
+}
+
+fn advance<S>(resources: &Resources) -> Vec<(Transition, Box<dyn Any>)>
+where
+    S: Any + Clone + PartialEq + Send + Sync,
+{
+    let Some(state) = resources.get::<State<S>>() else {
+        return Vec::new();
+    };
+    let mut state = state.lock().unwrap();
+    let State {
+        current,
+        next,
+        entered,
+    } = &mut *state;
+
+    let mut transitions = Vec::new();
+    if !*entered {
+        *entered = true;
+        transitions.push((Transition::Enter, Box::new(current.clone()) as Box<dyn Any>));
+    }
+
+    if let Some(next) = next.take() {
+        if next != *current {
+            let previous = std::mem::replace(current, next);
+            transitions.push((Transition::Exit, Box::new(previous)));
+            transitions.push((Transition::Enter, Box::new(current.clone())));
+        }
+    }
+
+    transitions
+}
+
+impl<'a> World<'a> {
+    /// Inserts a [`State`] resource that starts out in the given state, replacing any state of the
+    /// same type.
+    pub fn insert_state<S: Any + Clone + PartialEq + Send + Sync>(&mut self, initial: S) {
+        self.register_state::<S>();
+        self.insert_resource(State {
+            current: initial,
+            next: None,
+            entered: false,
+        });
+    }
+
+    /// Returns the state the world is currently in.
+    pub fn state<S: Any + Clone>(&self) -> Option<S> {
+        self.resource::<State<S>>()
+            .map(|state| state.lock().unwrap().current.clone())
+    }
+
+    /// Requests a transition to another state at the start of the next tick.
+    ///
+    /// Does nothing if the state has not been inserted with [`insert_state`].
+    ///
+    /// [`insert_state`]: Self::insert_state()
+    pub fn set_state<S: Any>(&mut self, next: S) {
+        if let Some(state) = self.resource::<State<S>>() {
+            state.lock().unwrap().set(next);
+        }
+    }
+
+    /// Registers a system that runs once whenever the world enters a particular state.
+    pub fn on_enter<S, T>(&mut self, state: S, system: T)
+    where
+        S: Any + Clone + PartialEq + Send + Sync,
+        T: SystemInterface + 'a,
+    {
+        self.add_state_system(Transition::Enter, state, system);
+    }
+
+    /// Registers a system that runs once whenever the world leaves a particular state.
+    pub fn on_exit<S, T>(&mut self, state: S, system: T)
+    where
+        S: Any + Clone + PartialEq + Send + Sync,
+        T: SystemInterface + 'a,
+    {
+        self.add_state_system(Transition::Exit, state, system);
+    }
+
+    /// Registers a fixed system that only runs while the world is in a particular state.
+    ///
+    /// See [`add_fixed_system`] for when fixed systems run.
+    ///
+    /// [`add_fixed_system`]: Self::add_fixed_system()
+    pub fn add_fixed_system_in<S, T>(&mut self, state: S, system: T)
+    where
+        S: Any + Clone + PartialEq + Send + Sync,
+        T: SystemInterface + 'a,
+    {
+        self.add_fixed_system(system);
+        let system = self.fixed_systems.pop().unwrap();
+        self.fixed_systems
+            .push(system.with_condition(move |resources| in_state(resources, &state)));
+    }
+
+    /// Registers a dependent system that only runs while the world is in a particular state.
+    ///
+    /// See [`add_dependent_system`] for when dependent systems run.
+    ///
+    /// [`add_dependent_system`]: Self::add_dependent_system()
+    pub fn add_dependent_system_in<S, T>(&mut self, state: S, system: T)
+    where
+        S: Any + Clone + PartialEq + Send + Sync,
+        T: SystemInterface + 'a,
+    {
+        self.add_dependent_system(system);
+        let system = self.dependent_systems.pop().unwrap();
+        self.dependent_systems
+            .push(system.with_condition(move |resources| in_state(resources, &state)));
+    }
+
+    fn add_state_system<S, T>(&mut self, transition: Transition, state: S, system: T)
+    where
+        S: Any + Clone + PartialEq + Send + Sync,
+        T: SystemInterface + 'a,
+    {
+        self.register_state::<S>();
+
+        let label = system.name().map(str::to_string).unwrap_or_else(|| {
+            let kind = match transition {
+                Transition::Enter => "enter",
+                Transition::Exit => "exit",
+            };
+            format!("{} system {}", kind, self.states.systems.len())
+        });
+        let stream = self.rng_stream(system.name());
+
+        self.states.systems.push(StateSystem {
+            type_id: TypeId::of::<S>(),
+            transition,
+            matches: Box::new(move |value| value.downcast_ref::<S>() == Some(&state)),
+            system: ScheduledSystem::new(Box::new(system), label, stream),
+        });
+    }
+
+    fn register_state<S: Any + Clone + PartialEq + Send + Sync>(&mut self) {
+        let type_id = TypeId::of::<S>();
+        if !self.states.kinds.iter().any(|(kind, _)| *kind == type_id) {
+            self.states.kinds.push((type_id, advance::<S>));
+        }
+    }
+
+    /// Carries out every requested transition, running the systems registered for leaving and
+    /// entering states.
+    pub(crate) fn apply_state_transitions(&mut self) -> Result<(), SystemError> {
+        for index in 0..self.states.kinds.len() {
+            let (type_id, advance) = self.states.kinds[index];
+
+            for (transition, state) in advance(&self.resources) {
+                for state_system in self.states.systems.iter_mut().filter(|s| {
+                    s.type_id == type_id && s.transition == transition && (s.matches)(&*state)
+                }) {
+                    let system = &mut state_system.system;
+                    if !system.should_run(&self.resources) {
+                        continue;
+                    }
+
+                    if let Err(error) = system.run(
+                        &mut self.components,
+                        &self.resources,
+                        &self.archetypes,
+                        &self.changes,
+                    ) {
+                        system.fail(error, self.error_policy)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the world is currently in a particular state.
+fn in_state<S: Any + PartialEq>(resources: &Resources, state: &S) -> bool {
+    resources
+        .get::<State<S>>()
+        .is_some_and(|current| current.lock().unwrap().current == *state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Query, System, World, WorldBuilder};
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Screen {
+        Menu,
+        Game,
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn log(query: &Query, entry: &'static str) {
+        query.resource::<Log>().unwrap().lock().unwrap().0.push(entry);
+    }
+
+    fn entries(world: &World) -> Vec<&'static str> {
+        world.resource::<Log>().unwrap().lock().unwrap().0.drain(..).collect()
+    }
+
+    #[test]
+    fn transitions_run_exit_then_enter_systems_before_the_tick() {
+        let mut world = WorldBuilder::new().build();
+        world.insert_resource(Log::default());
+        world.insert_state(Screen::Menu);
+        world.on_enter(Screen::Menu, System::new(vec![], |q| log(&q, "enter menu")));
+        world.on_exit(Screen::Menu, System::new(vec![], |q| log(&q, "exit menu")));
+        world.on_enter(Screen::Game, System::new(vec![], |q| log(&q, "enter game")));
+        world.add_fixed_system_in(Screen::Game, System::new(vec![], |q| log(&q, "play")));
+        world.add_fixed_system_in(Screen::Menu, System::new(vec![], |q| log(&q, "browse")));
+
+        world.step().unwrap();
+        assert_eq!(entries(&world), ["enter menu", "browse"]);
+
+        world.set_state(Screen::Game);
+        world.step().unwrap();
+        assert_eq!(entries(&world), ["exit menu", "enter game", "play"]);
+        assert_eq!(world.state::<Screen>(), Some(Screen::Game));
+
+        world.set_state(Screen::Game);
+        world.step().unwrap();
+        assert_eq!(entries(&world), ["play"]);
+    }
+}
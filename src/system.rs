@@ -1,8 +1,18 @@
-use crate::{component::Component, entity::Entity};
+use crate::{
+    component::{ArchetypeId, Archetypes, Component, StorageType},
+    entity::{Entity, Names, Tags},
+    relation::Relation,
+    resource::Resources,
+};
 use std::any::Any;
 use std::any::TypeId;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Every matched entity along with one of its components.
+type Matched<'q> = Box<dyn Iterator<Item = (Entity, &'q Arc<Mutex<dyn Any>>)> + 'q>;
 
 /// A query for entities containing specific components
 ///
@@ -12,52 +22,200 @@ use std::sync::{Arc, Mutex, MutexGuard};
 #[derive(Debug)]
 pub struct Query<'a> {
     matches: Vec<&'a mut Component<Arc<Mutex<dyn Any>>>>,
+    resources: &'a Resources,
+    matched: &'a HashMap<Entity, usize>,
+    /// The archetypes holding every table component the query operates on, if the query matches
+    /// whole archetypes rather than a single entity.
+    archetypes: Option<Vec<ArchetypeId>>,
+    /// The random stream of the system running the query, if it has a name.
+    stream: Option<&'a str>,
+    /// The component types the system was given write access to, which are marked as changed
+    /// once the query is dropped.
+    written: RefCell<HashSet<TypeId>>,
     /// A list of all the entities that match the filter
     pub entities: Vec<Entity>
 }
 
 impl<'a> Query<'a> {
     pub(crate) fn new(
-        haystack: &'a mut Vec<Component<Arc<Mutex<dyn Any>>>>,
-        filter: &HashSet<TypeId>,
+        haystack: &'a mut [Component<Arc<Mutex<dyn Any>>>],
+        cache: &'a QueryCache,
+        resources: &'a Resources,
+        stream: Option<&'a str>,
     ) -> Self {
-        // TODO: significantly increase frame rate by not performing this logic every frame
         let matches: Vec<_> = haystack
-                .iter_mut()
-                .filter(|e| filter.contains(&(*e).type_id))
-                .collect();
-            
-        let entities = matches.iter().flat_map(|e| e.data.keys().cloned()).fold(HashMap::new(), |mut map, val| {
-            *(map.entry(val).or_insert(0)) += 1;
-            map
-        }).into_iter().filter(|(_, value)| { *value == filter.len() }).map(|(key, _)| {key}).collect();
+            .iter_mut()
+            .filter(|e| cache.filter.contains(&e.type_id))
+            .collect();
 
+        // an archetype matches when every table the query operates on has a column for it, since
+        // columns only exist for archetypes holding their type
+        let archetypes = if cache.single || matches.len() != cache.filter.len() {
+            None
+        } else {
+            let tables = matches
+                .iter()
+                .filter_map(|e| e.data.as_table())
+                .collect::<Vec<_>>();
+            tables.split_first().map(|(first, rest)| {
+                first
+                    .archetypes()
+                    .filter(|archetype| rest.iter().all(|table| table.has_column(archetype)))
+                    .collect()
+            })
+        };
 
         Self {
             matches,
-            entities
+            resources,
+            matched: &cache.indices,
+            archetypes,
+            stream,
+            written: RefCell::new(HashSet::new()),
+            entities: cache.entities.clone(),
         }
     }
 
+    /// Records that the system may write to the components of type `T`.
+    fn write<T: 'static>(&self) {
+        self.written.borrow_mut().insert(TypeId::of::<T>());
+    }
+
+    fn store<T: 'static>(&self) -> Option<&Component<Arc<Mutex<dyn Any>>>> {
+        self.matches
+            .iter()
+            .find(|e| e.type_id == TypeId::of::<T>())
+            .map(|e| &**e)
+    }
+
+    /// Returns every matched entity along with its component of type `T`.
+    ///
+    /// Table components are read straight from the columns of the matched archetypes, checking
+    /// only the sparse components of each entity. Every value is still boxed on its own since
+    /// [`get`] and [`World::share_component`] hand out shared references to single components.
+    ///
+    /// [`get`]: Self::get()
+    /// [`World::share_component`]: crate::World::share_component()
+    fn matched<T: 'static>(&self) -> Matched<'_> {
+        let Some(store) = self.store::<T>() else {
+            return Box::new(std::iter::empty());
+        };
+
+        match (&self.archetypes, store.data.as_table()) {
+            (Some(archetypes), Some(table)) => {
+                let sparse = self
+                    .matches
+                    .iter()
+                    .filter(|e| e.data.storage_type() == StorageType::SparseSet)
+                    .map(|e| &**e)
+                    .collect::<Vec<_>>();
+
+                Box::new(
+                    archetypes
+                        .iter()
+                        .filter_map(|archetype| table.column(archetype))
+                        .flat_map(|(entities, values)| entities.iter().copied().zip(values))
+                        .filter(move |(entity, _)| {
+                            sparse.iter().all(|e| e.data.contains_key(entity))
+                        }),
+                )
+            }
+            _ => Box::new(
+                self.entities
+                    .iter()
+                    .filter_map(move |entity| Some((*entity, store.data.get(entity)?))),
+            ),
+        }
+    }
+
+    /// Returns the random stream of the system running the query, if it has a name.
+    pub(crate) fn stream(&self) -> Option<&str> {
+        self.stream
+    }
+
+    /// Returns a resource of a particular type if it has been inserted into the world.
+    ///
+    /// Unlike components, resources are returned already downcasted since there is only ever a
+    /// single resource of each type.
+    pub fn resource<T: 'static>(&self) -> Option<Arc<Mutex<T>>> {
+        self.resources.get::<T>()
+    }
+
+    /// Returns a non-send resource of a particular type if it has been inserted into the world.
+    ///
+    /// See [`World::insert_non_send_resource`].
+    ///
+    /// [`World::insert_non_send_resource`]: crate::World::insert_non_send_resource()
+    pub fn non_send_resource<T: 'static>(&self) -> Option<Arc<Mutex<T>>> {
+        self.resources.get_non_send::<T>()
+    }
+
+    /// Returns the entity with a particular [`Name`] regardless of whether it matches the query.
+    ///
+    /// [`Name`]: crate::entity::Name
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        self.resource::<Names>()
+            .and_then(|names| names.lock().unwrap().get(name))
+    }
+
+    /// Returns every entity holding a particular tag regardless of whether it matches the query.
+    pub fn tagged<T: 'static>(&self) -> Vec<Entity> {
+        self.resource::<Tags>()
+            .map(|tags| tags.lock().unwrap().get::<T>())
+            .unwrap_or_default()
+    }
+
+    /// Returns every matched entity holding a relation of kind `R` paired with its target.
+    ///
+    /// The targets themselves need not match the query; their components can be read with
+    /// [`get_target`] as long as the query operates on the component type.
+    ///
+    /// [`get_target`]: Self::get_target()
+    pub fn pairs<R: 'static>(&self) -> Vec<(Entity, Entity)> {
+        self.matched::<Relation<R>>()
+            .filter_map(|(entity, relation)| {
+                relation
+                    .lock()
+                    .unwrap()
+                    .downcast_ref::<Relation<R>>()
+                    .map(|relation| (entity, relation.target()))
+            })
+            .collect()
+    }
+
+    /// Returns a component on a particular entity even if the entity does not match the query.
+    ///
+    /// Intended for reading the targets of relations returned by [`pairs`]. The component type
+    /// must still be one the query operates on, and the same downcasting restrictions apply as
+    /// do on [`get`].
+    ///
+    /// [`pairs`]: Self::pairs()
+    /// [`get`]: Self::get()
+    pub fn get_target<T: 'static>(&self, ent: &Entity) -> Option<Arc<Mutex<dyn Any>>> {
+        self.write::<T>();
+        self.store::<T>()?.data.get(ent).cloned()
+    }
+
     /// Returns a component on a particular entity if that component exists
     ///
     /// Returns the data without downcasting it so as to prevent the MutexGuard from being
     /// destroyed when returned from the function, which means the return value will have to be
     /// manually downcasted upon retrieval in order to operate on it.
+    ///
+    /// Since the component can be written to through the returned [`Arc`], its type is marked as
+    /// changed; components that are only read should be accessed with [`read`] instead.
+    ///
+    /// [`read`]: Self::read()
     pub fn get<T: 'static>(&self, ent: &Entity) -> Option<Arc<Mutex<dyn Any>>> {
         // it is guaranteed that dyn Any is of type T, but it seems impossible to downcast the Mutex
         // without first turning it into a MutexGuard
         // TODO: fix this
-        // TODO: check to make sure there's only one of each type of component
-        match self
-            .matches
-            .iter()
-            .filter(|e| e.type_id == TypeId::of::<T>())
-            .next()
-            {
-                Some(component) => { if self.entities.contains(&ent) { component.data.get(ent).map(|v| v.clone())} else { None } },
-                None => None,
-            }
+        if self.matched.contains_key(ent) {
+            self.write::<T>();
+            self.store::<T>()?.data.get(ent).cloned()
+        } else {
+            None
+        }
     }
 
     /// Returns all entities and components of a certain type
@@ -67,28 +225,18 @@ impl<'a> Query<'a> {
     ///
     /// [`get`]: Self::get()
     pub fn get_all<T: 'static>(&self) -> HashMap<Entity, Arc<Mutex<dyn Any>>> {
-        self.matches
-            .iter()
-            .filter(|e| e.type_id == TypeId::of::<T>())
-            .flat_map(|e| {
-                e.data
-                    .iter()
-                    .map(|(k, v)| (k.clone(), v.clone()))
-                    .collect::<Vec<(Entity, Arc<Mutex<dyn Any>>)>>()
-            }).filter(|(entity, _)| self.entities.contains(entity))
-        .collect()
+        self.write::<T>();
+        self.matched::<T>()
+            .map(|(entity, data)| (entity, data.clone()))
+            .collect()
     }
 
     /// Applies a function on every entity of a specific component
     pub fn each<T: 'static>(&mut self, f: fn(&mut T)) {
-        self.matches
-            .iter_mut()
-            .filter(|e| e.type_id == TypeId::of::<T>())
-            .for_each(|e| {
-                e.data.iter_mut().filter(|(e, _)| {self.entities.contains(e)}).for_each(|(_, v)| {
-                    f(v.lock().unwrap().downcast_mut::<T>().unwrap());
-                });
-            });
+        self.write::<T>();
+        for (_, data) in self.matched::<T>() {
+            f(data.lock().unwrap().downcast_mut::<T>().unwrap());
+        }
     }
 
     /// Applies a function to all entities of a specific component
@@ -100,31 +248,403 @@ impl<'a> Query<'a> {
     ///
     /// [`get_all`]: Self::get_all()
     pub fn all<T: 'static>(&mut self, f: impl FnOnce(HashMap<Entity, &mut T>)) {
+        self.write::<T>();
         let mut data = self
-            .matches
-            .iter_mut()
-            .filter(|e| e.type_id == TypeId::of::<T>())
-            .flat_map(|e| {
-                e.data
-                    .iter().filter(|(e, _)| self.entities.contains(e))
-                    .map(|(k, v)| (k.clone(), v.lock().unwrap())) // TODO: can we directly deref
-                    .collect::<Vec<(Entity, MutexGuard<dyn Any>)>>()
-            })
-        .collect::<HashMap<Entity, MutexGuard<dyn Any>>>();
+            .matched::<T>()
+            .map(|(entity, data)| (entity, data.lock().unwrap())) // TODO: can we directly deref
+            .collect::<HashMap<Entity, MutexGuard<dyn Any>>>();
 
         let matches = data
             .iter_mut()
             .map(|(k, v)| {
                 (
-                    k.clone(),
+                    *k,
                     v.downcast_mut::<T>()
-                    .expect(&format!("couldn't downcast value for ent: {:?}", k)),
+                    .unwrap_or_else(|| panic!("couldn't downcast value for ent: {:?}", k)),
                 )
             })
         .collect::<HashMap<Entity, &mut T>>();
 
         f(matches);
     }
+
+    /// Runs a function on the component of type `T` held by a matched entity, returning its
+    /// result if the entity holds one.
+    ///
+    /// Unlike the other accessors, reading a component doesn't mark its type as changed, which
+    /// lets [`Snapshot`]s keep reusing the copies they made of it.
+    ///
+    /// [`Snapshot`]: crate::snapshot::Snapshot
+    pub fn read<T: 'static, R>(&self, ent: &Entity, f: impl FnOnce(&T) -> R) -> Option<R> {
+        if !self.matched.contains_key(ent) {
+            return None;
+        }
+
+        let data = self.store::<T>()?.data.get(ent)?.lock().unwrap();
+        data.downcast_ref::<T>().map(f)
+    }
+
+    /// Applies a function to every matched component of type `T` without writing to them.
+    ///
+    /// See [`read`] and [`all`].
+    ///
+    /// [`read`]: Self::read()
+    /// [`all`]: Self::all()
+    pub fn read_all<T: 'static>(&self, f: impl FnOnce(HashMap<Entity, &T>)) {
+        let data = self
+            .matched::<T>()
+            .map(|(entity, data)| (entity, data.lock().unwrap()))
+            .collect::<HashMap<Entity, MutexGuard<dyn Any>>>();
+
+        let matches = data
+            .iter()
+            .map(|(k, v)| {
+                (
+                    *k,
+                    v.downcast_ref::<T>()
+                        .unwrap_or_else(|| panic!("couldn't downcast value for ent: {:?}", k)),
+                )
+            })
+            .collect::<HashMap<Entity, &T>>();
+
+        f(matches);
+    }
+}
+
+impl Drop for Query<'_> {
+    fn drop(&mut self) {
+        let written = self.written.get_mut();
+        for component in self.matches.iter_mut() {
+            if written.contains(&component.type_id) {
+                component.touch();
+            }
+        }
+    }
+}
+
+/// The entities whose component sets have changed since the end of the last tick.
+///
+/// Positions in the log are counted from the creation of the world so that a [`QueryCache`] can
+/// tell which changes it has already applied. Changes that were cleared before a cache saw them
+/// force it to be rebuilt.
+#[derive(Debug)]
+pub(crate) struct Changes {
+    entities: Vec<Entity>,
+    start: u64,
+}
+
+impl Default for Changes {
+    fn default() -> Self {
+        // caches start at position zero, so they are all built from scratch the first time
+        Self {
+            entities: Vec::new(),
+            start: 1,
+        }
+    }
+}
+
+impl Changes {
+    /// Records that the components held by an entity have changed.
+    pub(crate) fn record(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+
+    /// Forgets every change once all systems have seen them.
+    pub(crate) fn clear(&mut self) {
+        self.start = self.end();
+        self.entities.clear();
+    }
+
+    fn end(&self) -> u64 {
+        self.start + self.entities.len() as u64
+    }
+}
+
+/// The entities matched by the query of a particular system, kept up to date between ticks.
+///
+/// Rather than searching every component store each time the system runs, the cache only looks
+/// at the entities whose component sets have changed since it was last updated, so the cost of
+/// running a system is proportional to the number of entities it matches.
+#[derive(Debug, Default)]
+pub(crate) struct QueryCache {
+    filter: HashSet<TypeId>,
+    /// Whether the cache only ever looks at a single entity rather than the whole world.
+    single: bool,
+    seen: u64,
+    entities: Vec<Entity>,
+    indices: HashMap<Entity, usize>,
+}
+
+impl QueryCache {
+    /// Creates a cache that matches at most a single entity (e.g. for observers).
+    ///
+    /// Unlike other caches, an empty filter matches the entity rather than nothing.
+    pub(crate) fn single(
+        filter: &HashSet<TypeId>,
+        entity: Entity,
+        components: &[Component<Arc<Mutex<dyn Any>>>],
+    ) -> Self {
+        let mut cache = Self {
+            filter: filter.clone(),
+            single: true,
+            ..Self::default()
+        };
+
+        if filter.is_empty() {
+            cache.indices.insert(entity, 0);
+            cache.entities.push(entity);
+        } else {
+            cache.refresh(entity, components);
+        }
+
+        cache
+    }
+
+    /// Applies every change made since the cache was last updated.
+    pub(crate) fn update(
+        &mut self,
+        filter: &HashSet<TypeId>,
+        components: &[Component<Arc<Mutex<dyn Any>>>],
+        archetypes: &Archetypes,
+        changes: &Changes,
+    ) {
+        if *filter != self.filter {
+            self.filter = filter.clone();
+            self.seen = 0;
+        }
+
+        if self.seen < changes.start {
+            self.rebuild(components, archetypes);
+        } else {
+            let unseen = (self.seen - changes.start) as usize;
+            for entity in &changes.entities[unseen..] {
+                self.refresh(*entity, components);
+            }
+        }
+
+        self.seen = changes.end();
+    }
+
+    fn rebuild(&mut self, components: &[Component<Arc<Mutex<dyn Any>>>], archetypes: &Archetypes) {
+        let matches: Vec<&Component<_>> = components
+            .iter()
+            .filter(|e| self.filter.contains(&e.type_id))
+            .collect();
+
+        let mut entities = Vec::new();
+        if !self.filter.is_empty() && matches.len() == self.filter.len() {
+            let (tables, sparse): (Vec<&Component<_>>, Vec<&Component<_>>) = matches
+                .into_iter()
+                .partition(|e| e.data.storage_type() == StorageType::Table);
+
+            // table components are found through the archetypes that hold all of them, leaving
+            // only sparse components to be checked entity by entity
+            entities = if tables.is_empty() {
+                sparse
+                    .iter()
+                    .min_by_key(|e| e.data.len())
+                    .map(|e| e.data.keys().collect())
+                    .unwrap_or_default()
+            } else {
+                archetypes.matching(&tables.iter().map(|e| e.type_id).collect::<Vec<_>>())
+            };
+            entities.retain(|entity| sparse.iter().all(|e| e.data.contains_key(entity)));
+        }
+
+        self.indices = entities.iter().enumerate().map(|(i, e)| (*e, i)).collect();
+        self.entities = entities;
+    }
+
+    /// Adds or removes a single entity depending on whether it still matches.
+    fn refresh(&mut self, entity: Entity, components: &[Component<Arc<Mutex<dyn Any>>>]) {
+        let matches = !self.filter.is_empty()
+            && self.filter.iter().all(|type_id| {
+                components
+                    .iter()
+                    .any(|c| c.type_id == *type_id && c.data.contains_key(&entity))
+            });
+
+        match (matches, self.indices.get(&entity).copied()) {
+            (true, None) => {
+                self.indices.insert(entity, self.entities.len());
+                self.entities.push(entity);
+            }
+            (false, Some(index)) => {
+                self.indices.remove(&entity);
+                self.entities.swap_remove(index);
+                if let Some(moved) = self.entities.get(index) {
+                    self.indices.insert(*moved, index);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Decides from the resources of the world whether a system should run.
+pub(crate) type Condition<'a> = Box<dyn Fn(&Resources) -> bool + 'a>;
+
+/// A system registered with the world along with the cached state of its query.
+pub(crate) struct ScheduledSystem<'a> {
+    pub(crate) system: Box<dyn SystemInterface + 'a>,
+    pub(crate) label: String,
+    pub(crate) failures: u32,
+    pub(crate) enabled: bool,
+    /// The time spent running the system since diagnostics were last recorded.
+    pub(crate) elapsed: Duration,
+    /// The number of times the system ran since diagnostics were last recorded.
+    pub(crate) runs: u32,
+    /// Decides whether the system runs in a particular tick (e.g. only in certain states).
+    pub(crate) condition: Option<Condition<'a>>,
+    /// The random stream of the system (see [`World::rng_stream`]).
+    ///
+    /// [`World::rng_stream`]: crate::World::rng_stream()
+    pub(crate) stream: Option<String>,
+    cache: QueryCache,
+}
+
+impl<'a> ScheduledSystem<'a> {
+    pub(crate) fn new(
+        system: Box<dyn SystemInterface + 'a>,
+        label: String,
+        stream: Option<String>,
+    ) -> Self {
+        Self {
+            system,
+            label,
+            stream,
+            failures: 0,
+            enabled: true,
+            elapsed: Duration::ZERO,
+            runs: 0,
+            condition: None,
+            cache: QueryCache::default(),
+        }
+    }
+
+    /// Only runs the system when a condition holds.
+    pub(crate) fn with_condition(mut self, condition: impl Fn(&Resources) -> bool + 'a) -> Self {
+        self.condition = Some(Box::new(condition));
+        self
+    }
+
+    /// Returns whether the system should run given the current resources of the world.
+    pub(crate) fn should_run(&self, resources: &Resources) -> bool {
+        self.enabled
+            && self
+                .condition
+                .as_ref()
+                .is_none_or(|condition| condition(resources))
+    }
+
+    /// Brings the cached query up to date and runs the system.
+    pub(crate) fn run(
+        &mut self,
+        components: &mut [Component<Arc<Mutex<dyn Any>>>],
+        resources: &Resources,
+        archetypes: &Archetypes,
+        changes: &Changes,
+    ) -> Result<(), SystemError> {
+        let _span = span!("system", name = %self.label);
+        let start = Instant::now();
+
+        self.cache
+            .update(self.system.components(), components, archetypes, changes);
+        let result = self
+            .system
+            .try_execute(Query::new(components, &self.cache, resources, self.stream.as_deref()));
+
+        self.elapsed += start.elapsed();
+        self.runs += 1;
+
+        result.map_err(|error| SystemError {
+            system: self.label.clone(),
+            error,
+        })
+    }
+
+    /// Records a failure of the system, returning the error if the world should stop.
+    pub(crate) fn fail(
+        &mut self,
+        error: SystemError,
+        policy: ErrorPolicy,
+    ) -> Result<(), SystemError> {
+        self.failures += 1;
+
+        match policy {
+            ErrorPolicy::Log => error!("{}", error),
+            ErrorPolicy::Disable(limit) => {
+                error!("{}", error);
+                if self.failures >= limit {
+                    error!("{} failed {} times; disabling it", self.label, self.failures);
+                    self.enabled = false;
+                }
+            }
+            ErrorPolicy::Stop => return Err(error),
+        }
+
+        Ok(())
+    }
+}
+
+/// An error returned by a system, boxed so that systems may fail with any error type.
+pub type BoxedError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The value returned by the function of a system.
+///
+/// Implemented for `()`, for systems that can't fail, and for [`Result<(), E>`], for systems that
+/// can. Errors returned by systems are handled according to the world's [`ErrorPolicy`].
+pub trait SystemResult {
+    /// Converts the value into the result of running the system.
+    fn into_result(self) -> Result<(), BoxedError>;
+}
+
+impl SystemResult for () {
+    fn into_result(self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+}
+
+impl<E: Into<BoxedError>> SystemResult for Result<(), E> {
+    fn into_result(self) -> Result<(), BoxedError> {
+        self.map_err(Into::into)
+    }
+}
+
+/// What the world does when a system returns an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// The error is logged and the system keeps running.
+    #[default]
+    Log,
+    /// The error is logged and the system is disabled once it has failed the given number of
+    /// times.
+    Disable(u32),
+    /// The tick is stopped immediately and the error is returned from [`World::tick`].
+    ///
+    /// [`World::tick`]: crate::World::tick()
+    Stop,
+}
+
+/// An error returned by a system while the world was ticking.
+#[derive(Debug)]
+pub struct SystemError {
+    /// Describes the system that failed.
+    pub system: String,
+    /// The error returned by the system.
+    pub error: BoxedError,
+}
+
+impl std::fmt::Display for SystemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed: {}", self.system, self.error)
+    }
+}
+
+impl std::error::Error for SystemError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.error)
+    }
 }
 
 /// Defines the interface for all systems
@@ -136,74 +656,291 @@ pub trait SystemInterface {
     fn execute(&mut self, query: Query);
     /// Returns the [`TypeId`]s of all components upon which the system operates.
     fn components(&self) -> &HashSet<TypeId>;
+    /// Called by the world when the system runs, returning any error the system produced.
+    ///
+    /// Defaults to calling [`execute`], which can't fail. Systems that can fail should override
+    /// this method so that their errors are handled according to the world's [`ErrorPolicy`].
+    ///
+    /// [`execute`]: Self::execute()
+    fn try_execute(&mut self, query: Query) -> Result<(), BoxedError> {
+        self.execute(query);
+        Ok(())
+    }
+    /// Returns the name the system is reported under (e.g. in [`Diagnostics`] or errors).
+    ///
+    /// Systems without a name are reported by their position in the schedule.
+    ///
+    /// [`Diagnostics`]: crate::diagnostics::Diagnostics
+    fn name(&self) -> Option<&str> {
+        None
+    }
 }
 
 /// The standard system.
 ///
 /// Stores the types of components it operates on and runs a function that depends on nothing
-/// external.
-pub struct System {
+/// external. The function may either return nothing or a [`Result`], in which case any error is
+/// handled according to the world's [`ErrorPolicy`].
+pub struct System<O = ()> {
     /// The components on which the system operates.
     pub components: HashSet<TypeId>,
     /// The function to execute when the system runs.
-    pub executable: fn(Query),
+    pub executable: fn(Query) -> O,
+    /// The name the system is reported under.
+    pub name: Option<String>,
+}
+
+/// Lists the [`TypeId`]s of some types, e.g. of the components a [`System`] operates on.
+///
+/// ```
+/// # use aspeng::{mesh::Instance, type_ids, Query, System};
+/// # struct Velocity;
+/// # fn movement(_: Query) {}
+/// let system = System::new(type_ids![Instance, Velocity], movement);
+/// ```
+#[macro_export]
+macro_rules! type_ids {
+    ($($ty:ty),* $(,)?) => {
+        vec![$(::std::any::TypeId::of::<$ty>()),*]
+    };
 }
 
-impl System {
+impl<O: SystemResult> System<O> {
     /// Creates a new system based on the [`TypeId`]s of the components on which it operates and a
     /// function pointer that  will be executed when the system is.
-    pub fn new(components: Vec<TypeId>, executable: fn(Query)) -> Self {
+    ///
+    /// The types are most easily listed with [`type_ids!`](crate::type_ids!).
+    pub fn new(components: Vec<TypeId>, executable: fn(Query) -> O) -> Self {
         Self {
             components: components.into_iter().collect(),
             executable,
+            name: None,
         }
     }
+
+    /// Names the system so that it can be told apart from others (e.g. in [`Diagnostics`]).
+    ///
+    /// [`Diagnostics`]: crate::diagnostics::Diagnostics
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
 }
 
-impl SystemInterface for System {
+impl<O: SystemResult> SystemInterface for System<O> {
     fn execute(&mut self, query: Query) {
-        (self.executable)(query)
+        if let Err(e) = self.try_execute(query) {
+            panic!("system failed: {}", e);
+        }
     }
 
     fn components(&self) -> &HashSet<TypeId> {
         &self.components
     }
+
+    fn try_execute(&mut self, query: Query) -> Result<(), BoxedError> {
+        (self.executable)(query).into_result()
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
 }
 
 /// Another basic system that pulls in an external resource.
 ///
 /// Stores data upon the creation of the system that is passed into the function that runs when the
 /// system executes. Used internally for systems that require access to the renderer to provide
-/// them access without creating global state.
-pub struct ResourcedSystem<T> {
+/// them access without creating global state. Like [`System`], the function may return a
+/// [`Result`].
+pub struct ResourcedSystem<T, O = ()> {
     /// The components on which the system operates.
     pub components: HashSet<TypeId>,
     /// The function to execute when the system runs. Takes in the query and an immutable reference
     /// to the resource.
-    pub executable: fn(Query, &T),
+    pub executable: fn(Query, &T) -> O,
     /// The resource that should be accessible when the system runs.
     pub resource: T,
+    /// The name the system is reported under.
+    pub name: Option<String>,
 }
 
-impl<T> ResourcedSystem<T> {
+impl<T, O: SystemResult> ResourcedSystem<T, O> {
     /// Creates a new system based on the [`TypeId`]s of the components on which it operates, a
     /// function pointer that  will be executed when the system is, and the resource that should be
     /// stored by the system.
-    pub fn new(components: Vec<TypeId>, resource: T, executable: fn(Query, &T)) -> Self {
+    pub fn new(components: Vec<TypeId>, resource: T, executable: fn(Query, &T) -> O) -> Self {
         Self {
             components: components.into_iter().collect(),
             resource,
             executable,
+            name: None,
         }
     }
+
+    /// Names the system so that it can be told apart from others (e.g. in [`Diagnostics`]).
+    ///
+    /// [`Diagnostics`]: crate::diagnostics::Diagnostics
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
 }
 
-impl<T> SystemInterface for ResourcedSystem<T> {
+impl<T, O: SystemResult> SystemInterface for ResourcedSystem<T, O> {
     fn execute(&mut self, query: Query) {
-        (self.executable)(query, &self.resource)
+        if let Err(e) = self.try_execute(query) {
+            panic!("system failed: {}", e);
+        }
     }
 
     fn components(&self) -> &HashSet<TypeId> {
         &self.components
     }
+
+    fn try_execute(&mut self, query: Query) -> Result<(), BoxedError> {
+        (self.executable)(query, &self.resource).into_result()
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorPolicy, QueryCache};
+    use crate::{Entity, Query, StorageType, System, World, WorldBuilder};
+
+    #[derive(Clone, Debug)]
+    struct Position;
+
+    #[derive(Clone, Debug, Default)]
+    struct Selected;
+
+    #[derive(Clone, Debug, Default)]
+    struct Matched(Vec<Entity>);
+
+    #[derive(Clone, Debug, Default)]
+    struct Runs(Vec<&'static str>);
+
+    fn log(query: &Query, name: &'static str) {
+        query.resource::<Runs>().unwrap().lock().unwrap().0.push(name);
+    }
+
+    fn failing(query: Query) -> Result<(), &'static str> {
+        log(&query, "failing");
+        Err("out of fuel")
+    }
+
+    fn succeeding(query: Query) {
+        log(&query, "succeeding");
+    }
+
+    /// Builds a world with a failing system followed by a succeeding one.
+    fn failing_world<'a>(policy: ErrorPolicy) -> World<'a> {
+        let mut world = WorldBuilder::new().with_error_policy(policy).build();
+        world.insert_resource(Runs::default());
+        let entity = world.new_entity();
+        world.add_component(entity, Position);
+        world.add_fixed_system(System::new(type_ids![Position], failing).with_name("failing"));
+        world.add_fixed_system(System::new(type_ids![Position], succeeding));
+        world
+    }
+
+    fn runs(world: &World) -> Vec<&'static str> {
+        world.resource::<Runs>().unwrap().lock().unwrap().0.drain(..).collect()
+    }
+
+    fn matched(query: Query) {
+        let mut entities = query.entities.clone();
+        entities.sort_by_key(Entity::id);
+        query.resource::<Matched>().unwrap().lock().unwrap().0 = entities;
+    }
+
+    #[test]
+    fn systems_see_component_changes_between_ticks() {
+        let mut world = WorldBuilder::new().build();
+        world.set_storage::<Selected>(StorageType::SparseSet);
+        world.insert_resource(Matched::default());
+        world.add_fixed_system(System::new(type_ids![Position, Selected], matched));
+
+        let entities = (0..3).map(|_| world.new_entity()).collect::<Vec<_>>();
+        for &entity in &entities {
+            world.add_component(entity, Position);
+        }
+        world.add_component(entities[1], Selected);
+        world.step_n(1).unwrap();
+        assert_eq!(world.resource::<Matched>().unwrap().lock().unwrap().0, [entities[1]]);
+
+        world.add_component(entities[0], Selected);
+        world.add_component(entities[2], Selected);
+        world.remove_component::<Position>(entities[1]);
+        world.step_n(1).unwrap();
+        assert_eq!(
+            world.resource::<Matched>().unwrap().lock().unwrap().0,
+            [entities[0], entities[2]]
+        );
+
+        world.despawn(entities[0]);
+        world.step_n(1).unwrap();
+        assert_eq!(world.resource::<Matched>().unwrap().lock().unwrap().0, [entities[2]]);
+    }
+
+    #[test]
+    fn caches_only_apply_changes_they_have_not_seen() {
+        let mut world = WorldBuilder::new().build();
+        let filter = type_ids![Position].into_iter().collect();
+        let mut cache = QueryCache::default();
+
+        let first = world.new_entity();
+        world.add_component(first, Position);
+        cache.update(&filter, &world.components, &world.archetypes, &world.changes);
+        assert_eq!(cache.entities, [first]);
+        let seen = cache.seen;
+
+        world.changes.clear();
+        let second = world.new_entity();
+        world.add_component(second, Position);
+        world.remove_component::<Position>(first);
+        cache.update(&filter, &world.components, &world.archetypes, &world.changes);
+        assert_eq!(cache.entities, [second]);
+        assert_eq!(cache.seen, seen + 2);
+
+        // changes that were cleared before the cache saw them force a rebuild
+        world.add_component(first, Position);
+        world.changes.clear();
+        world.changes.clear();
+        cache.update(&filter, &world.components, &world.archetypes, &world.changes);
+        let mut entities = cache.entities.clone();
+        entities.sort_by_key(Entity::id);
+        assert_eq!(entities, [first, second]);
+    }
+
+    #[test]
+    fn disabled_systems_stop_running_until_enabled() {
+        let mut world = failing_world(ErrorPolicy::Disable(2));
+
+        world.step_n(3).unwrap();
+        assert_eq!(
+            runs(&world),
+            ["failing", "succeeding", "failing", "succeeding", "succeeding"]
+        );
+
+        assert!(world.set_system_enabled("failing", true));
+        world.step_n(2).unwrap();
+        assert_eq!(runs(&world), ["failing", "succeeding", "failing", "succeeding"]);
+        world.step_n(1).unwrap();
+        assert_eq!(runs(&world), ["succeeding"]);
+    }
+
+    #[test]
+    fn stopping_ends_the_tick_with_the_error() {
+        let mut world = failing_world(ErrorPolicy::Stop);
+
+        let error = world.step().unwrap_err();
+        assert_eq!(error.system, "failing");
+        assert_eq!(error.to_string(), "failing failed: out of fuel");
+        assert_eq!(runs(&world), ["failing"]);
+    }
 }
@@ -0,0 +1,186 @@
+use crate::{command::WorldHandle, World};
+use futures::channel::oneshot;
+use futures::executor::ThreadPool;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+/// A pool of threads on which long-running work (e.g. file I/O, procedural generation, or heavy
+/// precomputation) can run without holding up the tick, held by the world as a resource.
+///
+/// Futures are run by a shared thread pool that is only started once the first one is spawned,
+/// while blocking work gets a thread of its own so that it can't starve the futures.
+///
+/// There are two ways of getting results back into the world:
+/// - [`spawn`] and [`spawn_blocking`] return a [`Task`] that can be polled in later ticks (e.g. by
+///   storing it as a component with [`World::add_component`] or in a resource).
+/// - [`spawn_and_apply`] and [`spawn_blocking_and_apply`] apply the result to the world through
+///   its [`WorldHandle`] at the start of the tick after the work finished.
+///
+/// ```
+/// # use aspeng::{task::TaskPool, Query};
+/// # #[derive(Clone)]
+/// # struct Terrain;
+/// # fn generate_terrain() -> Terrain {
+/// #     Terrain
+/// # }
+/// fn start_generating(query: Query) {
+///     let pool = query.resource::<TaskPool>().unwrap().lock().unwrap().clone();
+///     pool.spawn_blocking_and_apply(
+///         generate_terrain,
+///         |terrain, world| world.insert_resource(terrain),
+///     );
+/// }
+/// ```
+///
+/// [`spawn`]: Self::spawn()
+/// [`spawn_blocking`]: Self::spawn_blocking()
+/// [`spawn_and_apply`]: Self::spawn_and_apply()
+/// [`spawn_blocking_and_apply`]: Self::spawn_blocking_and_apply()
+/// [`World::add_component`]: crate::World::add_component()
+#[derive(Clone)]
+pub struct TaskPool {
+    pool: Arc<OnceLock<ThreadPool>>,
+    handle: WorldHandle,
+}
+
+impl std::fmt::Debug for TaskPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskPool")
+            .field("started", &self.pool.get().is_some())
+            .finish()
+    }
+}
+
+impl TaskPool {
+    /// Creates a task pool that applies results to the world behind a handle.
+    pub fn new(handle: WorldHandle) -> Self {
+        Self {
+            pool: Arc::new(OnceLock::new()),
+            handle,
+        }
+    }
+
+    fn pool(&self) -> &ThreadPool {
+        self.pool
+            .get_or_init(|| ThreadPool::new().expect("Couldn't create task pool"))
+    }
+
+    /// Runs a future on the pool, returning a handle to its result.
+    pub fn spawn<F>(&self, future: F) -> Task<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.pool().spawn_ok(async move {
+            let _ = sender.send(future.await);
+        });
+
+        Task::new(receiver)
+    }
+
+    /// Runs a blocking function on a thread of its own, returning a handle to its result.
+    pub fn spawn_blocking<F, T>(&self, f: F) -> Task<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::Builder::new()
+            .name("blocking task".to_string())
+            .spawn(move || {
+                let _ = sender.send(f());
+            })
+            .expect("Couldn't spawn blocking task");
+
+        Task::new(receiver)
+    }
+
+    /// Runs a future on the pool and applies its result to the world at the start of the tick
+    /// after it finished.
+    pub fn spawn_and_apply<F, A>(&self, future: F, apply: A)
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+        A: FnOnce(F::Output, &mut World<'_>) + Send + 'static,
+    {
+        let handle = self.handle.clone();
+        self.pool().spawn_ok(async move {
+            let output = future.await;
+            // the world may have been dropped in the meantime, in which case there is nothing to
+            // apply the result to
+            let _ = handle.run(move |world| apply(output, world));
+        });
+    }
+
+    /// Runs a blocking function on a thread of its own and applies its result to the world at the
+    /// start of the tick after it finished.
+    pub fn spawn_blocking_and_apply<F, T, A>(&self, f: F, apply: A)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+        A: FnOnce(T, &mut World<'_>) + Send + 'static,
+    {
+        let handle = self.handle.clone();
+        std::thread::Builder::new()
+            .name("blocking task".to_string())
+            .spawn(move || {
+                let output = f();
+                let _ = handle.run(move |world| apply(output, world));
+            })
+            .expect("Couldn't spawn blocking task");
+    }
+}
+
+/// A handle to the result of work spawned on a [`TaskPool`].
+///
+/// Dropping the handle doesn't stop the work; its result is simply discarded.
+#[derive(Debug)]
+pub struct Task<T> {
+    receiver: oneshot::Receiver<T>,
+    done: bool,
+    failed: bool,
+}
+
+impl<T> Task<T> {
+    fn new(receiver: oneshot::Receiver<T>) -> Self {
+        Self {
+            receiver,
+            done: false,
+            failed: false,
+        }
+    }
+
+    /// Returns the result of the task if it has finished since the last poll.
+    ///
+    /// The result is only returned once; polling again afterwards returns `None`.
+    pub fn poll(&mut self) -> Option<T> {
+        if self.done {
+            return None;
+        }
+
+        match self.receiver.try_recv() {
+            Ok(Some(output)) => {
+                self.done = true;
+                Some(output)
+            }
+            Ok(None) => None,
+            Err(_) => {
+                self.done = true;
+                self.failed = true;
+                None
+            }
+        }
+    }
+
+    /// Returns whether the result has been taken or the task has failed, i.e. whether polling it
+    /// is pointless.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns whether the task panicked before producing a result.
+    pub fn has_failed(&self) -> bool {
+        self.failed
+    }
+}
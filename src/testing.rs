@@ -0,0 +1,212 @@
+use crate::{
+    entity::Name, plugin::Plugin, prefab::Prefab, system::SystemError, system::SystemInterface,
+    World, WorldBuilder,
+};
+use std::any::Any;
+
+/// Builds a world set up for a unit test.
+///
+/// The world needs no window or GPU and is seeded with a fixed seed so that every run of a test
+/// draws the same random numbers. Entities are described with [`Prefab`]s and named so that the
+/// test can find them again with [`World::find_by_name`]. Once built, the world should be advanced
+/// with [`World::step_n`] rather than [`World::tick`], which depends on how much real time passed.
+///
+/// ```
+/// # use aspeng::{assert_component, testing::Fixture, type_ids, Prefab, Query, System};
+/// #[derive(Clone, Debug, Default)]
+/// struct Body {
+///     x: f32,
+///     speed: f32,
+/// }
+///
+/// fn movement(mut query: Query) {
+///     query.each(|body: &mut Body| body.x += body.speed);
+/// }
+///
+/// let mut world = Fixture::new()
+///     .with_entity("ball", Prefab::new().with(Body { x: 0.0, speed: 1.0 }))
+///     .with_fixed_system(System::new(type_ids![Body], movement))
+///     .build();
+/// let ball = world.find_by_name("ball").unwrap();
+///
+/// world.step_n(10).unwrap();
+/// assert_component!(world, ball, Body, |b| b.x == 10.0);
+/// ```
+///
+/// [`World::find_by_name`]: crate::World::find_by_name()
+/// [`World::step_n`]: crate::World::step_n()
+/// [`World::tick`]: crate::World::tick()
+pub struct Fixture<'a> {
+    world: World<'a>,
+}
+
+impl Default for Fixture<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Fixture<'a> {
+    /// Starts a fixture from an otherwise empty world with a fixed frequency of 60 Hz and a seed
+    /// of zero.
+    pub fn new() -> Self {
+        Self::from_builder(WorldBuilder::new().with_seed(0))
+    }
+
+    /// Starts a fixture from a configured builder (e.g. with registered types, plugins, or another
+    /// seed).
+    pub fn from_builder(builder: WorldBuilder) -> Self {
+        Self {
+            world: builder.build(),
+        }
+    }
+
+    /// Spawns an entity from a prefab under a [`Name`] it can be found by.
+    pub fn with_entity(mut self, name: &str, prefab: Prefab) -> Self {
+        self.world.spawn_prefab(&prefab.with(Name::new(name)));
+        self
+    }
+
+    /// Spawns an unnamed entity from a prefab.
+    pub fn with_prefab(mut self, prefab: Prefab) -> Self {
+        self.world.spawn_prefab(&prefab);
+        self
+    }
+
+    /// Inserts a resource, replacing any resource of the same type.
+    pub fn with_resource<T: Any + Clone + Send + Sync>(mut self, resource: T) -> Self {
+        self.world.insert_resource(resource);
+        self
+    }
+
+    /// Adds a fixed system, which runs once per step.
+    pub fn with_fixed_system<T: SystemInterface + 'a>(mut self, system: T) -> Self {
+        self.world.add_fixed_system(system);
+        self
+    }
+
+    /// Adds a dependent system, which runs once per step after the fixed systems.
+    pub fn with_dependent_system<T: SystemInterface + 'a>(mut self, system: T) -> Self {
+        self.world.add_dependent_system(system);
+        self
+    }
+
+    /// Builds a plugin into the world.
+    pub fn with_plugin<P: Plugin>(mut self, plugin: P) -> Self {
+        self.world.add_plugin(plugin);
+        self
+    }
+
+    /// Returns the world that was set up.
+    pub fn build(self) -> World<'a> {
+        self.world
+    }
+}
+
+impl World<'_> {
+    /// Runs exactly `n` fixed steps, stopping at the first error.
+    ///
+    /// See [`step`] for how a step differs from a tick.
+    ///
+    /// [`step`]: Self::step()
+    pub fn step_n(&mut self, n: u32) -> Result<(), SystemError> {
+        for _ in 0..n {
+            self.step()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks a component against a predicate for [`assert_component!`].
+#[doc(hidden)]
+pub fn satisfies<T, F: FnOnce(&T) -> bool>(component: &T, predicate: F) -> bool {
+    predicate(component)
+}
+
+/// Asserts that an entity holds a component, optionally checking it against a predicate.
+///
+/// Panics with the entity, the component, and the predicate if the entity doesn't hold the
+/// component or the component doesn't satisfy the predicate. The component must implement
+/// [`Debug`](std::fmt::Debug).
+///
+/// ```
+/// # use aspeng::{assert_component, testing::Fixture, Prefab};
+/// # #[derive(Clone, Debug)]
+/// # struct Velocity {
+/// #     x: f32,
+/// # }
+/// # let world = Fixture::new()
+/// #     .with_entity("ball", Prefab::new().with(Velocity { x: 1.0 }))
+/// #     .build();
+/// # let ball = world.find_by_name("ball").unwrap();
+/// assert_component!(world, ball, Velocity);
+/// assert_component!(world, ball, Velocity, |v| v.x > 0.0);
+/// ```
+#[macro_export]
+macro_rules! assert_component {
+    ($world:expr, $entity:expr, $ty:ty $(,)?) => {
+        $crate::assert_component!($world, $entity, $ty, |_| true)
+    };
+    ($world:expr, $entity:expr, $ty:ty, $predicate:expr $(,)?) => {{
+        let world = &$world;
+        let entity = $entity;
+        let checked = world.with_component(entity, |component: &$ty| {
+            if $crate::testing::satisfies(component, $predicate) {
+                None
+            } else {
+                Some(format!("{:?}", component))
+            }
+        });
+
+        match checked {
+            None => panic!(
+                "assertion failed: {} has no {}",
+                world.label(entity),
+                stringify!($ty),
+            ),
+            Some(Some(component)) => panic!(
+                "assertion failed: {} of {} doesn't satisfy `{}`\n  component: {}",
+                stringify!($ty),
+                world.label(entity),
+                stringify!($predicate),
+                component,
+            ),
+            Some(None) => {}
+        }
+    }};
+}
+
+/// Asserts that a world holds a resource, optionally checking it against a predicate.
+///
+/// Operates like [`assert_component!`](crate::assert_component!) but for resources.
+///
+/// ```
+/// # use aspeng::{assert_resource, testing::Fixture};
+/// # #[derive(Clone, Debug)]
+/// # struct Score(u32);
+/// # let world = Fixture::new().with_resource(Score(3)).build();
+/// assert_resource!(world, Score, |s| s.0 == 3);
+/// ```
+#[macro_export]
+macro_rules! assert_resource {
+    ($world:expr, $ty:ty $(,)?) => {
+        $crate::assert_resource!($world, $ty, |_| true)
+    };
+    ($world:expr, $ty:ty, $predicate:expr $(,)?) => {{
+        match $world.resource::<$ty>() {
+            None => panic!("assertion failed: the world has no {}", stringify!($ty)),
+            Some(resource) => {
+                let resource = resource.lock().unwrap();
+                if !$crate::testing::satisfies(&*resource, $predicate) {
+                    panic!(
+                        "assertion failed: {} doesn't satisfy `{}`\n  resource: {:?}",
+                        stringify!($ty),
+                        stringify!($predicate),
+                        *resource,
+                    );
+                }
+            }
+        }
+    }};
+}
@@ -1,6 +1,9 @@
+use crate::mesh::{res_path, LoadError};
+
 /// A WGPU texture representing the underlying data used by WGPU to actually attach the texture to
 /// an object.
 pub struct Texture {
+    #[allow(dead_code)]
     pub(crate) texture: wgpu::Texture,
     pub(crate) view: wgpu::TextureView,
     pub(crate) sampler: wgpu::Sampler
@@ -38,6 +41,7 @@ impl Texture {
     ///
     /// Should only be used internally or when creating a new renderer.
     pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        let _span = span!("create depth texture", width = config.width, height = config.height);
         let size = wgpu::Extent3d {
             width: config.width.max(1),
             height: config.height.max(1),
@@ -89,18 +93,34 @@ pub struct TextureBuilder {
 impl TextureBuilder {
     /// Generates a [`TextureBuilder`] from an image which is placed in the res folder in the build
     /// directory.
+    ///
+    /// Panics if the image can't be loaded; see [`try_from_image`] for a fallible alternative.
+    ///
+    /// [`try_from_image`]: Self::try_from_image()
     pub fn from_image(filename: &str) -> Self {
-        let diffuse_image = image::ImageReader::open(std::path::Path::new(env!("OUT_DIR")).join("res").join(filename)).unwrap().decode().unwrap();
+        Self::try_from_image(filename).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        Self {
+    /// Generates a [`TextureBuilder`] from an image in the res folder, returning an error if the
+    /// image is missing or can't be decoded.
+    pub fn try_from_image(filename: &str) -> Result<Self, LoadError> {
+        let _span = span!("load image", file = filename);
+        let path = res_path(filename);
+        let diffuse_image = image::ImageReader::open(&path)
+            .map_err(|e| LoadError::Io(path.clone(), e))?
+            .decode()
+            .map_err(|e| LoadError::Invalid(path, e.to_string()))?;
+
+        Ok(Self {
             image: diffuse_image.to_rgba8()
-        }
+        })
     }
 
     /// Builds the texture from the given configuration.
     ///
     /// Should only be used internally or when creating a new renderer.
     pub fn build(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Texture {
+        let _span = span!("create texture");
         let dimensions = self.image.dimensions();
 
         let texture_size = wgpu::Extent3d {
@@ -0,0 +1,57 @@
+//! Macros used to instrument the engine.
+//!
+//! With the `tracing` feature enabled, spans and messages are emitted through [`tracing`] so that
+//! they can be captured by any subscriber (e.g. a Chrome trace). Otherwise spans compile to
+//! nothing and messages are printed to standard error.
+
+/// Enters a span that lasts until the returned guard is dropped.
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($($arg:tt)*) => {
+        tracing::info_span!($($arg)*).entered()
+    };
+}
+
+/// Enters a span that lasts until the returned guard is dropped.
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($($arg:tt)*) => {
+        $crate::trace::NoSpan
+    };
+}
+
+/// Reports something the user should know about.
+#[cfg(feature = "tracing")]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        tracing::info!($($arg)*)
+    };
+}
+
+/// Reports something the user should know about.
+#[cfg(not(feature = "tracing"))]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        eprintln!($($arg)*)
+    };
+}
+
+/// Reports a failure that the engine recovered from.
+#[cfg(feature = "tracing")]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        tracing::error!($($arg)*)
+    };
+}
+
+/// Reports a failure that the engine recovered from.
+#[cfg(not(feature = "tracing"))]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        eprintln!($($arg)*)
+    };
+}
+
+/// Stands in for a span guard when the `tracing` feature is disabled.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;